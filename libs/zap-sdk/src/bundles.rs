@@ -0,0 +1,178 @@
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction};
+use damm_v2::state::Pool;
+use dlmm::accounts::LbPair;
+
+use crate::instructions::{
+    close_ledger_account, get_user_token_accounts, initialize_ledger_account, set_ledger_balance,
+    update_ledger_balance_after_swap, zap_in_damm_v2, zap_in_dlmm_for_initialized_position,
+    zap_in_dlmm_for_uninitialized_position, ZapInDammV2Accounts, ZapInDammV2Args,
+    ZapInDlmmAccounts, ZapInDlmmArgs,
+};
+
+// how ledger balance of one token is set before zap in
+pub enum LedgerBalance {
+    // exact amount, e.g. the part of input token that isn't swapped
+    Amount(u64),
+    // balance increase of the token account since pre_balance, capped at max_transfer_amount
+    AfterSwap {
+        token_account: Pubkey,
+        pre_balance: u64,
+        max_transfer_amount: u64,
+    },
+}
+
+impl LedgerBalance {
+    fn to_instruction(&self, owner: &Pubkey, is_token_a: bool) -> Instruction {
+        match self {
+            LedgerBalance::Amount(amount) => set_ledger_balance(owner, *amount, is_token_a),
+            LedgerBalance::AfterSwap {
+                token_account,
+                pre_balance,
+                max_transfer_amount,
+            } => update_ledger_balance_after_swap(
+                owner,
+                token_account,
+                *pre_balance,
+                *max_transfer_amount,
+                is_token_a,
+            ),
+        }
+    }
+}
+
+// pre instructions (swaps, withdraw...) -> initialize ledger -> set balances -> zap in -> close ledger,
+// owner pays for and receives rent of the ledger account
+pub fn build_zap_in_bundle(
+    owner: &Pubkey,
+    pre_instructions: Vec<Instruction>,
+    balance_a: LedgerBalance,
+    balance_b: LedgerBalance,
+    zap_in_instruction: Instruction,
+) -> Vec<Instruction> {
+    let mut instructions = pre_instructions;
+    instructions.push(initialize_ledger_account(owner, owner));
+    instructions.push(balance_a.to_instruction(owner, true));
+    instructions.push(balance_b.to_instruction(owner, false));
+    instructions.push(zap_in_instruction);
+    instructions.push(close_ledger_account(owner, owner));
+    instructions
+}
+
+// damm v2 example 1 and 2 in ZAPIN.md, user swaps through external amms before zap in
+pub fn build_zap_in_damm_v2_bundle(
+    accounts: &ZapInDammV2Accounts,
+    pool_state: &Pool,
+    swap_instructions: Vec<Instruction>,
+    balance_a: LedgerBalance,
+    balance_b: LedgerBalance,
+    args: ZapInDammV2Args,
+) -> Vec<Instruction> {
+    build_zap_in_bundle(
+        &accounts.owner,
+        swap_instructions,
+        balance_a,
+        balance_b,
+        zap_in_damm_v2(accounts, pool_state, args),
+    )
+}
+
+// damm v2 example 3 in ZAPIN.md, zap in swaps through the pool itself
+pub fn build_single_token_zap_in_damm_v2_bundle(
+    accounts: &ZapInDammV2Accounts,
+    pool_state: &Pool,
+    amount: u64,
+    is_token_a: bool,
+    args: ZapInDammV2Args,
+) -> Vec<Instruction> {
+    let (amount_a, amount_b) = if is_token_a { (amount, 0) } else { (0, amount) };
+    build_zap_in_damm_v2_bundle(
+        accounts,
+        pool_state,
+        vec![],
+        LedgerBalance::Amount(amount_a),
+        LedgerBalance::Amount(amount_b),
+        args,
+    )
+}
+
+// dlmm example 1 in ZAPIN.md, position is created in zap in if it's not initialized
+pub fn build_zap_in_dlmm_bundle(
+    accounts: ZapInDlmmAccounts,
+    lb_pair_state: &LbPair,
+    swap_instructions: Vec<Instruction>,
+    balance_x: LedgerBalance,
+    balance_y: LedgerBalance,
+    args: ZapInDlmmArgs,
+    is_position_initialized: bool,
+) -> Vec<Instruction> {
+    let owner = accounts.owner;
+    let zap_in_instruction = if is_position_initialized {
+        zap_in_dlmm_for_initialized_position(accounts, lb_pair_state, args)
+    } else {
+        zap_in_dlmm_for_uninitialized_position(accounts, lb_pair_state, args)
+    };
+    build_zap_in_bundle(
+        &owner,
+        swap_instructions,
+        balance_x,
+        balance_y,
+        zap_in_instruction,
+    )
+}
+
+// dlmm example 4 in ZAPIN.md, zap in swaps through the lb pair itself
+pub fn build_single_token_zap_in_dlmm_bundle(
+    accounts: ZapInDlmmAccounts,
+    lb_pair_state: &LbPair,
+    amount: u64,
+    is_token_x: bool,
+    args: ZapInDlmmArgs,
+    is_position_initialized: bool,
+) -> Vec<Instruction> {
+    let (amount_x, amount_y) = if is_token_x { (amount, 0) } else { (0, amount) };
+    build_zap_in_dlmm_bundle(
+        accounts,
+        lb_pair_state,
+        vec![],
+        LedgerBalance::Amount(amount_x),
+        LedgerBalance::Amount(amount_y),
+        ZapInDlmmArgs {
+            single_token_swap: true,
+            ..args
+        },
+        is_position_initialized,
+    )
+}
+
+// dlmm example 2 in ZAPIN.md, withdraw position then zap out part of it and add back with new balances,
+// pre balances are balances of owner token accounts of the lb pair before withdraw
+pub fn build_rebalance_dlmm_bundle(
+    accounts: ZapInDlmmAccounts,
+    lb_pair_state: &LbPair,
+    withdraw_instructions: Vec<Instruction>,
+    zap_out_instruction: Instruction,
+    pre_balance_x: u64,
+    pre_balance_y: u64,
+    args: ZapInDlmmArgs,
+) -> Vec<Instruction> {
+    let (user_token_x, user_token_y) = get_user_token_accounts(&accounts.owner, lb_pair_state);
+    let mut pre_instructions = withdraw_instructions;
+    pre_instructions.push(zap_out_instruction);
+    build_zap_in_dlmm_bundle(
+        accounts,
+        lb_pair_state,
+        pre_instructions,
+        LedgerBalance::AfterSwap {
+            token_account: user_token_x,
+            pre_balance: pre_balance_x,
+            max_transfer_amount: u64::MAX,
+        },
+        LedgerBalance::AfterSwap {
+            token_account: user_token_y,
+            pre_balance: pre_balance_y,
+            max_transfer_amount: u64::MAX,
+        },
+        args,
+        true,
+    )
+}
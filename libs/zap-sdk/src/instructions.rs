@@ -0,0 +1,707 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, sysvar},
+    system_program, InstructionData,
+};
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id, token, token_2022,
+};
+use damm_v2::state::Pool;
+use dlmm::{accounts::LbPair, types::RemainingAccountsInfo};
+use zap::{LeftoverPolicy, PositionLockMode, PriceRange, StrategyType, ZapOutParameters};
+
+use crate::pda::{
+    derive_bin_array_bitmap_extension, derive_damm_v2_event_authority,
+    derive_damm_v2_pool_authority, derive_dlmm_event_authority, derive_ledger_account,
+    derive_oracle,
+};
+
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+// token program flag in damm v2 pool and dlmm lb pair, 0 is spl token, 1 is token 2022
+pub fn get_token_program(flag: u8) -> Pubkey {
+    if flag == 0 {
+        token::ID
+    } else {
+        token_2022::ID
+    }
+}
+
+fn build_instruction(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+    remaining_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut account_metas = accounts.to_account_metas(None);
+    account_metas.extend(remaining_accounts);
+    Instruction {
+        program_id: zap::ID,
+        accounts: account_metas,
+        data: data.data(),
+    }
+}
+
+////// LEDGER //////
+
+pub fn initialize_ledger_account(owner: &Pubkey, payer: &Pubkey) -> Instruction {
+    build_instruction(
+        zap::accounts::InitializeLedgerAccountCtx {
+            ledger: derive_ledger_account(owner),
+            owner: *owner,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        zap::instruction::InitializeLedgerAccount {},
+        vec![],
+    )
+}
+
+pub fn close_ledger_account(owner: &Pubkey, rent_receiver: &Pubkey) -> Instruction {
+    build_instruction(
+        zap::accounts::CloseLedgerAccountCtx {
+            ledger: derive_ledger_account(owner),
+            owner: *owner,
+            rent_receiver: *rent_receiver,
+        },
+        zap::instruction::CloseLedgerAccount {},
+        vec![],
+    )
+}
+
+pub fn set_ledger_balance(owner: &Pubkey, amount: u64, is_token_a: bool) -> Instruction {
+    build_instruction(
+        zap::accounts::SetLedgerBalanceCtx {
+            ledger: derive_ledger_account(owner),
+            owner: *owner,
+        },
+        zap::instruction::SetLedgerBalance { amount, is_token_a },
+        vec![],
+    )
+}
+
+pub fn update_ledger_balance_after_swap(
+    owner: &Pubkey,
+    token_account: &Pubkey,
+    pre_source_token_balance: u64,
+    max_transfer_amount: u64,
+    is_token_a: bool,
+) -> Instruction {
+    build_instruction(
+        zap::accounts::UpdateLedgerBalanceAfterSwapCtx {
+            ledger: derive_ledger_account(owner),
+            token_account: *token_account,
+            owner: *owner,
+        },
+        zap::instruction::UpdateLedgerBalanceAfterSwap {
+            pre_source_token_balance,
+            max_transfer_amount,
+            is_token_a,
+        },
+        vec![],
+    )
+}
+
+////// ZAP OUT //////
+
+// amm_instruction is the swap instruction of a whitelisted amm, its amount in at offset_amount_in
+// is replaced with the balance change of user_token_in_account
+pub fn zap_out(
+    user_token_in_account: &Pubkey,
+    amm_instruction: Instruction,
+    percentage: u8,
+    offset_amount_in: u16,
+    pre_user_token_balance: u64,
+    max_swap_amount: u64,
+) -> Instruction {
+    build_instruction(
+        zap::accounts::ZapOutCtx {
+            user_token_in_account: *user_token_in_account,
+            amm_program: amm_instruction.program_id,
+        },
+        zap::instruction::ZapOut {
+            params: ZapOutParameters {
+                percentage,
+                offset_amount_in,
+                pre_user_token_balance,
+                max_swap_amount,
+                payload_data: amm_instruction.data,
+            },
+        },
+        amm_instruction.accounts,
+    )
+}
+
+////// DAMM V2 //////
+
+pub struct ZapInDammV2Accounts {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub position_nft_account: Pubkey,
+    pub referral_token_account: Option<Pubkey>,
+    // new keypair, required for vesting lock mode, owner pays for the vesting account
+    pub vesting: Option<Pubkey>,
+    // required for transfer leftover policy
+    pub leftover_token_a_account: Option<Pubkey>,
+    pub leftover_token_b_account: Option<Pubkey>,
+}
+
+pub struct ZapInDammV2Args {
+    pub pre_sqrt_price: u128,
+    pub max_sqrt_price_change_bps: u32,
+    pub min_liquidity_delta: u128,
+    pub token_a_amount_threshold: u64,
+    pub token_b_amount_threshold: u64,
+    pub lock_mode: PositionLockMode,
+    pub max_rounds: u8,
+    pub dust_threshold: u64,
+    pub leftover_policy: LeftoverPolicy,
+}
+
+impl ZapInDammV2Args {
+    // same behavior as zap in before lock mode, rounds and leftover policy are added
+    pub fn new(pre_sqrt_price: u128, max_sqrt_price_change_bps: u32) -> Self {
+        Self {
+            pre_sqrt_price,
+            max_sqrt_price_change_bps,
+            min_liquidity_delta: 0,
+            token_a_amount_threshold: u64::MAX,
+            token_b_amount_threshold: u64::MAX,
+            lock_mode: PositionLockMode::Unlocked,
+            max_rounds: 1,
+            dust_threshold: 0,
+            leftover_policy: LeftoverPolicy::Keep,
+        }
+    }
+}
+
+pub fn zap_in_damm_v2(
+    accounts: &ZapInDammV2Accounts,
+    pool_state: &Pool,
+    args: ZapInDammV2Args,
+) -> Instruction {
+    let token_a_program = get_token_program(pool_state.token_a_flag);
+    let token_b_program = get_token_program(pool_state.token_b_flag);
+    build_instruction(
+        zap::accounts::ZapInDammv2Ctx {
+            ledger: derive_ledger_account(&accounts.owner),
+            pool: accounts.pool,
+            pool_authority: derive_damm_v2_pool_authority(),
+            position: accounts.position,
+            token_a_account: get_associated_token_address_with_program_id(
+                &accounts.owner,
+                &pool_state.token_a_mint,
+                &token_a_program,
+            ),
+            token_b_account: get_associated_token_address_with_program_id(
+                &accounts.owner,
+                &pool_state.token_b_mint,
+                &token_b_program,
+            ),
+            token_a_vault: pool_state.token_a_vault,
+            token_b_vault: pool_state.token_b_vault,
+            token_a_mint: pool_state.token_a_mint,
+            token_b_mint: pool_state.token_b_mint,
+            position_nft_account: accounts.position_nft_account,
+            owner: accounts.owner,
+            token_a_program,
+            token_b_program,
+            damm_program: damm_v2::ID,
+            damm_event_authority: derive_damm_v2_event_authority(),
+            referral_token_account: accounts.referral_token_account,
+            vesting: accounts.vesting,
+            payer: accounts.vesting.map(|_| accounts.owner),
+            system_program: accounts.vesting.map(|_| system_program::ID),
+            leftover_token_a_account: accounts.leftover_token_a_account,
+            leftover_token_b_account: accounts.leftover_token_b_account,
+        },
+        zap::instruction::ZapInDammV2 {
+            pre_sqrt_price: args.pre_sqrt_price,
+            max_sqrt_price_change_bps: args.max_sqrt_price_change_bps,
+            min_liquidity_delta: args.min_liquidity_delta,
+            token_a_amount_threshold: args.token_a_amount_threshold,
+            token_b_amount_threshold: args.token_b_amount_threshold,
+            lock_mode: args.lock_mode,
+            max_rounds: args.max_rounds,
+            dust_threshold: args.dust_threshold,
+            leftover_policy: args.leftover_policy,
+        },
+        // damm v2 swap reads instructions sysvar for rate limiter pools
+        vec![AccountMeta::new_readonly(sysvar::instructions::ID, false)],
+    )
+}
+
+pub struct CompoundDammV2PositionAccounts {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub position_nft_account: Pubkey,
+    pub referral_token_account: Option<Pubkey>,
+}
+
+pub struct CompoundDammV2PositionArgs {
+    pub pre_sqrt_price: u128,
+    pub max_sqrt_price_change_bps: u32,
+    pub min_liquidity_delta: u128,
+    pub max_rounds: u8,
+    // fee worth less than this in token b is left in owner token accounts
+    pub dust_threshold: u64,
+}
+
+pub fn compound_damm_v2_position(
+    accounts: &CompoundDammV2PositionAccounts,
+    pool_state: &Pool,
+    args: CompoundDammV2PositionArgs,
+) -> Instruction {
+    let token_a_program = get_token_program(pool_state.token_a_flag);
+    let token_b_program = get_token_program(pool_state.token_b_flag);
+    build_instruction(
+        zap::accounts::CompoundDammV2PositionCtx {
+            pool: accounts.pool,
+            pool_authority: derive_damm_v2_pool_authority(),
+            position: accounts.position,
+            token_a_account: get_associated_token_address_with_program_id(
+                &accounts.owner,
+                &pool_state.token_a_mint,
+                &token_a_program,
+            ),
+            token_b_account: get_associated_token_address_with_program_id(
+                &accounts.owner,
+                &pool_state.token_b_mint,
+                &token_b_program,
+            ),
+            token_a_vault: pool_state.token_a_vault,
+            token_b_vault: pool_state.token_b_vault,
+            token_a_mint: pool_state.token_a_mint,
+            token_b_mint: pool_state.token_b_mint,
+            position_nft_account: accounts.position_nft_account,
+            owner: accounts.owner,
+            token_a_program,
+            token_b_program,
+            damm_program: damm_v2::ID,
+            damm_event_authority: derive_damm_v2_event_authority(),
+            referral_token_account: accounts.referral_token_account,
+        },
+        zap::instruction::CompoundDammV2Position {
+            pre_sqrt_price: args.pre_sqrt_price,
+            max_sqrt_price_change_bps: args.max_sqrt_price_change_bps,
+            min_liquidity_delta: args.min_liquidity_delta,
+            max_rounds: args.max_rounds,
+            dust_threshold: args.dust_threshold,
+        },
+        // damm v2 swap reads instructions sysvar for rate limiter pools
+        vec![AccountMeta::new_readonly(sysvar::instructions::ID, false)],
+    )
+}
+
+pub fn quote_zap_in_damm_v2(
+    pool: &Pubkey,
+    pool_state: &Pool,
+    amount_a: u64,
+    amount_b: u64,
+    has_referral: bool,
+    max_rounds: u8,
+    dust_threshold: u64,
+) -> Instruction {
+    build_instruction(
+        zap::accounts::QuoteZapInDammv2Ctx {
+            pool: *pool,
+            token_a_mint: pool_state.token_a_mint,
+            token_b_mint: pool_state.token_b_mint,
+        },
+        zap::instruction::QuoteZapInDammV2 {
+            amount_a,
+            amount_b,
+            has_referral,
+            max_rounds,
+            dust_threshold,
+        },
+        vec![],
+    )
+}
+
+////// DLMM //////
+
+pub struct ZapInDlmmAccounts {
+    pub owner: Pubkey,
+    pub lb_pair: Pubkey,
+    pub position: Pubkey,
+    pub rent_payer: Pubkey,
+    pub bin_array_bitmap_extension_required: bool,
+    // required for transfer leftover policy
+    pub leftover_token_x_account: Option<Pubkey>,
+    pub leftover_token_y_account: Option<Pubkey>,
+    // bin arrays covering the position range (missing ones are initialized by the program), followed by transfer hook accounts if any
+    pub remaining_accounts: Vec<AccountMeta>,
+}
+
+pub struct ZapInDlmmArgs {
+    pub active_id: i32,
+    pub min_delta_id: i32,
+    pub max_delta_id: i32,
+    pub max_active_bin_slippage: u16,
+    pub favor_x_in_active_id: bool,
+    pub strategy: StrategyType,
+    pub remaining_accounts_info: RemainingAccountsInfo,
+    pub leftover_policy: LeftoverPolicy,
+    // swap the only funded side to the ratio of strategy, bin arrays of the swap path must be in remaining accounts
+    pub single_token_swap: bool,
+    // claim fee and reward before deposit, only for initialized position
+    pub should_claim_fee: bool,
+    pub should_claim_reward: bool,
+    // deposit claimed amounts of token x and token y
+    pub compound: bool,
+    // dlmm shrink mode of empty bins after deposit
+    pub shrink_mode: u8,
+    // bins of price range replace min_delta_id and max_delta_id
+    pub price_range: Option<PriceRange>,
+    // max deviation of the active bin price from the price of active_id, in bps, None skips the check
+    pub max_price_deviation_bps: Option<u16>,
+}
+
+// owner associated token accounts of token x and token y
+pub fn get_user_token_accounts(owner: &Pubkey, lb_pair_state: &LbPair) -> (Pubkey, Pubkey) {
+    (
+        get_associated_token_address_with_program_id(
+            owner,
+            &lb_pair_state.token_x_mint,
+            &get_token_program(lb_pair_state.token_mint_x_program_flag),
+        ),
+        get_associated_token_address_with_program_id(
+            owner,
+            &lb_pair_state.token_y_mint,
+            &get_token_program(lb_pair_state.token_mint_y_program_flag),
+        ),
+    )
+}
+
+// oracle is needed when zap in swaps through the lb pair
+fn is_dlmm_swap_required(args: &ZapInDlmmArgs) -> bool {
+    args.single_token_swap
+        || matches!(
+            args.leftover_policy,
+            LeftoverPolicy::SwapToTokenA | LeftoverPolicy::SwapToTokenB
+        )
+}
+
+pub fn zap_in_dlmm_for_initialized_position(
+    accounts: ZapInDlmmAccounts,
+    lb_pair_state: &LbPair,
+    args: ZapInDlmmArgs,
+) -> Instruction {
+    let token_x_program = get_token_program(lb_pair_state.token_mint_x_program_flag);
+    let token_y_program = get_token_program(lb_pair_state.token_mint_y_program_flag);
+    let (user_token_x, user_token_y) = get_user_token_accounts(&accounts.owner, lb_pair_state);
+    build_instruction(
+        zap::accounts::ZapInDlmmForInitializedPositionCtx {
+            ledger: derive_ledger_account(&accounts.owner),
+            lb_pair: accounts.lb_pair,
+            position: accounts.position,
+            bin_array_bitmap_extension: accounts
+                .bin_array_bitmap_extension_required
+                .then(|| derive_bin_array_bitmap_extension(&accounts.lb_pair)),
+            user_token_x,
+            user_token_y,
+            reserve_x: lb_pair_state.reserve_x,
+            reserve_y: lb_pair_state.reserve_y,
+            token_x_mint: lb_pair_state.token_x_mint,
+            token_y_mint: lb_pair_state.token_y_mint,
+            dlmm_program: dlmm::ID,
+            owner: accounts.owner,
+            rent_payer: accounts.rent_payer,
+            token_x_program,
+            token_y_program,
+            memo_program: MEMO_PROGRAM_ID,
+            system_program: system_program::ID,
+            dlmm_event_authority: derive_dlmm_event_authority(),
+            oracle: is_dlmm_swap_required(&args).then(|| derive_oracle(&accounts.lb_pair)),
+            leftover_token_x_account: accounts.leftover_token_x_account,
+            leftover_token_y_account: accounts.leftover_token_y_account,
+            // bitmap extension is initialized if missing, which needs rent sysvar
+            rent: accounts
+                .bin_array_bitmap_extension_required
+                .then_some(sysvar::rent::ID),
+        },
+        zap::instruction::ZapInDlmmForInitializedPosition {
+            active_id: args.active_id,
+            min_delta_id: args.min_delta_id,
+            max_delta_id: args.max_delta_id,
+            max_active_bin_slippage: args.max_active_bin_slippage,
+            favor_x_in_active_id: args.favor_x_in_active_id,
+            strategy: args.strategy,
+            remaining_accounts_info: args.remaining_accounts_info,
+            leftover_policy: args.leftover_policy,
+            single_token_swap: args.single_token_swap,
+            should_claim_fee: args.should_claim_fee,
+            should_claim_reward: args.should_claim_reward,
+            compound: args.compound,
+            shrink_mode: args.shrink_mode,
+            price_range: args.price_range,
+            max_price_deviation_bps: args.max_price_deviation_bps,
+        },
+        accounts.remaining_accounts,
+    )
+}
+
+pub struct ZapInDlmmForMultiplePositionsAccounts {
+    pub owner: Pubkey,
+    pub lb_pair: Pubkey,
+    // initialized positions of adjacent ranges in ascending order, covering the zap in range
+    pub positions: Vec<Pubkey>,
+    pub rent_payer: Pubkey,
+    pub bin_array_bitmap_extension_required: bool,
+    // required for transfer leftover policy
+    pub leftover_token_x_account: Option<Pubkey>,
+    pub leftover_token_y_account: Option<Pubkey>,
+    // bin arrays covering all position ranges, followed by transfer hook accounts if any
+    pub remaining_accounts: Vec<AccountMeta>,
+}
+
+pub struct ZapInDlmmForMultiplePositionsArgs {
+    pub active_id: i32,
+    pub min_delta_id: i32,
+    pub max_delta_id: i32,
+    pub max_active_bin_slippage: u16,
+    pub favor_x_in_active_id: bool,
+    pub strategy: StrategyType,
+    pub remaining_accounts_info: RemainingAccountsInfo,
+    // max deviation of the active bin price from the price of active_id, in bps, None skips the check
+    pub max_price_deviation_bps: Option<u16>,
+    pub leftover_policy: LeftoverPolicy,
+}
+
+pub fn zap_in_dlmm_for_multiple_positions(
+    accounts: ZapInDlmmForMultiplePositionsAccounts,
+    lb_pair_state: &LbPair,
+    args: ZapInDlmmForMultiplePositionsArgs,
+) -> Instruction {
+    let token_x_program = get_token_program(lb_pair_state.token_mint_x_program_flag);
+    let token_y_program = get_token_program(lb_pair_state.token_mint_y_program_flag);
+    let (user_token_x, user_token_y) = get_user_token_accounts(&accounts.owner, lb_pair_state);
+    let position_count =
+        u8::try_from(accounts.positions.len()).expect("at most 255 positions in one instruction");
+    // positions go before other remaining accounts
+    let remaining_accounts = accounts
+        .positions
+        .iter()
+        .map(|position| AccountMeta::new(*position, false))
+        .chain(accounts.remaining_accounts)
+        .collect();
+    build_instruction(
+        zap::accounts::ZapInDlmmForMultiplePositionsCtx {
+            ledger: derive_ledger_account(&accounts.owner),
+            lb_pair: accounts.lb_pair,
+            bin_array_bitmap_extension: accounts
+                .bin_array_bitmap_extension_required
+                .then(|| derive_bin_array_bitmap_extension(&accounts.lb_pair)),
+            user_token_x,
+            user_token_y,
+            reserve_x: lb_pair_state.reserve_x,
+            reserve_y: lb_pair_state.reserve_y,
+            token_x_mint: lb_pair_state.token_x_mint,
+            token_y_mint: lb_pair_state.token_y_mint,
+            dlmm_program: dlmm::ID,
+            owner: accounts.owner,
+            rent_payer: accounts.rent_payer,
+            token_x_program,
+            token_y_program,
+            memo_program: MEMO_PROGRAM_ID,
+            system_program: system_program::ID,
+            dlmm_event_authority: derive_dlmm_event_authority(),
+            oracle: matches!(
+                args.leftover_policy,
+                LeftoverPolicy::SwapToTokenA | LeftoverPolicy::SwapToTokenB
+            )
+            .then(|| derive_oracle(&accounts.lb_pair)),
+            leftover_token_x_account: accounts.leftover_token_x_account,
+            leftover_token_y_account: accounts.leftover_token_y_account,
+            // bitmap extension is initialized if missing, which needs rent sysvar
+            rent: accounts
+                .bin_array_bitmap_extension_required
+                .then_some(sysvar::rent::ID),
+        },
+        zap::instruction::ZapInDlmmForMultiplePositions {
+            active_id: args.active_id,
+            min_delta_id: args.min_delta_id,
+            max_delta_id: args.max_delta_id,
+            max_active_bin_slippage: args.max_active_bin_slippage,
+            favor_x_in_active_id: args.favor_x_in_active_id,
+            strategy: args.strategy,
+            remaining_accounts_info: args.remaining_accounts_info,
+            position_count,
+            max_price_deviation_bps: args.max_price_deviation_bps,
+            leftover_policy: args.leftover_policy,
+        },
+        remaining_accounts,
+    )
+}
+
+// position is a new keypair, it must sign the transaction
+pub fn zap_in_dlmm_for_uninitialized_position(
+    accounts: ZapInDlmmAccounts,
+    lb_pair_state: &LbPair,
+    args: ZapInDlmmArgs,
+) -> Instruction {
+    let token_x_program = get_token_program(lb_pair_state.token_mint_x_program_flag);
+    let token_y_program = get_token_program(lb_pair_state.token_mint_y_program_flag);
+    let (user_token_x, user_token_y) = get_user_token_accounts(&accounts.owner, lb_pair_state);
+    build_instruction(
+        zap::accounts::ZapInDlmmForUnintializedPositionCtx {
+            ledger: derive_ledger_account(&accounts.owner),
+            lb_pair: accounts.lb_pair,
+            position: accounts.position,
+            bin_array_bitmap_extension: accounts
+                .bin_array_bitmap_extension_required
+                .then(|| derive_bin_array_bitmap_extension(&accounts.lb_pair)),
+            user_token_x,
+            user_token_y,
+            reserve_x: lb_pair_state.reserve_x,
+            reserve_y: lb_pair_state.reserve_y,
+            token_x_mint: lb_pair_state.token_x_mint,
+            token_y_mint: lb_pair_state.token_y_mint,
+            dlmm_program: dlmm::ID,
+            owner: accounts.owner,
+            rent_payer: accounts.rent_payer,
+            token_x_program,
+            token_y_program,
+            memo_program: MEMO_PROGRAM_ID,
+            system_program: system_program::ID,
+            dlmm_event_authority: derive_dlmm_event_authority(),
+            oracle: is_dlmm_swap_required(&args).then(|| derive_oracle(&accounts.lb_pair)),
+            leftover_token_x_account: accounts.leftover_token_x_account,
+            leftover_token_y_account: accounts.leftover_token_y_account,
+            // bitmap extension is initialized if missing, which needs rent sysvar
+            rent: accounts
+                .bin_array_bitmap_extension_required
+                .then_some(sysvar::rent::ID),
+        },
+        zap::instruction::ZapInDlmmForUninitializedPosition {
+            min_delta_id: args.min_delta_id,
+            max_delta_id: args.max_delta_id,
+            active_id: args.active_id,
+            max_active_bin_slippage: args.max_active_bin_slippage,
+            favor_x_in_active_id: args.favor_x_in_active_id,
+            strategy: args.strategy,
+            remaining_accounts_info: args.remaining_accounts_info,
+            leftover_policy: args.leftover_policy,
+            single_token_swap: args.single_token_swap,
+            price_range: args.price_range,
+            max_price_deviation_bps: args.max_price_deviation_bps,
+            shrink_mode: args.shrink_mode,
+        },
+        accounts.remaining_accounts,
+    )
+}
+
+pub struct RebalanceDlmmPositionAccounts {
+    pub owner: Pubkey,
+    pub lb_pair: Pubkey,
+    pub position: Pubkey,
+    pub rent_payer: Pubkey,
+    pub bin_array_bitmap_extension_required: bool,
+    // required for transfer leftover policy
+    pub leftover_token_x_account: Option<Pubkey>,
+    pub leftover_token_y_account: Option<Pubkey>,
+    // bin arrays covering the current and the new position range and the swap path, followed by transfer hook accounts if any
+    pub remaining_accounts: Vec<AccountMeta>,
+}
+
+// leftover of the rebalance stays in user token accounts unless leftover policy moves it
+pub struct RebalanceDlmmPositionArgs {
+    pub active_id: i32,
+    pub min_delta_id: i32,
+    pub max_delta_id: i32,
+    pub max_active_bin_slippage: u16,
+    pub favor_x_in_active_id: bool,
+    pub strategy: StrategyType,
+    pub remaining_accounts_info: RemainingAccountsInfo,
+    // claim fee and reward before withdraw
+    pub should_claim_fee: bool,
+    pub should_claim_reward: bool,
+    // add claimed amounts of token x and token y back to position
+    pub compound: bool,
+    // max deviation of the active bin price from the price of active_id, in bps, None skips the check
+    pub max_price_deviation_bps: Option<u16>,
+    // dlmm shrink mode of empty bins after deposit
+    pub shrink_mode: u8,
+    pub leftover_policy: LeftoverPolicy,
+}
+
+pub fn rebalance_dlmm_position(
+    accounts: RebalanceDlmmPositionAccounts,
+    lb_pair_state: &LbPair,
+    args: RebalanceDlmmPositionArgs,
+) -> Instruction {
+    let token_x_program = get_token_program(lb_pair_state.token_mint_x_program_flag);
+    let token_y_program = get_token_program(lb_pair_state.token_mint_y_program_flag);
+    let (user_token_x, user_token_y) = get_user_token_accounts(&accounts.owner, lb_pair_state);
+    build_instruction(
+        zap::accounts::RebalanceDlmmPositionCtx {
+            lb_pair: accounts.lb_pair,
+            position: accounts.position,
+            bin_array_bitmap_extension: accounts
+                .bin_array_bitmap_extension_required
+                .then(|| derive_bin_array_bitmap_extension(&accounts.lb_pair)),
+            user_token_x,
+            user_token_y,
+            reserve_x: lb_pair_state.reserve_x,
+            reserve_y: lb_pair_state.reserve_y,
+            token_x_mint: lb_pair_state.token_x_mint,
+            token_y_mint: lb_pair_state.token_y_mint,
+            dlmm_program: dlmm::ID,
+            owner: accounts.owner,
+            rent_payer: accounts.rent_payer,
+            token_x_program,
+            token_y_program,
+            memo_program: MEMO_PROGRAM_ID,
+            system_program: system_program::ID,
+            dlmm_event_authority: derive_dlmm_event_authority(),
+            oracle: derive_oracle(&accounts.lb_pair),
+            leftover_token_x_account: accounts.leftover_token_x_account,
+            leftover_token_y_account: accounts.leftover_token_y_account,
+            // bitmap extension is initialized if missing, which needs rent sysvar
+            rent: accounts
+                .bin_array_bitmap_extension_required
+                .then_some(sysvar::rent::ID),
+        },
+        zap::instruction::RebalanceDlmmPosition {
+            active_id: args.active_id,
+            min_delta_id: args.min_delta_id,
+            max_delta_id: args.max_delta_id,
+            max_active_bin_slippage: args.max_active_bin_slippage,
+            favor_x_in_active_id: args.favor_x_in_active_id,
+            strategy: args.strategy,
+            remaining_accounts_info: args.remaining_accounts_info,
+            should_claim_fee: args.should_claim_fee,
+            should_claim_reward: args.should_claim_reward,
+            compound: args.compound,
+            max_price_deviation_bps: args.max_price_deviation_bps,
+            shrink_mode: args.shrink_mode,
+            leftover_policy: args.leftover_policy,
+        },
+        accounts.remaining_accounts,
+    )
+}
+
+pub fn quote_zap_in_dlmm(
+    lb_pair: &Pubkey,
+    amount_x: u64,
+    amount_y: u64,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    favor_x_in_active_id: bool,
+    strategy: StrategyType,
+) -> Instruction {
+    build_instruction(
+        zap::accounts::QuoteZapInDlmmCtx { lb_pair: *lb_pair },
+        zap::instruction::QuoteZapInDlmm {
+            amount_x,
+            amount_y,
+            min_delta_id,
+            max_delta_id,
+            favor_x_in_active_id,
+            strategy,
+        },
+        vec![],
+    )
+}
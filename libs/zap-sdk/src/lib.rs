@@ -0,0 +1,10 @@
+//! Client helpers to build zap program instructions and the transaction bundles in `ZAPIN.md`
+pub mod bundles;
+pub use bundles::*;
+pub mod instructions;
+pub use instructions::*;
+pub mod pda;
+pub use pda::*;
+pub mod quote;
+#[cfg(test)]
+mod tests;
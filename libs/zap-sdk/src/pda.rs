@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use zap::constants::seeds::USER_LEDGER_PREFIX;
+// bin array helpers are shared with the program, which initializes missing bin arrays of the same pda
+pub use zap::{
+    bin_id_to_bin_array_index, derive_bin_array, is_overflow_default_bin_array_bitmap,
+    BIN_ARRAY_BITMAP_SIZE, MAX_BIN_PER_ARRAY,
+};
+
+const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+// dlmm seeds
+const BIN_ARRAY_BITMAP_SEED: &[u8] = b"bitmap";
+const ORACLE_SEED: &[u8] = b"oracle";
+
+// damm v2 seeds
+const POOL_AUTHORITY_SEED: &[u8] = b"pool_authority";
+const POSITION_SEED: &[u8] = b"position";
+const POSITION_NFT_ACCOUNT_SEED: &[u8] = b"position_nft_account";
+
+////// ZAP //////
+
+pub fn derive_ledger_account(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[USER_LEDGER_PREFIX, owner.as_ref()], &zap::ID).0
+}
+
+////// DLMM //////
+
+pub fn derive_dlmm_event_authority() -> Pubkey {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &dlmm::ID).0
+}
+
+pub fn derive_bin_array_bitmap_extension(lb_pair: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[BIN_ARRAY_BITMAP_SEED, lb_pair.as_ref()], &dlmm::ID).0
+}
+
+pub fn derive_oracle(lb_pair: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[ORACLE_SEED, lb_pair.as_ref()], &dlmm::ID).0
+}
+
+// bin arrays covering [lower_bin_id, upper_bin_id] in ascending order
+pub fn get_bin_arrays_by_bin_range(
+    lb_pair: &Pubkey,
+    lower_bin_id: i32,
+    upper_bin_id: i32,
+) -> Vec<Pubkey> {
+    (bin_id_to_bin_array_index(lower_bin_id)..=bin_id_to_bin_array_index(upper_bin_id))
+        .map(|index| derive_bin_array(lb_pair, index))
+        .collect()
+}
+
+pub fn get_bin_array_metas_by_bin_range(
+    lb_pair: &Pubkey,
+    lower_bin_id: i32,
+    upper_bin_id: i32,
+) -> Vec<AccountMeta> {
+    get_bin_arrays_by_bin_range(lb_pair, lower_bin_id, upper_bin_id)
+        .into_iter()
+        .map(|bin_array| AccountMeta::new(bin_array, false))
+        .collect()
+}
+
+// bitmap extension is only needed when the bin array is out of lb pair internal bitmap
+pub fn is_bin_array_bitmap_extension_required(bin_id: i32) -> bool {
+    is_overflow_default_bin_array_bitmap(bin_id_to_bin_array_index(bin_id))
+}
+
+////// DAMM V2 //////
+
+pub fn derive_damm_v2_pool_authority() -> Pubkey {
+    Pubkey::find_program_address(&[POOL_AUTHORITY_SEED], &damm_v2::ID).0
+}
+
+pub fn derive_damm_v2_event_authority() -> Pubkey {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &damm_v2::ID).0
+}
+
+pub fn derive_damm_v2_position(position_nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[POSITION_SEED, position_nft_mint.as_ref()], &damm_v2::ID).0
+}
+
+pub fn derive_damm_v2_position_nft_account(position_nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[POSITION_NFT_ACCOUNT_SEED, position_nft_mint.as_ref()],
+        &damm_v2::ID,
+    )
+    .0
+}
@@ -0,0 +1,199 @@
+//! Off-chain quote with the same code the program runs, inputs are deserialized accounts and a clock
+use anchor_lang::prelude::*;
+use damm_v2::{
+    activation_handler::ActivationType, params::swap::TradeDirection, state::Pool, PoolError,
+};
+use dlmm::accounts::{BinArray, LbPair};
+use zap::{
+    calculate_dlmm_single_token_swap_amount, calculate_dlmm_swap_amount,
+    damm_v2_utils::{calculate_swap_amount, get_zap_in_damm_v2_quote},
+    get_zap_in_dlmm_quote, DlmmSwapSimulator, StrategyType, TransferFeeCalculator,
+    ZapInDammV2Quote, ZapInDlmmQuote, ZapInRebalancingParams,
+};
+
+pub use zap::damm_v2_utils::get_price_change_bps;
+
+// current point of damm v2 pool by activation type, same as damm v2 ActivationHandler with the given clock
+pub fn get_current_point(activation_type: u8, clock: &Clock) -> Result<u64> {
+    let activation_type =
+        ActivationType::try_from(activation_type).map_err(|_| PoolError::InvalidActivationType)?;
+    Ok(match activation_type {
+        ActivationType::Slot => clock.slot,
+        ActivationType::Timestamp => clock.unix_timestamp as u64,
+    })
+}
+
+// owner and data of a mint account
+pub struct MintAccount<'a> {
+    pub owner: &'a Pubkey,
+    pub data: &'a [u8],
+}
+
+impl MintAccount<'_> {
+    pub fn get_transfer_fee_calculator(&self, clock: &Clock) -> Result<TransferFeeCalculator> {
+        TransferFeeCalculator::from_mint_data(self.owner, self.data, || Ok(clock.epoch))
+    }
+}
+
+pub struct DammV2QuoteContext<'a> {
+    pub pool: &'a Pool,
+    pub token_a_mint: MintAccount<'a>,
+    pub token_b_mint: MintAccount<'a>,
+    pub clock: &'a Clock,
+    pub has_referral: bool,
+}
+
+impl DammV2QuoteContext<'_> {
+    fn get_transfer_fee_calculators(
+        &self,
+    ) -> Result<(TransferFeeCalculator, TransferFeeCalculator)> {
+        Ok((
+            self.token_a_mint.get_transfer_fee_calculator(self.clock)?,
+            self.token_b_mint.get_transfer_fee_calculator(self.clock)?,
+        ))
+    }
+
+    // (swap in amount, swap out amount) that zap_in_damm_v2 swaps for remaining_amount
+    pub fn get_swap_amount(
+        &self,
+        remaining_amount: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<(u64, u64)> {
+        let (token_a_transfer_fee_calculator, token_b_transfer_fee_calculator) =
+            self.get_transfer_fee_calculators()?;
+        calculate_swap_amount(
+            self.pool,
+            &token_a_transfer_fee_calculator,
+            &token_b_transfer_fee_calculator,
+            remaining_amount,
+            trade_direction,
+            get_current_point(self.pool.activation_type, self.clock)?,
+            self.has_referral,
+        )
+    }
+
+    // same result as quote_zap_in_damm_v2 instruction
+    pub fn get_zap_in_quote(
+        &self,
+        amount_a: u64,
+        amount_b: u64,
+        max_rounds: u8,
+        dust_threshold: u64,
+    ) -> Result<ZapInDammV2Quote> {
+        let (token_a_transfer_fee_calculator, token_b_transfer_fee_calculator) =
+            self.get_transfer_fee_calculators()?;
+        get_zap_in_damm_v2_quote(
+            self.pool,
+            &token_a_transfer_fee_calculator,
+            &token_b_transfer_fee_calculator,
+            amount_a,
+            amount_b,
+            get_current_point(self.pool.activation_type, self.clock)?,
+            self.clock.unix_timestamp as u64,
+            self.has_referral,
+            max_rounds,
+            dust_threshold,
+        )
+    }
+}
+
+// same result as quote_zap_in_dlmm instruction, without return data size limit
+pub fn get_zap_in_dlmm_bin_distribution(
+    lb_pair: &LbPair,
+    amount_x: u64,
+    amount_y: u64,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    favor_x_in_active_id: bool,
+    strategy: StrategyType,
+) -> Result<ZapInDlmmQuote> {
+    get_zap_in_dlmm_quote(
+        lb_pair,
+        amount_x,
+        amount_y,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        strategy,
+    )
+}
+
+pub struct DlmmQuoteContext<'a> {
+    pub lb_pair: &'a LbPair,
+    // bin arrays of the swap path
+    pub bin_arrays: &'a [BinArray],
+    pub token_x_mint: MintAccount<'a>,
+    pub token_y_mint: MintAccount<'a>,
+    pub clock: &'a Clock,
+}
+
+impl DlmmQuoteContext<'_> {
+    // (swap in amount, swap out amount) that zap in dlmm swaps with single_token_swap
+    pub fn get_single_token_swap_amount(
+        &self,
+        amount_in: u64,
+        swap_for_y: bool,
+        min_delta_id: i32,
+        max_delta_id: i32,
+        favor_x_in_active_id: bool,
+        strategy: StrategyType,
+    ) -> Result<(u64, u64)> {
+        let bin_arrays = self.bin_arrays.iter().collect::<Vec<_>>();
+        let simulator =
+            DlmmSwapSimulator::new(self.lb_pair, &bin_arrays, self.clock.unix_timestamp);
+        let (amount_x, amount_y) = if swap_for_y {
+            (amount_in, 0)
+        } else {
+            (0, amount_in)
+        };
+        let params = ZapInRebalancingParams {
+            amount_x,
+            amount_y,
+            active_id: self.lb_pair.active_id,
+            bin_step: self.lb_pair.bin_step,
+            min_delta_id,
+            max_delta_id,
+            favor_x_in_active_id,
+            strategy,
+        };
+        calculate_dlmm_single_token_swap_amount(
+            &simulator,
+            &params,
+            &self.token_x_mint.get_transfer_fee_calculator(self.clock)?,
+            &self.token_y_mint.get_transfer_fee_calculator(self.clock)?,
+            amount_in,
+            swap_for_y,
+        )
+    }
+
+    // (swap in amount, swap out amount, swap_for_y) that rebalance dlmm position swaps with withdrawn amounts
+    pub fn get_swap_amount(
+        &self,
+        amount_x: u64,
+        amount_y: u64,
+        min_delta_id: i32,
+        max_delta_id: i32,
+        favor_x_in_active_id: bool,
+        strategy: StrategyType,
+    ) -> Result<(u64, u64, bool)> {
+        let bin_arrays = self.bin_arrays.iter().collect::<Vec<_>>();
+        let simulator =
+            DlmmSwapSimulator::new(self.lb_pair, &bin_arrays, self.clock.unix_timestamp);
+        let params = ZapInRebalancingParams {
+            amount_x,
+            amount_y,
+            active_id: self.lb_pair.active_id,
+            bin_step: self.lb_pair.bin_step,
+            min_delta_id,
+            max_delta_id,
+            favor_x_in_active_id,
+            strategy,
+        };
+        calculate_dlmm_swap_amount(
+            &simulator,
+            &params,
+            &self.token_x_mint.get_transfer_fee_calculator(self.clock)?,
+            &self.token_y_mint.get_transfer_fee_calculator(self.clock)?,
+        )
+    }
+}
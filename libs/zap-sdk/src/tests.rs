@@ -0,0 +1,207 @@
+use anchor_lang::{prelude::*, Discriminator};
+use damm_v2::params::swap::TradeDirection;
+
+use crate::{
+    bin_id_to_bin_array_index, build_single_token_zap_in_damm_v2_bundle, derive_oracle,
+    get_bin_arrays_by_bin_range, is_bin_array_bitmap_extension_required,
+    quote::{get_current_point, DammV2QuoteContext, MintAccount},
+    zap_in_damm_v2, zap_in_dlmm_for_multiple_positions, ZapInDammV2Accounts, ZapInDammV2Args,
+    ZapInDlmmForMultiplePositionsAccounts, ZapInDlmmForMultiplePositionsArgs,
+};
+
+#[test]
+fn test_bin_id_to_bin_array_index() {
+    assert_eq!(bin_id_to_bin_array_index(0), 0);
+    assert_eq!(bin_id_to_bin_array_index(69), 0);
+    assert_eq!(bin_id_to_bin_array_index(70), 1);
+    assert_eq!(bin_id_to_bin_array_index(-1), -1);
+    assert_eq!(bin_id_to_bin_array_index(-70), -1);
+    assert_eq!(bin_id_to_bin_array_index(-71), -2);
+
+    let lb_pair = Pubkey::new_unique();
+    assert_eq!(get_bin_arrays_by_bin_range(&lb_pair, -1, 0).len(), 2);
+    assert_eq!(get_bin_arrays_by_bin_range(&lb_pair, 0, 69).len(), 1);
+
+    assert!(!is_bin_array_bitmap_extension_required(0));
+    assert!(!is_bin_array_bitmap_extension_required(512 * 70 - 1));
+    assert!(is_bin_array_bitmap_extension_required(512 * 70));
+    assert!(!is_bin_array_bitmap_extension_required(-512 * 70));
+    assert!(is_bin_array_bitmap_extension_required(-512 * 70 - 1));
+}
+
+#[test]
+fn test_get_current_point() {
+    let clock = Clock {
+        slot: 100,
+        unix_timestamp: 1762837786,
+        ..Default::default()
+    };
+    assert_eq!(get_current_point(0, &clock).unwrap(), 100);
+    assert_eq!(get_current_point(1, &clock).unwrap(), 1762837786);
+    assert!(get_current_point(2, &clock).is_err());
+}
+
+#[test]
+fn test_single_token_zap_in_damm_v2_bundle() {
+    let owner = Pubkey::new_unique();
+    let accounts = ZapInDammV2Accounts {
+        owner,
+        pool: Pubkey::new_unique(),
+        position: Pubkey::new_unique(),
+        position_nft_account: Pubkey::new_unique(),
+        referral_token_account: None,
+        vesting: None,
+        leftover_token_a_account: None,
+        leftover_token_b_account: None,
+    };
+    let pool_state = damm_v2::state::Pool::default();
+
+    let zap_in_instruction =
+        zap_in_damm_v2(&accounts, &pool_state, ZapInDammV2Args::new(1 << 64, 100));
+    assert_eq!(zap_in_instruction.program_id, zap::ID);
+    assert_eq!(
+        &zap_in_instruction.data[..8],
+        zap::instruction::ZapInDammV2::DISCRIMINATOR
+    );
+    // optional accounts are replaced by zap program id
+    assert_eq!(zap_in_instruction.accounts[16].pubkey, zap::ID);
+    // instructions sysvar is the last remaining account
+    assert_eq!(
+        zap_in_instruction.accounts.last().unwrap().pubkey,
+        anchor_lang::solana_program::sysvar::instructions::ID
+    );
+
+    let instructions = build_single_token_zap_in_damm_v2_bundle(
+        &accounts,
+        &pool_state,
+        1_000_000_000,
+        true,
+        ZapInDammV2Args::new(1 << 64, 100),
+    );
+    let discriminators: Vec<&[u8]> = instructions.iter().map(|ix| &ix.data[..8]).collect();
+    assert_eq!(
+        discriminators,
+        vec![
+            zap::instruction::InitializeLedgerAccount::DISCRIMINATOR,
+            zap::instruction::SetLedgerBalance::DISCRIMINATOR,
+            zap::instruction::SetLedgerBalance::DISCRIMINATOR,
+            zap::instruction::ZapInDammV2::DISCRIMINATOR,
+            zap::instruction::CloseLedgerAccount::DISCRIMINATOR,
+        ]
+    );
+}
+
+#[test]
+fn test_off_chain_quote_matches_program_quote() {
+    let path = "../../fixtures/8Pm2kZpnxD3hoMmt4bjStX2Pw2Z9abpbHzZxMPqxPmie.bin";
+    let account_data = std::fs::read(path).expect("Failed to read account data");
+    let pool: damm_v2::state::Pool = bytemuck::pod_read_unaligned(&account_data[8..]);
+
+    let clock = Clock {
+        slot: 1,
+        unix_timestamp: 1762837786,
+        ..Default::default()
+    };
+    // spl token mint doesn't have transfer fee, data is not read
+    let context = DammV2QuoteContext {
+        pool: &pool,
+        token_a_mint: MintAccount {
+            owner: &anchor_spl::token::ID,
+            data: &[],
+        },
+        token_b_mint: MintAccount {
+            owner: &anchor_spl::token::ID,
+            data: &[],
+        },
+        clock: &clock,
+        has_referral: false,
+    };
+
+    let current_point = get_current_point(pool.activation_type, &clock).unwrap();
+    let current_timestamp = clock.unix_timestamp as u64;
+    let transfer_fee_calculator = zap::TransferFeeCalculator::no_transfer_fee();
+
+    let quote = context.get_zap_in_quote(1_000_000_000, 0, 1, 0).unwrap();
+    let program_quote = zap::get_zap_in_damm_v2_quote(
+        &pool,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        1_000_000_000,
+        0,
+        current_point,
+        current_timestamp,
+        false,
+        1,
+        0,
+    )
+    .unwrap();
+    assert_eq!(quote, program_quote);
+
+    let (swap_in_amount, swap_out_amount) = context
+        .get_swap_amount(1_000_000_000, TradeDirection::AtoB)
+        .unwrap();
+    assert_eq!(swap_in_amount, quote.swap_in_amount);
+    assert_eq!(swap_out_amount, quote.swap_out_amount);
+}
+
+#[test]
+fn test_zap_in_dlmm_for_multiple_positions() {
+    let positions = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let bin_array = Pubkey::new_unique();
+    let lb_pair = Pubkey::new_unique();
+    let lb_pair_state: dlmm::accounts::LbPair = bytemuck::Zeroable::zeroed();
+    let instruction = zap_in_dlmm_for_multiple_positions(
+        ZapInDlmmForMultiplePositionsAccounts {
+            owner: Pubkey::new_unique(),
+            lb_pair,
+            positions: positions.clone(),
+            rent_payer: Pubkey::new_unique(),
+            bin_array_bitmap_extension_required: false,
+            leftover_token_x_account: None,
+            leftover_token_y_account: None,
+            remaining_accounts: vec![AccountMeta::new(bin_array, false)],
+        },
+        &lb_pair_state,
+        ZapInDlmmForMultiplePositionsArgs {
+            active_id: 0,
+            min_delta_id: -100,
+            max_delta_id: 100,
+            max_active_bin_slippage: 5,
+            favor_x_in_active_id: false,
+            strategy: zap::StrategyType::Spot,
+            remaining_accounts_info: dlmm::types::RemainingAccountsInfo { slices: vec![] },
+            max_price_deviation_bps: Some(100),
+            leftover_policy: zap::LeftoverPolicy::SwapToTokenB,
+        },
+    );
+    assert_eq!(
+        &instruction.data[..8],
+        zap::instruction::ZapInDlmmForMultiplePositions::DISCRIMINATOR
+    );
+    // positions are writable and go before other remaining accounts
+    let remaining_accounts = &instruction.accounts[instruction.accounts.len() - 3..];
+    assert_eq!(
+        remaining_accounts
+            .iter()
+            .map(|account| account.pubkey)
+            .collect::<Vec<_>>(),
+        vec![positions[0], positions[1], bin_array]
+    );
+    assert!(remaining_accounts[..2]
+        .iter()
+        .all(|account| account.is_writable && !account.is_signer));
+    // swap leftover policy needs oracle
+    assert!(instruction
+        .accounts
+        .iter()
+        .any(|account| account.pubkey == derive_oracle(&lb_pair)));
+    // position count is followed by optional max price deviation bps and leftover policy in instruction data
+    let data_len = instruction.data.len();
+    assert_eq!(instruction.data[data_len - 5], 2);
+    assert_eq!(instruction.data[data_len - 4], 1);
+    assert_eq!(
+        instruction.data[data_len - 3..data_len - 1],
+        100u16.to_le_bytes()
+    );
+    assert_eq!(instruction.data[data_len - 1], 3);
+}
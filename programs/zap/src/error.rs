@@ -31,4 +31,34 @@ pub enum ZapError {
 
     #[msg("Unsupported fee mode")]
     UnsupportedFeeMode,
+
+    #[msg("Unable to swap for single token zap in")]
+    SingleTokenZapInFailed,
+
+    #[msg("Invalid lock parameters")]
+    InvalidLockParameters,
+
+    #[msg("Missing accounts for leftover policy")]
+    MissingLeftoverAccounts,
+
+    #[msg("Invalid strategy weights")]
+    InvalidStrategyWeights,
+
+    #[msg("Invalid strategy segments")]
+    InvalidStrategySegments,
+
+    #[msg("Missing oracle account for dlmm swap")]
+    MissingDlmmOracle,
+
+    #[msg("Positions don't cover the range with adjacent bins")]
+    InvalidPositionRanges,
+
+    #[msg("Invalid price range")]
+    InvalidPriceRange,
+
+    #[msg("Missing rent sysvar to initialize bin array bitmap extension")]
+    MissingRentSysvar,
+
+    #[msg("Nothing to zap in")]
+    EmptyZapInAmount,
 }
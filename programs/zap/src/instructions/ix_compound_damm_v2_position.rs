@@ -0,0 +1,275 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{token::accessor, token_interface::Mint};
+use damm_v2::{
+    params::swap::TradeDirection, state::Pool, AddLiquidityParameters, SwapMode, SwapParameters2,
+};
+
+use crate::{
+    add_liquidity_with_swap_rounds, error::ZapError, new_transfer_fee_calculator,
+    safe_math::SafeMath, CompoundDammV2PositionResult, DammV2ZapInAccounts,
+    DammV2ZapInRoundsResult, UserLedger,
+};
+
+#[derive(Accounts)]
+pub struct CompoundDammV2PositionCtx<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// CHECK: pool_authority, will be checked when we call function in damm v2
+    pub pool_authority: UncheckedAccount<'info>,
+
+    /// CHECK: position, will be checked when we call function in damm v2
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+
+    /// CHECK: The user token a account
+    #[account(mut)]
+    pub token_a_account: UncheckedAccount<'info>,
+
+    /// CHECK: The user token b account
+    #[account(mut)]
+    pub token_b_account: UncheckedAccount<'info>,
+
+    /// CHECK: token_a_vault, will be checked when we call function in damm v2
+    #[account(mut)]
+    pub token_a_vault: UncheckedAccount<'info>,
+
+    /// CHECK: token_b_vault, will be checked when we call function in damm v2
+    #[account(mut)]
+    pub token_b_vault: UncheckedAccount<'info>,
+
+    /// CHECK: The mint of token a
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The mint of token b
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: position_nft_account, will be checked when we call function in damm v2
+    pub position_nft_account: UncheckedAccount<'info>,
+
+    /// owner of position
+    pub owner: Signer<'info>,
+
+    /// CHECK: Token a program
+    pub token_a_program: UncheckedAccount<'info>,
+
+    /// CHECK: Token b program
+    pub token_b_program: UncheckedAccount<'info>,
+
+    pub damm_program: Program<'info, damm_v2::program::CpAmm>,
+
+    /// CHECK: damm event authority, will be check in damm v2 functions
+    pub damm_event_authority: UncheckedAccount<'info>,
+
+    /// CHECK: referral token account, will be checked in damm v2 swap
+    #[account(mut)]
+    pub referral_token_account: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> CompoundDammV2PositionCtx<'info> {
+    fn claim_position_fee(&self) -> Result<()> {
+        damm_v2::cpi::claim_position_fee(CpiContext::new(
+            self.damm_program.to_account_info(),
+            damm_v2::cpi::accounts::ClaimPositionFeeCtx {
+                pool_authority: self.pool_authority.to_account_info(),
+                pool: self.pool.to_account_info(),
+                position: self.position.to_account_info(),
+                token_a_account: self.token_a_account.to_account_info(),
+                token_b_account: self.token_b_account.to_account_info(),
+                token_a_vault: self.token_a_vault.to_account_info(),
+                token_b_vault: self.token_b_vault.to_account_info(),
+                token_a_mint: self.token_a_mint.to_account_info(),
+                token_b_mint: self.token_b_mint.to_account_info(),
+                position_nft_account: self.position_nft_account.to_account_info(),
+                owner: self.owner.to_account_info(),
+                token_a_program: self.token_a_program.to_account_info(),
+                token_b_program: self.token_b_program.to_account_info(),
+                event_authority: self.damm_event_authority.to_account_info(),
+                program: self.damm_program.to_account_info(),
+            },
+        ))?;
+        Ok(())
+    }
+}
+
+impl<'info> DammV2ZapInAccounts<'info> for CompoundDammV2PositionCtx<'info> {
+    fn pool(&self) -> &AccountLoader<'info, Pool> {
+        &self.pool
+    }
+
+    fn token_a_account(&self) -> AccountInfo<'info> {
+        self.token_a_account.to_account_info()
+    }
+
+    fn token_b_account(&self) -> AccountInfo<'info> {
+        self.token_b_account.to_account_info()
+    }
+
+    fn has_referral(&self) -> bool {
+        self.referral_token_account.is_some()
+    }
+
+    fn swap(
+        &self,
+        amount: u64,
+        trade_direction: TradeDirection,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let (input_token_account, output_token_account) = if trade_direction == TradeDirection::AtoB
+        {
+            (
+                self.token_a_account.to_account_info(),
+                self.token_b_account.to_account_info(),
+            )
+        } else {
+            (
+                self.token_b_account.to_account_info(),
+                self.token_a_account.to_account_info(),
+            )
+        };
+        damm_v2::cpi::swap2(
+            CpiContext::new(
+                self.damm_program.to_account_info(),
+                damm_v2::cpi::accounts::SwapCtx {
+                    pool_authority: self.pool_authority.to_account_info(),
+                    input_token_account,
+                    output_token_account,
+                    pool: self.pool.to_account_info(),
+                    token_a_vault: self.token_a_vault.to_account_info(),
+                    token_b_vault: self.token_b_vault.to_account_info(),
+                    token_a_mint: self.token_a_mint.to_account_info(),
+                    token_b_mint: self.token_b_mint.to_account_info(),
+                    token_a_program: self.token_a_program.to_account_info(),
+                    token_b_program: self.token_b_program.to_account_info(),
+                    event_authority: self.damm_event_authority.to_account_info(),
+                    program: self.damm_program.to_account_info(),
+                    payer: self.owner.to_account_info(),
+                    referral_token_account: self
+                        .referral_token_account
+                        .as_ref()
+                        .map(|account| account.to_account_info()),
+                },
+            )
+            .with_remaining_accounts(remaining_accounts.to_vec()),
+            SwapParameters2 {
+                amount_0: amount,
+                amount_1: 0,
+                swap_mode: SwapMode::ExactIn.into(),
+            },
+        )?;
+        Ok(())
+    }
+
+    fn add_liquidity(
+        &self,
+        liquidity: u128,
+        token_a_amount_threshold: u64,
+        token_b_amount_threshold: u64,
+    ) -> Result<()> {
+        damm_v2::cpi::add_liquidity(
+            CpiContext::new(
+                self.damm_program.to_account_info(),
+                damm_v2::cpi::accounts::AddLiquidityCtx {
+                    pool: self.pool.to_account_info(),
+                    position: self.position.to_account_info(),
+                    token_a_account: self.token_a_account.to_account_info(),
+                    token_b_account: self.token_b_account.to_account_info(),
+                    token_a_vault: self.token_a_vault.to_account_info(),
+                    token_b_vault: self.token_b_vault.to_account_info(),
+                    token_a_mint: self.token_a_mint.to_account_info(),
+                    token_b_mint: self.token_b_mint.to_account_info(),
+                    position_nft_account: self.position_nft_account.to_account_info(),
+                    owner: self.owner.to_account_info(),
+                    token_a_program: self.token_a_program.to_account_info(),
+                    token_b_program: self.token_b_program.to_account_info(),
+                    event_authority: self.damm_event_authority.to_account_info(),
+                    program: self.damm_program.to_account_info(),
+                },
+            ),
+            AddLiquidityParameters {
+                liquidity_delta: liquidity,
+                token_a_amount_threshold,
+                token_b_amount_threshold,
+            },
+        )?;
+        Ok(())
+    }
+}
+
+// claim position fee and add it back to the same position with the zap in algorithm,
+// claimed amounts are tracked in memory instead of a ledger account
+pub fn handle_compound_damm_v2_position<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, CompoundDammV2PositionCtx<'info>>,
+    pre_sqrt_price: u128,           // sqrt price user observe in local
+    max_sqrt_price_change_bps: u32, // max sqrt price change after swap
+    min_liquidity_delta: u128,      // min total liquidity added to position
+    max_rounds: u8,                 // max swap and add liquidity rounds, at least 1 round
+    dust_threshold: u64,            // stop swapping when remaining value in token b <= this
+) -> Result<CompoundDammV2PositionResult> {
+    let token_a_account_ai = ctx.accounts.token_a_account.to_account_info();
+    let token_b_account_ai = ctx.accounts.token_b_account.to_account_info();
+    let pre_amount_a = accessor::amount(&token_a_account_ai)?;
+    let pre_amount_b = accessor::amount(&token_b_account_ai)?;
+
+    ctx.accounts.claim_position_fee()?;
+
+    let amount_a_claimed = accessor::amount(&token_a_account_ai)?.safe_sub(pre_amount_a)?;
+    let amount_b_claimed = accessor::amount(&token_b_account_ai)?.safe_sub(pre_amount_b)?;
+
+    let mut ledger = UserLedger {
+        owner: ctx.accounts.owner.key(),
+        amount_a: amount_a_claimed,
+        amount_b: amount_b_claimed,
+    };
+
+    let token_a_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_a_mint)?;
+    let token_b_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_b_mint)?;
+
+    // nothing more than claimed amounts is deposited
+    let DammV2ZapInRoundsResult {
+        liquidity_delta,
+        amount_a_deposited,
+        amount_b_deposited,
+        swap_in_amount,
+        swap_out_amount,
+    } = add_liquidity_with_swap_rounds(
+        &*ctx.accounts,
+        ctx.remaining_accounts,
+        &mut ledger,
+        &token_a_transfer_fee_calculator,
+        &token_b_transfer_fee_calculator,
+        pre_sqrt_price,
+        max_sqrt_price_change_bps,
+        amount_a_claimed,
+        amount_b_claimed,
+        max_rounds,
+        dust_threshold,
+        true, // fees that can't be swapped are left in owner token accounts
+    )?;
+
+    require!(
+        liquidity_delta >= min_liquidity_delta,
+        ZapError::ExceededSlippage
+    );
+
+    // log will be truncated, shouldn't rely on that
+    msg!(
+        "claimed_amounts: {} {}, remaining_amounts: {} {}",
+        amount_a_claimed,
+        amount_b_claimed,
+        ledger.amount_a,
+        ledger.amount_b
+    );
+
+    Ok(CompoundDammV2PositionResult {
+        amount_a_claimed,
+        amount_b_claimed,
+        liquidity_delta,
+        amount_a_deposited,
+        amount_b_deposited,
+        swap_in_amount,
+        swap_out_amount,
+        remaining_amount_a: ledger.amount_a,
+        remaining_amount_b: ledger.amount_b,
+    })
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use damm_v2::{activation_handler::ActivationHandler, state::Pool};
+
+use crate::{
+    damm_v2_utils::get_zap_in_damm_v2_quote, new_transfer_fee_calculator, ZapInDammV2Quote,
+};
+
+#[derive(Accounts)]
+pub struct QuoteZapInDammv2Ctx<'info> {
+    #[account(has_one = token_a_mint, has_one = token_b_mint)]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// CHECK: The mint of token a
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The mint of token b
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+}
+
+pub fn handle_quote_zap_in_damm_v2(
+    ctx: Context<QuoteZapInDammv2Ctx>,
+    amount_a: u64,
+    amount_b: u64,
+    has_referral: bool,
+    max_rounds: u8,
+    dust_threshold: u64,
+) -> Result<ZapInDammV2Quote> {
+    let pool = ctx.accounts.pool.load()?;
+    let token_a_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_a_mint)?;
+    let token_b_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_b_mint)?;
+    let current_point = ActivationHandler::get_current_point(pool.activation_type)?;
+    let current_timestamp = Clock::get()?.unix_timestamp as u64;
+
+    get_zap_in_damm_v2_quote(
+        &pool,
+        &token_a_transfer_fee_calculator,
+        &token_b_transfer_fee_calculator,
+        amount_a,
+        amount_b,
+        current_point,
+        current_timestamp,
+        has_referral,
+        max_rounds,
+        dust_threshold,
+    )
+}
@@ -2,15 +2,79 @@ use anchor_lang::prelude::*;
 use anchor_spl::{token::accessor, token_interface::Mint};
 use damm_v2::{
     activation_handler::ActivationHandler, params::swap::TradeDirection, state::Pool,
-    AddLiquidityParameters, SwapMode, SwapParameters2,
+    AddLiquidityParameters, SwapMode, SwapParameters2, VestingParameters,
 };
 
 use crate::{
-    damm_v2_utils::{calculate_swap_amount, get_price_change_bps},
+    constants::MAX_BASIS_POINT,
+    damm_v2_utils::{
+        calculate_swap_amount, get_amount_a_value_in_token_b, get_price_change_bps,
+        get_swap_out_amount,
+    },
     error::ZapError,
-    new_transfer_fee_calculator, UserLedger,
+    new_transfer_fee_calculator,
+    safe_math::SafeMath,
+    transfer_from_user, LeftoverPolicy, TransferFeeCalculator, UserLedger, ZapInResult,
 };
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum PositionLockMode {
+    // keep liquidity unlocked
+    Unlocked,
+    // lock liquidity added in zap in with vesting schedule
+    Vesting {
+        cliff_point: Option<u64>,
+        period_frequency: u64,
+        cliff_unlock_bps: u16, // percentage of liquidity unlocked at cliff point
+        number_of_period: u16,
+    },
+    // permanently lock liquidity added in zap in
+    Permanent,
+}
+
+impl PositionLockMode {
+    pub fn get_vesting_parameters(&self, liquidity: u128) -> Result<Option<VestingParameters>> {
+        let &PositionLockMode::Vesting {
+            cliff_point,
+            period_frequency,
+            cliff_unlock_bps,
+            number_of_period,
+        } = self
+        else {
+            return Ok(None);
+        };
+
+        require!(
+            cliff_unlock_bps <= MAX_BASIS_POINT,
+            ZapError::InvalidLockParameters
+        );
+
+        let cliff_unlock_liquidity = liquidity
+            .safe_mul(cliff_unlock_bps.into())?
+            .safe_div(MAX_BASIS_POINT.into())?;
+
+        let liquidity_per_period = if number_of_period == 0 {
+            0
+        } else {
+            liquidity
+                .safe_sub(cliff_unlock_liquidity)?
+                .safe_div(number_of_period.into())?
+        };
+
+        // rounding remainder is unlocked at cliff point, so all liquidity is locked
+        let cliff_unlock_liquidity =
+            liquidity.safe_sub(liquidity_per_period.safe_mul(number_of_period.into())?)?;
+
+        Ok(Some(VestingParameters {
+            cliff_point,
+            period_frequency,
+            cliff_unlock_liquidity,
+            liquidity_per_period,
+            number_of_period,
+        }))
+    }
+}
+
 #[derive(Accounts)]
 pub struct ZapInDammv2Ctx<'info> {
     #[account(mut, has_one = owner)]
@@ -64,9 +128,72 @@ pub struct ZapInDammv2Ctx<'info> {
 
     /// CHECK: damm event authority, will be check in damm v2 functions
     pub damm_event_authority: UncheckedAccount<'info>,
+
+    /// CHECK: referral token account, will be checked in damm v2 swap
+    #[account(mut)]
+    pub referral_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: vesting account, only needed for vesting lock mode, will be initialized in damm v2 lock_position
+    #[account(mut)]
+    pub vesting: Option<Signer<'info>>,
+
+    /// payer for vesting account, only needed for vesting lock mode
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+
+    pub system_program: Option<Program<'info, System>>,
+
+    /// CHECK: token a account receives leftover, only needed for transfer leftover policy, will be checked in token program
+    #[account(mut)]
+    pub leftover_token_a_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: token b account receives leftover, only needed for transfer leftover policy, will be checked in token program
+    #[account(mut)]
+    pub leftover_token_b_account: Option<UncheckedAccount<'info>>,
 }
 
-impl<'info> ZapInDammv2Ctx<'info> {
+/// Accounts used by add liquidity and swap rounds of damm v2 zap in
+pub trait DammV2ZapInAccounts<'info> {
+    fn pool(&self) -> &AccountLoader<'info, Pool>;
+
+    fn token_a_account(&self) -> AccountInfo<'info>;
+
+    fn token_b_account(&self) -> AccountInfo<'info>;
+
+    fn has_referral(&self) -> bool;
+
+    fn swap(
+        &self,
+        amount: u64,
+        trade_direction: TradeDirection,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()>;
+
+    fn add_liquidity(
+        &self,
+        liquidity: u128,
+        token_a_amount_threshold: u64,
+        token_b_amount_threshold: u64,
+    ) -> Result<()>;
+}
+
+impl<'info> DammV2ZapInAccounts<'info> for ZapInDammv2Ctx<'info> {
+    fn pool(&self) -> &AccountLoader<'info, Pool> {
+        &self.pool
+    }
+
+    fn token_a_account(&self) -> AccountInfo<'info> {
+        self.token_a_account.to_account_info()
+    }
+
+    fn token_b_account(&self) -> AccountInfo<'info> {
+        self.token_b_account.to_account_info()
+    }
+
+    fn has_referral(&self) -> bool {
+        self.referral_token_account.is_some()
+    }
+
     fn swap(
         &self,
         amount: u64,
@@ -102,7 +229,10 @@ impl<'info> ZapInDammv2Ctx<'info> {
                     event_authority: self.damm_event_authority.to_account_info(),
                     program: self.damm_program.to_account_info(),
                     payer: self.owner.to_account_info(),
-                    referral_token_account: None, // TODO check whether it should be some(damm_program)
+                    referral_token_account: self
+                        .referral_token_account
+                        .as_ref()
+                        .map(|account| account.to_account_info()),
                 },
             )
             .with_remaining_accounts(remaining_accounts.to_vec()),
@@ -115,7 +245,12 @@ impl<'info> ZapInDammv2Ctx<'info> {
         Ok(())
     }
 
-    fn add_liquidity(&self, liquidity: u128) -> Result<()> {
+    fn add_liquidity(
+        &self,
+        liquidity: u128,
+        token_a_amount_threshold: u64,
+        token_b_amount_threshold: u64,
+    ) -> Result<()> {
         damm_v2::cpi::add_liquidity(
             CpiContext::new(
                 self.damm_program.to_account_info(),
@@ -138,36 +273,126 @@ impl<'info> ZapInDammv2Ctx<'info> {
             ),
             AddLiquidityParameters {
                 liquidity_delta: liquidity,
-                token_a_amount_threshold: u64::MAX,
-                token_b_amount_threshold: u64::MAX,
+                token_a_amount_threshold,
+                token_b_amount_threshold,
             },
         )?;
         Ok(())
     }
 }
 
-pub fn handle_zap_in_damm_v2<'c: 'info, 'info>(
-    ctx: Context<'_, '_, 'c, 'info, ZapInDammv2Ctx<'info>>,
-    pre_sqrt_price: u128,           // sqrt price user observe in local
-    max_sqrt_price_change_bps: u32, // max sqrt price change after swap
+impl<'info> ZapInDammv2Ctx<'info> {
+    fn lock_position(&self, liquidity: u128, lock_mode: &PositionLockMode) -> Result<()> {
+        if liquidity == 0 {
+            return Ok(());
+        }
+        match lock_mode {
+            PositionLockMode::Unlocked => {}
+            PositionLockMode::Vesting { .. } => {
+                let (Some(vesting), Some(payer), Some(system_program)) =
+                    (&self.vesting, &self.payer, &self.system_program)
+                else {
+                    return Err(ZapError::InvalidLockParameters.into());
+                };
+                let vesting_parameters = lock_mode
+                    .get_vesting_parameters(liquidity)?
+                    .ok_or(ZapError::InvalidLockParameters)?;
+                damm_v2::cpi::lock_position(
+                    CpiContext::new(
+                        self.damm_program.to_account_info(),
+                        damm_v2::cpi::accounts::LockPositionCtx {
+                            pool: self.pool.to_account_info(),
+                            position: self.position.to_account_info(),
+                            vesting: vesting.to_account_info(),
+                            position_nft_account: self.position_nft_account.to_account_info(),
+                            owner: self.owner.to_account_info(),
+                            payer: payer.to_account_info(),
+                            system_program: system_program.to_account_info(),
+                            event_authority: self.damm_event_authority.to_account_info(),
+                            program: self.damm_program.to_account_info(),
+                        },
+                    ),
+                    vesting_parameters,
+                )?;
+            }
+            PositionLockMode::Permanent => {
+                damm_v2::cpi::permanent_lock_position(
+                    CpiContext::new(
+                        self.damm_program.to_account_info(),
+                        damm_v2::cpi::accounts::PermanentLockPositionCtx {
+                            pool: self.pool.to_account_info(),
+                            position: self.position.to_account_info(),
+                            position_nft_account: self.position_nft_account.to_account_info(),
+                            owner: self.owner.to_account_info(),
+                            event_authority: self.damm_event_authority.to_account_info(),
+                            program: self.damm_program.to_account_info(),
+                        },
+                    ),
+                    liquidity,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct DammV2ZapInRoundsResult {
+    pub liquidity_delta: u128,
+    pub amount_a_deposited: u64,
+    pub amount_b_deposited: u64,
+    pub swap_in_amount: u64,
+    pub swap_out_amount: u64,
+}
+
+// single token zap in stops swapping only after something has been swapped or added,
+// so the other side isn't left unfunded silently
+fn validate_single_token_zap_in(
+    is_single_token: bool,
+    allow_no_op: bool,
+    round: u8,
+    total_liquidity_delta: u128,
 ) -> Result<()> {
-    let mut ledger = ctx.accounts.ledger.load_mut()?;
-    let max_deposit_a_amount = ledger.amount_a;
-    let max_deposit_b_amount = ledger.amount_b;
-    // 1. we add liquidity firstly, so later if we need swap, user could get some fees back
-    let pool = ctx.accounts.pool.load()?;
-    let token_a_account_ai = ctx.accounts.token_a_account.to_account_info();
-    let token_b_account_ai = ctx.accounts.token_b_account.to_account_info();
+    require!(
+        !is_single_token || allow_no_op || round > 0 || total_liquidity_delta > 0,
+        ZapError::SingleTokenZapInFailed
+    );
+    Ok(())
+}
 
-    let token_a_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_a_mint)?;
-    let token_b_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_b_mint)?;
+// 1-3 of damm v2 zap in, shared with compound, ledger balances are updated with deposited and swapped amounts
+pub fn add_liquidity_with_swap_rounds<'info>(
+    accounts: &impl DammV2ZapInAccounts<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    ledger: &mut UserLedger,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    pre_sqrt_price: u128,
+    max_sqrt_price_change_bps: u32,
+    token_a_amount_threshold: u64,
+    token_b_amount_threshold: u64,
+    max_rounds: u8,
+    dust_threshold: u64,
+    allow_no_op: bool,
+) -> Result<DammV2ZapInRoundsResult> {
+    // nothing to add is only allowed if caller allows adding nothing, e.g. compound of one-sided or small fees
+    require!(
+        allow_no_op || ledger.amount_a > 0 || ledger.amount_b > 0,
+        ZapError::EmptyZapInAmount
+    );
+    // single token zap in: only one side is funded, so the other side must come from the swap through the pool
+    let is_single_token = (ledger.amount_a == 0) != (ledger.amount_b == 0);
+    // 1. we add liquidity firstly, so later if we need swap, user could get some fees back
+    let pool = accounts.pool().load()?;
+    let token_a_account_ai = accounts.token_a_account();
+    let token_b_account_ai = accounts.token_b_account();
 
     let user_amount_a_1 = accessor::amount(&token_a_account_ai)?;
     let user_amount_b_1 = accessor::amount(&token_b_account_ai)?;
 
     let (liquidity, trade_direction) = ledger.get_liquidity_from_amounts_and_trade_direction(
-        &token_a_transfer_fee_calculator,
-        &token_b_transfer_fee_calculator,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
         pool.sqrt_price,
         pool.sqrt_min_price,
         pool.sqrt_max_price,
@@ -176,12 +401,16 @@ pub fn handle_zap_in_damm_v2<'c: 'info, 'info>(
     drop(pool);
 
     if liquidity > 0 {
-        ctx.accounts.add_liquidity(liquidity)?;
+        accounts.add_liquidity(
+            liquidity,
+            token_a_amount_threshold,
+            token_b_amount_threshold,
+        )?;
     }
+    let mut total_liquidity_delta = liquidity;
 
-    // 2. We check if user is still having some balance left, we will swap before they could add remaining liquidity
-    let user_amount_a_2 = accessor::amount(&token_a_account_ai)?;
-    let user_amount_b_2 = accessor::amount(&token_b_account_ai)?;
+    let mut user_amount_a_2 = accessor::amount(&token_a_account_ai)?;
+    let mut user_amount_b_2 = accessor::amount(&token_b_account_ai)?;
 
     ledger.update_ledger_balances(
         user_amount_a_1,
@@ -190,99 +419,310 @@ pub fn handle_zap_in_damm_v2<'c: 'info, 'info>(
         user_amount_b_2,
     )?;
 
-    let remaining_amount = if trade_direction == TradeDirection::AtoB {
-        ledger.amount_a
-    } else {
-        ledger.amount_b
-    };
+    let mut amount_a_deposited = user_amount_a_1.safe_sub(user_amount_a_2)?;
+    let mut amount_b_deposited = user_amount_b_1.safe_sub(user_amount_b_2)?;
+
+    // thresholds are applied for total deposited amounts, so we deduct the amounts used in each add liquidity
+    let mut token_a_amount_threshold = token_a_amount_threshold.safe_sub(amount_a_deposited)?;
+    let mut token_b_amount_threshold = token_b_amount_threshold.safe_sub(amount_b_deposited)?;
+
+    let mut swap_in_amount: u64 = 0;
+    let mut swap_out_amount: u64 = 0;
+
+    // 2. We check if user is still having some balance left, we will swap before they could add remaining liquidity,
+    // repeat until remaining is dust or rounds are exhausted
+    for round in 0..max_rounds.max(1) {
+        let remaining_amount = if trade_direction == TradeDirection::AtoB {
+            ledger.amount_a
+        } else {
+            ledger.amount_b
+        };
+        if remaining_amount == 0 {
+            break;
+        }
+
+        let pool = accounts.pool().load()?;
+        if dust_threshold > 0 {
+            let remaining_value = if trade_direction == TradeDirection::AtoB {
+                get_amount_a_value_in_token_b(remaining_amount, pool.sqrt_price)?
+            } else {
+                remaining_amount.into()
+            };
+            if remaining_value <= dust_threshold.into() {
+                // single token zap in of dust would add nothing, it shouldn't pass silently either
+                validate_single_token_zap_in(
+                    is_single_token,
+                    allow_no_op,
+                    round,
+                    total_liquidity_delta,
+                )?;
+                break;
+            }
+        }
 
-    if remaining_amount > 0 {
-        let pool = ctx.accounts.pool.load()?;
         let current_point = ActivationHandler::get_current_point(pool.activation_type)?;
         let swap_result = calculate_swap_amount(
             &pool,
-            &token_a_transfer_fee_calculator,
-            &token_b_transfer_fee_calculator,
+            token_a_transfer_fee_calculator,
+            token_b_transfer_fee_calculator,
             remaining_amount,
             trade_direction,
             current_point,
+            accounts.has_referral(),
         );
         match swap_result {
-            Ok((swap_in_amount, swap_out_amount)) => {
-                if swap_in_amount == 0 || swap_out_amount == 0 {
-                    msg!(
-                        "max_deposit_amounts: {} {}, remaining_amounts: {} {}, swap_amounts: {} {}",
-                        max_deposit_a_amount,
-                        max_deposit_b_amount,
-                        ledger.amount_a,
-                        ledger.amount_b,
-                        swap_in_amount,
-                        swap_out_amount
-                    );
-                    return Ok(()); // no need to swap, just return
-                }
+            Ok((amount_in, amount_out)) if amount_in > 0 && amount_out > 0 => {
                 drop(pool);
-                ctx.accounts
-                    .swap(swap_in_amount, trade_direction, &ctx.remaining_accounts)?;
+                accounts.swap(amount_in, trade_direction, remaining_accounts)?;
+            }
+            Ok((amount_in, amount_out)) => {
+                // nothing may have been deposited yet, so we shouldn't let single token zap in pass silently
+                validate_single_token_zap_in(
+                    is_single_token,
+                    allow_no_op,
+                    round,
+                    total_liquidity_delta,
+                )?;
+                msg!("swap_amounts: {} {}", amount_in, amount_out);
+                break; // no need to swap
             }
             Err(err) => {
-                // if calculation fail, we just skip swap and add liquidity with remaining amount
+                // if calculation fail, we just skip swap
                 msg!("Calculate swap amount error: {:?}", err);
-                msg!(
-                    "max_deposit_amounts: {} {}, remaining_amounts: {} {}",
-                    max_deposit_a_amount,
-                    max_deposit_b_amount,
-                    ledger.amount_a,
-                    ledger.amount_b
-                );
-                return Ok(());
+                validate_single_token_zap_in(
+                    is_single_token,
+                    allow_no_op,
+                    round,
+                    total_liquidity_delta,
+                )?;
+                break;
             }
         }
+
+        // validate pool price after swap, bound is applied for the price before zap in, so it covers all rounds
+        let pool = accounts.pool().load()?;
+        let post_sqrt_price = pool.sqrt_price;
+        // validate price change
+        let sqrt_price_change_bps = get_price_change_bps(pre_sqrt_price, post_sqrt_price)?;
+        require!(
+            sqrt_price_change_bps <= max_sqrt_price_change_bps,
+            ZapError::ExceededSlippage
+        );
+
+        // 3. Add liquidity again
+        // reload balance
+        let user_amount_a_3 = accessor::amount(&token_a_account_ai)?;
+        let user_amount_b_3 = accessor::amount(&token_b_account_ai)?;
+
+        ledger.update_ledger_balances(
+            user_amount_a_2,
+            user_amount_a_3,
+            user_amount_b_2,
+            user_amount_b_3,
+        )?;
+
+        // actual swap amounts, could be different from calculated ones because of transfer fee
+        let (amount_in, amount_out) = if trade_direction == TradeDirection::AtoB {
+            (
+                user_amount_a_2.safe_sub(user_amount_a_3)?,
+                user_amount_b_3.safe_sub(user_amount_b_2)?,
+            )
+        } else {
+            (
+                user_amount_b_2.safe_sub(user_amount_b_3)?,
+                user_amount_a_3.safe_sub(user_amount_a_2)?,
+            )
+        };
+        swap_in_amount = swap_in_amount.safe_add(amount_in)?;
+        swap_out_amount = swap_out_amount.safe_add(amount_out)?;
+
+        let (liquidity, next_trade_direction) = ledger
+            .get_liquidity_from_amounts_and_trade_direction(
+                token_a_transfer_fee_calculator,
+                token_b_transfer_fee_calculator,
+                pool.sqrt_price,
+                pool.sqrt_min_price,
+                pool.sqrt_max_price,
+            )?;
+
+        drop(pool);
+
+        if liquidity > 0 {
+            accounts.add_liquidity(
+                liquidity,
+                token_a_amount_threshold,
+                token_b_amount_threshold,
+            )?;
+        }
+        total_liquidity_delta = total_liquidity_delta.safe_add(liquidity)?;
+
+        let user_amount_a_4 = accessor::amount(&token_a_account_ai)?;
+        let user_amount_b_4 = accessor::amount(&token_b_account_ai)?;
+
+        ledger.update_ledger_balances(
+            user_amount_a_3,
+            user_amount_a_4,
+            user_amount_b_3,
+            user_amount_b_4,
+        )?;
+
+        let deposited_a = user_amount_a_3.safe_sub(user_amount_a_4)?;
+        let deposited_b = user_amount_b_3.safe_sub(user_amount_b_4)?;
+        amount_a_deposited = amount_a_deposited.safe_add(deposited_a)?;
+        amount_b_deposited = amount_b_deposited.safe_add(deposited_b)?;
+        token_a_amount_threshold = token_a_amount_threshold.safe_sub(deposited_a)?;
+        token_b_amount_threshold = token_b_amount_threshold.safe_sub(deposited_b)?;
+
+        // swap overshot, we don't swap back to avoid paying fees twice
+        if next_trade_direction != trade_direction {
+            break;
+        }
+
+        user_amount_a_2 = user_amount_a_4;
+        user_amount_b_2 = user_amount_b_4;
     }
 
-    // validate pool price after swap
-    let pool = ctx.accounts.pool.load()?;
-    let post_sqrt_price = pool.sqrt_price;
-    // validate price change
-    let sqrt_price_change_bps = get_price_change_bps(pre_sqrt_price, post_sqrt_price)?;
-    require!(
-        sqrt_price_change_bps <= max_sqrt_price_change_bps,
-        ZapError::ExceededSlippage
-    );
+    Ok(DammV2ZapInRoundsResult {
+        liquidity_delta: total_liquidity_delta,
+        amount_a_deposited,
+        amount_b_deposited,
+        swap_in_amount,
+        swap_out_amount,
+    })
+}
 
-    // 3. Do final add liquidity
-    // reload balance
-    let user_amount_a_3 = accessor::amount(&token_a_account_ai)?;
-    let user_amount_b_3 = accessor::amount(&token_b_account_ai)?;
+pub fn handle_zap_in_damm_v2<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, ZapInDammv2Ctx<'info>>,
+    pre_sqrt_price: u128,            // sqrt price user observe in local
+    max_sqrt_price_change_bps: u32,  // max sqrt price change after swap
+    min_liquidity_delta: u128,       // min total liquidity added to position
+    token_a_amount_threshold: u64,   // max total token a amount deposited to position
+    token_b_amount_threshold: u64,   // max total token b amount deposited to position
+    lock_mode: PositionLockMode,     // lock liquidity added in zap in
+    max_rounds: u8,                  // max swap and add liquidity rounds, at least 1 round
+    dust_threshold: u64,             // stop swapping when remaining value in token b <= this
+    leftover_policy: LeftoverPolicy, // what to do with remaining ledger balances
+) -> Result<ZapInResult> {
+    let mut ledger = ctx.accounts.ledger.load_mut()?;
+    let max_deposit_a_amount = ledger.amount_a;
+    let max_deposit_b_amount = ledger.amount_b;
+    let token_a_account_ai = ctx.accounts.token_a_account.to_account_info();
+    let token_b_account_ai = ctx.accounts.token_b_account.to_account_info();
 
-    ledger.update_ledger_balances(
-        user_amount_a_2,
-        user_amount_a_3,
-        user_amount_b_2,
-        user_amount_b_3,
-    )?;
+    let token_a_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_a_mint)?;
+    let token_b_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_b_mint)?;
 
-    let (liquidity, _trade_direction) = ledger.get_liquidity_from_amounts_and_trade_direction(
+    let DammV2ZapInRoundsResult {
+        liquidity_delta: total_liquidity_delta,
+        amount_a_deposited,
+        amount_b_deposited,
+        swap_in_amount,
+        swap_out_amount,
+    } = add_liquidity_with_swap_rounds(
+        &*ctx.accounts,
+        ctx.remaining_accounts,
+        &mut ledger,
         &token_a_transfer_fee_calculator,
         &token_b_transfer_fee_calculator,
-        pool.sqrt_price,
-        pool.sqrt_min_price,
-        pool.sqrt_max_price,
+        pre_sqrt_price,
+        max_sqrt_price_change_bps,
+        token_a_amount_threshold,
+        token_b_amount_threshold,
+        max_rounds,
+        dust_threshold,
+        false,
     )?;
 
-    if liquidity > 0 {
-        drop(pool);
-        ctx.accounts.add_liquidity(liquidity)?;
-    }
+    require!(
+        total_liquidity_delta >= min_liquidity_delta,
+        ZapError::ExceededSlippage
+    );
 
-    let user_amount_a_4 = accessor::amount(&token_a_account_ai)?;
-    let user_amount_b_4 = accessor::amount(&token_b_account_ai)?;
+    // 4. lock liquidity added in this zap in, so it's never observable as unlocked
+    ctx.accounts
+        .lock_position(total_liquidity_delta, &lock_mode)?;
+
+    // 5. handle leftover
+    let user_amount_a_5 = accessor::amount(&token_a_account_ai)?;
+    let user_amount_b_5 = accessor::amount(&token_b_account_ai)?;
+    let mut leftover_swap_in_amount = 0;
+    let mut leftover_swap_out_amount = 0;
+    match leftover_policy {
+        LeftoverPolicy::Keep => {}
+        LeftoverPolicy::Transfer => {
+            let (Some(leftover_token_a_account), Some(leftover_token_b_account)) = (
+                &ctx.accounts.leftover_token_a_account,
+                &ctx.accounts.leftover_token_b_account,
+            ) else {
+                return Err(ZapError::MissingLeftoverAccounts.into());
+            };
+            transfer_from_user(
+                ctx.accounts.token_a_program.to_account_info(),
+                token_a_account_ai.clone(),
+                leftover_token_a_account.to_account_info(),
+                &ctx.accounts.token_a_mint,
+                ctx.accounts.owner.to_account_info(),
+                ledger.amount_a,
+            )?;
+            transfer_from_user(
+                ctx.accounts.token_b_program.to_account_info(),
+                token_b_account_ai.clone(),
+                leftover_token_b_account.to_account_info(),
+                &ctx.accounts.token_b_mint,
+                ctx.accounts.owner.to_account_info(),
+                ledger.amount_b,
+            )?;
+        }
+        LeftoverPolicy::SwapToTokenA | LeftoverPolicy::SwapToTokenB => {
+            let (amount_in, trade_direction) = if leftover_policy == LeftoverPolicy::SwapToTokenA {
+                (ledger.amount_b, TradeDirection::BtoA)
+            } else {
+                (ledger.amount_a, TradeDirection::AtoB)
+            };
+            // leftover could be too small to get anything out, we just keep it in that case
+            let amount_out = if amount_in > 0 {
+                let pool = ctx.accounts.pool.load()?;
+                let current_point = ActivationHandler::get_current_point(pool.activation_type)?;
+                get_swap_out_amount(
+                    &pool,
+                    &token_a_transfer_fee_calculator,
+                    &token_b_transfer_fee_calculator,
+                    amount_in,
+                    trade_direction,
+                    current_point,
+                    ctx.accounts.referral_token_account.is_some(),
+                )?
+            } else {
+                0
+            };
+
+            if amount_out > 0 {
+                ctx.accounts
+                    .swap(amount_in, trade_direction, &ctx.remaining_accounts)?;
 
+                let pool = ctx.accounts.pool.load()?;
+                let sqrt_price_change_bps = get_price_change_bps(pre_sqrt_price, pool.sqrt_price)?;
+                require!(
+                    sqrt_price_change_bps <= max_sqrt_price_change_bps,
+                    ZapError::ExceededSlippage
+                );
+            }
+        }
+    }
+    let user_amount_a_6 = accessor::amount(&token_a_account_ai)?;
+    let user_amount_b_6 = accessor::amount(&token_b_account_ai)?;
+    if leftover_policy == LeftoverPolicy::SwapToTokenA {
+        leftover_swap_in_amount = user_amount_b_5.safe_sub(user_amount_b_6)?;
+        leftover_swap_out_amount = user_amount_a_6.safe_sub(user_amount_a_5)?;
+    } else if leftover_policy == LeftoverPolicy::SwapToTokenB {
+        leftover_swap_in_amount = user_amount_a_5.safe_sub(user_amount_a_6)?;
+        leftover_swap_out_amount = user_amount_b_6.safe_sub(user_amount_b_5)?;
+    }
     ledger.update_ledger_balances(
-        user_amount_a_3,
-        user_amount_a_4,
-        user_amount_b_3,
-        user_amount_b_4,
+        user_amount_a_5,
+        user_amount_a_6,
+        user_amount_b_5,
+        user_amount_b_6,
     )?;
 
     // log will be truncated, shouldn't rely on that
@@ -294,5 +734,15 @@ pub fn handle_zap_in_damm_v2<'c: 'info, 'info>(
         ledger.amount_b
     );
 
-    Ok(())
+    Ok(ZapInResult {
+        liquidity_delta: total_liquidity_delta,
+        amount_a_deposited,
+        amount_b_deposited,
+        swap_in_amount,
+        swap_out_amount,
+        remaining_amount_a: ledger.amount_a,
+        remaining_amount_b: ledger.amount_b,
+        leftover_swap_in_amount,
+        leftover_swap_out_amount,
+    })
 }
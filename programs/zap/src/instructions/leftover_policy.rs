@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+/// What to do with remaining ledger balances after zap in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeftoverPolicy {
+    // keep leftover in user token accounts
+    Keep,
+    // transfer leftover to leftover token accounts
+    Transfer,
+    // swap leftover token b (y in dlmm) to token a (x in dlmm) via the same pool
+    SwapToTokenA,
+    // swap leftover token a (x in dlmm) to token b (y in dlmm) via the same pool
+    SwapToTokenB,
+}
@@ -2,7 +2,15 @@ pub mod ix_zap_out;
 pub use ix_zap_out::*;
 pub mod ix_zap_in_damm_v2;
 pub use ix_zap_in_damm_v2::*;
+pub mod ix_quote_zap_in_damm_v2;
+pub use ix_quote_zap_in_damm_v2::*;
+pub mod ix_compound_damm_v2_position;
+pub use ix_compound_damm_v2_position::*;
 pub mod ledger_instructions;
 pub use ledger_instructions::*;
 pub mod zap_in_dlmm;
 pub use zap_in_dlmm::*;
+pub mod zap_in_result;
+pub use zap_in_result::*;
+pub mod leftover_policy;
+pub use leftover_policy::*;
@@ -0,0 +1,364 @@
+use std::mem::size_of;
+
+use anchor_lang::prelude::*;
+use anchor_spl::{token::accessor, token_interface::Mint};
+use damm_v2::safe_math::SafeMath;
+use dlmm::{
+    accounts::{BinArray, LbPair},
+    types::{RebalanceLiquidityParams, RemainingAccountsInfo},
+};
+
+use crate::{
+    bin_id_to_bin_array_index, derive_bin_array, error::ZapError, get_dlmm_swap_out_amount,
+    is_overflow_default_bin_array_bitmap, transfer_from_user, validate_active_bin_slippage,
+    validate_active_price_deviation, LeftoverPolicy, UserLedger,
+};
+
+/// Accounts shared by dlmm zap in and rebalance instructions, swap and leftover handling go through user token accounts,
+/// missing bin arrays are created with rent paid by rent payer
+pub trait DlmmZapInAccounts<'info> {
+    fn lb_pair(&self) -> &AccountLoader<'info, LbPair>;
+
+    fn bin_array_bitmap_extension(&self) -> Option<AccountInfo<'info>>;
+
+    fn user_token_x(&self) -> AccountInfo<'info>;
+
+    fn user_token_y(&self) -> AccountInfo<'info>;
+
+    fn reserve_x(&self) -> AccountInfo<'info>;
+
+    fn reserve_y(&self) -> AccountInfo<'info>;
+
+    fn token_x_mint(&self) -> &InterfaceAccount<'info, Mint>;
+
+    fn token_y_mint(&self) -> &InterfaceAccount<'info, Mint>;
+
+    fn dlmm_program(&self) -> AccountInfo<'info>;
+
+    fn owner(&self) -> AccountInfo<'info>;
+
+    fn rent_payer(&self) -> AccountInfo<'info>;
+
+    fn token_x_program(&self) -> AccountInfo<'info>;
+
+    fn token_y_program(&self) -> AccountInfo<'info>;
+
+    fn memo_program(&self) -> AccountInfo<'info>;
+
+    fn system_program(&self) -> AccountInfo<'info>;
+
+    fn dlmm_event_authority(&self) -> AccountInfo<'info>;
+
+    fn oracle(&self) -> Option<AccountInfo<'info>>;
+
+    fn leftover_token_x_account(&self) -> Option<AccountInfo<'info>>;
+
+    fn leftover_token_y_account(&self) -> Option<AccountInfo<'info>>;
+
+    fn rent(&self) -> Option<AccountInfo<'info>>;
+
+    fn rebalance_liquidity(
+        &self,
+        position: AccountInfo<'info>,
+        params: RebalanceLiquidityParams,
+        remaining_accounts: &[AccountInfo<'info>],
+        remaining_accounts_info: RemainingAccountsInfo,
+    ) -> Result<()> {
+        dlmm::cpi::rebalance_liquidity(
+            CpiContext::new(
+                self.dlmm_program(),
+                dlmm::cpi::accounts::RebalanceLiquidity {
+                    position,
+                    lb_pair: self.lb_pair().to_account_info(),
+                    bin_array_bitmap_extension: self.bin_array_bitmap_extension(),
+                    owner: self.owner(),
+                    user_token_x: self.user_token_x(),
+                    user_token_y: self.user_token_y(),
+                    reserve_x: self.reserve_x(),
+                    reserve_y: self.reserve_y(),
+                    token_x_mint: self.token_x_mint().to_account_info(),
+                    token_y_mint: self.token_y_mint().to_account_info(),
+                    rent_payer: self.rent_payer(),
+                    token_x_program: self.token_x_program(),
+                    token_y_program: self.token_y_program(),
+                    memo_program: self.memo_program(),
+                    system_program: self.system_program(),
+                    program: self.dlmm_program(),
+                    event_authority: self.dlmm_event_authority(),
+                },
+            )
+            .with_remaining_accounts(remaining_accounts.to_vec()),
+            params,
+            remaining_accounts_info,
+        )
+    }
+
+    fn swap(
+        &self,
+        amount_in: u64,
+        swap_for_y: bool,
+        remaining_accounts: &[AccountInfo<'info>],
+        remaining_accounts_info: RemainingAccountsInfo,
+    ) -> Result<()> {
+        let oracle = self.oracle().ok_or(ZapError::MissingDlmmOracle)?;
+        let (user_token_in, user_token_out) = if swap_for_y {
+            (self.user_token_x(), self.user_token_y())
+        } else {
+            (self.user_token_y(), self.user_token_x())
+        };
+        dlmm::cpi::swap2(
+            CpiContext::new(
+                self.dlmm_program(),
+                dlmm::cpi::accounts::Swap2 {
+                    lb_pair: self.lb_pair().to_account_info(),
+                    bin_array_bitmap_extension: self.bin_array_bitmap_extension(),
+                    reserve_x: self.reserve_x(),
+                    reserve_y: self.reserve_y(),
+                    user_token_in,
+                    user_token_out,
+                    token_x_mint: self.token_x_mint().to_account_info(),
+                    token_y_mint: self.token_y_mint().to_account_info(),
+                    oracle,
+                    host_fee_in: None,
+                    user: self.owner(),
+                    token_x_program: self.token_x_program(),
+                    token_y_program: self.token_y_program(),
+                    memo_program: self.memo_program(),
+                    event_authority: self.dlmm_event_authority(),
+                    program: self.dlmm_program(),
+                },
+            )
+            .with_remaining_accounts(remaining_accounts.to_vec()),
+            amount_in,
+            0, // slippage is checked by active bin after swap
+            remaining_accounts_info,
+        )?;
+        Ok(())
+    }
+
+    // swap from ledger balances and validate active bin after swap, returns amount out
+    fn swap_and_update_ledger(
+        &self,
+        ledger: &mut UserLedger,
+        amount_in: u64,
+        swap_for_y: bool,
+        active_id: i32,
+        max_active_bin_slippage: u16,
+        max_price_deviation_bps: Option<u16>,
+        remaining_accounts: &[AccountInfo<'info>],
+        remaining_accounts_info: RemainingAccountsInfo,
+    ) -> Result<u64> {
+        let token_x_account_ai = self.user_token_x();
+        let token_y_account_ai = self.user_token_y();
+        let pre_amount_x = accessor::amount(&token_x_account_ai)?;
+        let pre_amount_y = accessor::amount(&token_y_account_ai)?;
+        self.swap(
+            amount_in,
+            swap_for_y,
+            remaining_accounts,
+            remaining_accounts_info,
+        )?;
+        let post_amount_x = accessor::amount(&token_x_account_ai)?;
+        let post_amount_y = accessor::amount(&token_y_account_ai)?;
+        ledger.update_ledger_balances(pre_amount_x, post_amount_x, pre_amount_y, post_amount_y)?;
+
+        validate_active_bin_slippage(self.lb_pair(), active_id, max_active_bin_slippage)?;
+        validate_active_price_deviation(self.lb_pair(), active_id, max_price_deviation_bps)?;
+
+        let amount_out = if swap_for_y {
+            post_amount_y.safe_sub(pre_amount_y)?
+        } else {
+            post_amount_x.safe_sub(pre_amount_x)?
+        };
+        Ok(amount_out)
+    }
+
+    fn transfer_leftover(&self, amount_x: u64, amount_y: u64) -> Result<()> {
+        let (Some(leftover_token_x_account), Some(leftover_token_y_account)) = (
+            self.leftover_token_x_account(),
+            self.leftover_token_y_account(),
+        ) else {
+            return Err(ZapError::MissingLeftoverAccounts.into());
+        };
+        transfer_from_user(
+            self.token_x_program(),
+            self.user_token_x(),
+            leftover_token_x_account,
+            self.token_x_mint(),
+            self.owner(),
+            amount_x,
+        )?;
+        transfer_from_user(
+            self.token_y_program(),
+            self.user_token_y(),
+            leftover_token_y_account,
+            self.token_y_mint(),
+            self.owner(),
+            amount_y,
+        )
+    }
+
+    // apply leftover policy to remaining ledger balances after deposit, returns (swap in amount, swap out amount)
+    // of the leftover swap
+    fn handle_leftover(
+        &self,
+        ledger: &mut UserLedger,
+        leftover_policy: LeftoverPolicy,
+        active_id: i32,
+        max_active_bin_slippage: u16,
+        max_price_deviation_bps: Option<u16>,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        remaining_accounts_info: RemainingAccountsInfo,
+    ) -> Result<(u64, u64)> {
+        match leftover_policy {
+            LeftoverPolicy::Keep => {}
+            LeftoverPolicy::Transfer => {
+                let token_x_account_ai = self.user_token_x();
+                let token_y_account_ai = self.user_token_y();
+                let pre_amount_x = accessor::amount(&token_x_account_ai)?;
+                let pre_amount_y = accessor::amount(&token_y_account_ai)?;
+                self.transfer_leftover(ledger.amount_a, ledger.amount_b)?;
+                ledger.update_ledger_balances(
+                    pre_amount_x,
+                    accessor::amount(&token_x_account_ai)?,
+                    pre_amount_y,
+                    accessor::amount(&token_y_account_ai)?,
+                )?;
+            }
+            LeftoverPolicy::SwapToTokenA | LeftoverPolicy::SwapToTokenB => {
+                let swap_for_y = leftover_policy == LeftoverPolicy::SwapToTokenB;
+                let amount_in = if swap_for_y {
+                    ledger.amount_a
+                } else {
+                    ledger.amount_b
+                };
+                // dust that swaps to nothing is kept
+                let amount_out = if amount_in > 0 {
+                    get_dlmm_swap_out_amount(
+                        self.lb_pair(),
+                        remaining_accounts,
+                        self.token_x_mint(),
+                        self.token_y_mint(),
+                        amount_in,
+                        swap_for_y,
+                    )?
+                } else {
+                    0
+                };
+                if amount_out > 0 {
+                    let swap_out_amount = self.swap_and_update_ledger(
+                        ledger,
+                        amount_in,
+                        swap_for_y,
+                        active_id,
+                        max_active_bin_slippage,
+                        max_price_deviation_bps,
+                        remaining_accounts,
+                        remaining_accounts_info,
+                    )?;
+                    return Ok((amount_in, swap_out_amount));
+                }
+            }
+        }
+        Ok((0, 0))
+    }
+
+    // bin arrays of [lower_bin_id, upper_bin_id] in remaining accounts that don't exist yet are initialized,
+    // bin arrays not in remaining accounts are left to dlmm to validate
+    fn initialize_bin_arrays(
+        &self,
+        remaining_accounts: &[AccountInfo<'info>],
+        lower_bin_id: i32,
+        upper_bin_id: i32,
+    ) -> Result<()> {
+        let lower_index = bin_id_to_bin_array_index(lower_bin_id);
+        let upper_index = bin_id_to_bin_array_index(upper_bin_id);
+
+        if is_overflow_default_bin_array_bitmap(lower_index)
+            || is_overflow_default_bin_array_bitmap(upper_index)
+        {
+            if let Some(bin_array_bitmap_extension) = self
+                .bin_array_bitmap_extension()
+                .filter(is_uninitialized_account)
+            {
+                let rent = self.rent().ok_or(ZapError::MissingRentSysvar)?;
+                dlmm::cpi::initialize_bin_array_bitmap_extension(CpiContext::new(
+                    self.dlmm_program(),
+                    dlmm::cpi::accounts::InitializeBinArrayBitmapExtension {
+                        lb_pair: self.lb_pair().to_account_info(),
+                        bin_array_bitmap_extension,
+                        funder: self.rent_payer(),
+                        system_program: self.system_program(),
+                        rent,
+                    },
+                ))?;
+            }
+        }
+
+        let uninitialized_accounts = remaining_accounts
+            .iter()
+            .filter(|account| is_uninitialized_account(account))
+            .collect::<Vec<_>>();
+        // skip pda derivation when all passed accounts exist
+        if uninitialized_accounts.is_empty() {
+            return Ok(());
+        }
+
+        // indices of existing bin arrays are read from their data, only the missing ones are derived
+        // until every uninitialized account is matched
+        let lb_pair = self.lb_pair().to_account_info();
+        let disc_len = BinArray::DISCRIMINATOR.len();
+        let mut existing_indices = vec![];
+        for account in remaining_accounts.iter() {
+            if account.owner != &dlmm::ID {
+                continue;
+            }
+            let data = account.try_borrow_data()?;
+            if !data.starts_with(BinArray::DISCRIMINATOR) {
+                continue;
+            }
+            if let Some(bin_array) = data
+                .get(disc_len..disc_len.safe_add(size_of::<BinArray>())?)
+                .map(bytemuck::from_bytes::<BinArray>)
+                .filter(|bin_array| bin_array.lb_pair == *lb_pair.key)
+            {
+                existing_indices.push(bin_array.index);
+            }
+        }
+
+        let mut unmatched_count = uninitialized_accounts.len();
+        for index in lower_index..=upper_index {
+            if unmatched_count == 0 {
+                break;
+            }
+            if existing_indices.contains(&index) {
+                continue;
+            }
+            let bin_array_key = derive_bin_array(lb_pair.key, index);
+            let Some(bin_array) = uninitialized_accounts
+                .iter()
+                .find(|account| account.key == &bin_array_key)
+            else {
+                continue;
+            };
+            unmatched_count = unmatched_count.safe_sub(1)?;
+            dlmm::cpi::initialize_bin_array(
+                CpiContext::new(
+                    self.dlmm_program(),
+                    dlmm::cpi::accounts::InitializeBinArray {
+                        lb_pair: lb_pair.clone(),
+                        bin_array: (*bin_array).clone(),
+                        funder: self.rent_payer(),
+                        system_program: self.system_program(),
+                    },
+                ),
+                index,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn is_uninitialized_account(account: &AccountInfo) -> bool {
+    account.owner == &anchor_lang::system_program::ID && account.data_is_empty()
+}
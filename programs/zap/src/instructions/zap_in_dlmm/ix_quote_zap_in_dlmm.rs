@@ -0,0 +1,38 @@
+use anchor_lang::{prelude::*, solana_program::program::MAX_RETURN_DATA};
+use dlmm::accounts::LbPair;
+
+use crate::{error::ZapError, get_zap_in_dlmm_quote, StrategyType, ZapInDlmmQuote};
+
+#[derive(Accounts)]
+pub struct QuoteZapInDlmmCtx<'info> {
+    pub lb_pair: AccountLoader<'info, LbPair>,
+}
+
+pub fn handle_quote_zap_in_dlmm(
+    ctx: Context<QuoteZapInDlmmCtx>,
+    amount_x: u64,
+    amount_y: u64,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    favor_x_in_active_id: bool,
+    strategy: StrategyType,
+) -> Result<ZapInDlmmQuote> {
+    let lb_pair = ctx.accounts.lb_pair.load()?;
+    let quote = get_zap_in_dlmm_quote(
+        &lb_pair,
+        amount_x,
+        amount_y,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        strategy,
+    )?;
+
+    // return data is limited, so range is too wide to be quoted in one call
+    require!(
+        quote.try_to_vec()?.len() <= MAX_RETURN_DATA,
+        ZapError::InvalidDlmmZapInParameters
+    );
+
+    Ok(quote)
+}
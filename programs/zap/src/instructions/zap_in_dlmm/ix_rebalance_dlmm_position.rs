@@ -0,0 +1,477 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{token::accessor, token_interface::Mint};
+use damm_v2::{safe_math::SafeMath, token::calculate_transfer_fee_excluded_amount};
+use dlmm::{
+    accounts::LbPair,
+    types::{RebalanceLiquidityParams, RemainingAccountsInfo, RemoveLiquidityParams},
+};
+
+use crate::{
+    constants::MAX_BASIS_POINT, error::ZapError, get_claim_fee_and_reward_params,
+    get_deposit_bin_range, get_dlmm_position_withdraw_amounts, get_dlmm_swap_amount,
+    get_position_total_liquidity_share, validate_active_bin_slippage,
+    validate_active_price_deviation, DlmmZapInAccounts, LeftoverPolicy,
+    RebalanceDlmmPositionResult, StrategyType, UserLedger, ZapInRebalancingParams, NO_SHRINK_BOTH,
+};
+
+#[derive(Accounts)]
+pub struct RebalanceDlmmPositionCtx<'info> {
+    /// lb pair
+    #[account(mut)]
+    pub lb_pair: AccountLoader<'info, LbPair>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub bin_array_bitmap_extension: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub user_token_x: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub user_token_y: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub reserve_x: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub reserve_y: UncheckedAccount<'info>,
+
+    pub token_x_mint: InterfaceAccount<'info, Mint>,
+    pub token_y_mint: InterfaceAccount<'info, Mint>,
+
+    pub dlmm_program: Program<'info, dlmm::program::LbClmm>,
+
+    /// owner of position
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub rent_payer: Signer<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    pub token_x_program: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    pub token_y_program: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    pub memo_program: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    pub system_program: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    pub dlmm_event_authority: UncheckedAccount<'info>,
+
+    /// CHECK: oracle of lb pair, will be validated in dlmm program
+    #[account(mut)]
+    pub oracle: UncheckedAccount<'info>,
+
+    /// CHECK: token x account receives leftover, only needed for transfer leftover policy, will be checked in token program
+    #[account(mut)]
+    pub leftover_token_x_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: token y account receives leftover, only needed for transfer leftover policy, will be checked in token program
+    #[account(mut)]
+    pub leftover_token_y_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: rent sysvar, only needed to initialize bin array bitmap extension, will be validated in dlmm program
+    pub rent: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> DlmmZapInAccounts<'info> for RebalanceDlmmPositionCtx<'info> {
+    fn lb_pair(&self) -> &AccountLoader<'info, LbPair> {
+        &self.lb_pair
+    }
+
+    fn bin_array_bitmap_extension(&self) -> Option<AccountInfo<'info>> {
+        self.bin_array_bitmap_extension
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn user_token_x(&self) -> AccountInfo<'info> {
+        self.user_token_x.to_account_info()
+    }
+
+    fn user_token_y(&self) -> AccountInfo<'info> {
+        self.user_token_y.to_account_info()
+    }
+
+    fn reserve_x(&self) -> AccountInfo<'info> {
+        self.reserve_x.to_account_info()
+    }
+
+    fn reserve_y(&self) -> AccountInfo<'info> {
+        self.reserve_y.to_account_info()
+    }
+
+    fn token_x_mint(&self) -> &InterfaceAccount<'info, Mint> {
+        &self.token_x_mint
+    }
+
+    fn token_y_mint(&self) -> &InterfaceAccount<'info, Mint> {
+        &self.token_y_mint
+    }
+
+    fn dlmm_program(&self) -> AccountInfo<'info> {
+        self.dlmm_program.to_account_info()
+    }
+
+    fn owner(&self) -> AccountInfo<'info> {
+        self.owner.to_account_info()
+    }
+
+    fn rent_payer(&self) -> AccountInfo<'info> {
+        self.rent_payer.to_account_info()
+    }
+
+    fn token_x_program(&self) -> AccountInfo<'info> {
+        self.token_x_program.to_account_info()
+    }
+
+    fn token_y_program(&self) -> AccountInfo<'info> {
+        self.token_y_program.to_account_info()
+    }
+
+    fn memo_program(&self) -> AccountInfo<'info> {
+        self.memo_program.to_account_info()
+    }
+
+    fn system_program(&self) -> AccountInfo<'info> {
+        self.system_program.to_account_info()
+    }
+
+    fn dlmm_event_authority(&self) -> AccountInfo<'info> {
+        self.dlmm_event_authority.to_account_info()
+    }
+
+    fn oracle(&self) -> Option<AccountInfo<'info>> {
+        Some(self.oracle.to_account_info())
+    }
+
+    fn leftover_token_x_account(&self) -> Option<AccountInfo<'info>> {
+        self.leftover_token_x_account
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn leftover_token_y_account(&self) -> Option<AccountInfo<'info>> {
+        self.leftover_token_y_account
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn rent(&self) -> Option<AccountInfo<'info>> {
+        self.rent.as_ref().map(|account| account.to_account_info())
+    }
+}
+
+// withdraw all liquidity of position, swap the surplus side through the lb pair if needed and add back around active bin,
+// amounts are tracked by balance changes of user token accounts, so other tokens in those accounts are untouched,
+// leftover policy applies to what is left of the rebalance
+pub fn handle_rebalance_dlmm_position<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, RebalanceDlmmPositionCtx<'info>>,
+    active_id: i32,
+    max_active_bin_slippage: u16,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    favor_x_in_active_id: bool,
+    strategy: StrategyType,
+    remaining_accounts_info: RemainingAccountsInfo,
+    should_claim_fee: bool,
+    should_claim_reward: bool,
+    compound: bool,
+    max_price_deviation_bps: Option<u16>,
+    shrink_mode: u8,
+    leftover_policy: LeftoverPolicy,
+) -> Result<RebalanceDlmmPositionResult> {
+    require!(
+        min_delta_id <= max_delta_id,
+        ZapError::InvalidDlmmZapInParameters
+    );
+    require!(
+        !compound || should_claim_fee || should_claim_reward,
+        ZapError::InvalidDlmmZapInParameters
+    );
+    require!(
+        shrink_mode <= NO_SHRINK_BOTH,
+        ZapError::InvalidDlmmZapInParameters
+    );
+    validate_active_price_deviation(&ctx.accounts.lb_pair, active_id, max_price_deviation_bps)?;
+
+    // create missing bin arrays of the new range, the swap can move active id up to max_active_bin_slippage
+    let lb_pair_active_id = ctx.accounts.lb_pair.load()?.active_id;
+    let (lower_bin_id, upper_bin_id) = get_deposit_bin_range(
+        None,
+        min_delta_id,
+        max_delta_id,
+        lb_pair_active_id,
+        active_id,
+        max_active_bin_slippage,
+        true,
+    )?;
+    ctx.accounts
+        .initialize_bin_arrays(ctx.remaining_accounts, lower_bin_id, upper_bin_id)?;
+
+    let token_x_account_ai = ctx.accounts.user_token_x.to_account_info();
+    let token_y_account_ai = ctx.accounts.user_token_y.to_account_info();
+    let mut pre_user_amount_x = accessor::amount(&token_x_account_ai)?;
+    let mut pre_user_amount_y = accessor::amount(&token_y_account_ai)?;
+
+    let mut claimed_amount_x = 0;
+    let mut claimed_amount_y = 0;
+    if should_claim_fee || should_claim_reward {
+        ctx.accounts.rebalance_liquidity(
+            ctx.accounts.position.to_account_info(),
+            get_claim_fee_and_reward_params(
+                active_id,
+                max_active_bin_slippage,
+                should_claim_fee,
+                should_claim_reward,
+            ),
+            ctx.remaining_accounts,
+            remaining_accounts_info.clone(),
+        )?;
+        let post_claim_amount_x = accessor::amount(&token_x_account_ai)?;
+        let post_claim_amount_y = accessor::amount(&token_y_account_ai)?;
+        claimed_amount_x = post_claim_amount_x.safe_sub(pre_user_amount_x)?;
+        claimed_amount_y = post_claim_amount_y.safe_sub(pre_user_amount_y)?;
+        // claimed amounts are counted as withdrawn when compounding, otherwise they are left to user
+        if !compound {
+            pre_user_amount_x = post_claim_amount_x;
+            pre_user_amount_y = post_claim_amount_y;
+        }
+    }
+
+    let remove_all = vec![RemoveLiquidityParams {
+        min_bin_id: None,
+        max_bin_id: None,
+        bps: MAX_BASIS_POINT,
+        padding: [0; 16],
+    }];
+
+    // amounts to re-add are known before withdraw, so if they already match the strategy,
+    // liquidity is withdrawn and added back in one rebalance_liquidity
+    let (position_amount_x, position_amount_y) = get_dlmm_position_withdraw_amounts(
+        &ctx.accounts.position.to_account_info(),
+        &ctx.accounts.lb_pair,
+        ctx.remaining_accounts,
+        &ctx.accounts.token_x_mint,
+        &ctx.accounts.token_y_mint,
+    )?;
+    let expected_withdrawn_amount_x = accessor::amount(&token_x_account_ai)?
+        .safe_sub(pre_user_amount_x)?
+        .safe_add(position_amount_x)?;
+    let expected_withdrawn_amount_y = accessor::amount(&token_y_account_ai)?
+        .safe_sub(pre_user_amount_y)?
+        .safe_add(position_amount_y)?;
+    let (expected_swap_in_amount, _swap_for_y) = get_dlmm_swap_amount(
+        &ctx.accounts.lb_pair,
+        ctx.remaining_accounts,
+        &ctx.accounts.token_x_mint,
+        &ctx.accounts.token_y_mint,
+        expected_withdrawn_amount_x,
+        expected_withdrawn_amount_y,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        &strategy,
+    )?;
+
+    let withdrawn_amount_x;
+    let withdrawn_amount_y;
+    let mut swap_in_amount = 0;
+    let mut swap_out_amount = 0;
+    let removes;
+    let pre_deposit_amount_x;
+    let pre_deposit_amount_y;
+    if expected_swap_in_amount == 0 {
+        withdrawn_amount_x = expected_withdrawn_amount_x;
+        withdrawn_amount_y = expected_withdrawn_amount_y;
+        removes = remove_all;
+        pre_deposit_amount_x =
+            accessor::amount(&token_x_account_ai)?.safe_add(position_amount_x)?;
+        pre_deposit_amount_y =
+            accessor::amount(&token_y_account_ai)?.safe_add(position_amount_y)?;
+    } else {
+        // the swap needs withdrawn tokens and changes the active bin of the add, so it sits between
+        // a withdraw all and the add, position is kept to be resized in the add step
+        ctx.accounts.rebalance_liquidity(
+            ctx.accounts.position.to_account_info(),
+            RebalanceLiquidityParams {
+                active_id,
+                max_active_bin_slippage,
+                should_claim_fee: false, // claimed before withdraw
+                should_claim_reward: false,
+                min_withdraw_x_amount: 0,
+                max_deposit_x_amount: 0,
+                min_withdraw_y_amount: 0,
+                max_deposit_y_amount: 0,
+                shrink_mode: NO_SHRINK_BOTH,
+                padding: [0; 31],
+                removes: remove_all,
+                adds: vec![],
+            },
+            ctx.remaining_accounts,
+            remaining_accounts_info.clone(),
+        )?;
+
+        withdrawn_amount_x = accessor::amount(&token_x_account_ai)?.safe_sub(pre_user_amount_x)?;
+        withdrawn_amount_y = accessor::amount(&token_y_account_ai)?.safe_sub(pre_user_amount_y)?;
+
+        // swap the surplus side to the ratio of strategy
+        let swap_for_y;
+        (swap_in_amount, swap_for_y) = get_dlmm_swap_amount(
+            &ctx.accounts.lb_pair,
+            ctx.remaining_accounts,
+            &ctx.accounts.token_x_mint,
+            &ctx.accounts.token_y_mint,
+            withdrawn_amount_x,
+            withdrawn_amount_y,
+            min_delta_id,
+            max_delta_id,
+            favor_x_in_active_id,
+            &strategy,
+        )?;
+        if swap_in_amount > 0 {
+            let pre_swap_amount_out = if swap_for_y {
+                accessor::amount(&token_y_account_ai)?
+            } else {
+                accessor::amount(&token_x_account_ai)?
+            };
+            ctx.accounts.swap(
+                swap_in_amount,
+                swap_for_y,
+                ctx.remaining_accounts,
+                remaining_accounts_info.clone(),
+            )?;
+            let post_swap_amount_out = if swap_for_y {
+                accessor::amount(&token_y_account_ai)?
+            } else {
+                accessor::amount(&token_x_account_ai)?
+            };
+            swap_out_amount = post_swap_amount_out.safe_sub(pre_swap_amount_out)?;
+            validate_active_bin_slippage(
+                &ctx.accounts.lb_pair,
+                active_id,
+                max_active_bin_slippage,
+            )?;
+            validate_active_price_deviation(
+                &ctx.accounts.lb_pair,
+                active_id,
+                max_price_deviation_bps,
+            )?;
+        }
+        removes = vec![];
+        pre_deposit_amount_x = accessor::amount(&token_x_account_ai)?;
+        pre_deposit_amount_y = accessor::amount(&token_y_account_ai)?;
+    }
+
+    let max_deposit_x_amount = pre_deposit_amount_x.safe_sub(pre_user_amount_x)?;
+    let max_deposit_y_amount = pre_deposit_amount_y.safe_sub(pre_user_amount_y)?;
+
+    let amount_x = calculate_transfer_fee_excluded_amount(
+        &ctx.accounts
+            .token_x_mint
+            .to_account_info()
+            .try_borrow_data()?,
+        max_deposit_x_amount,
+    )?
+    .amount;
+    let amount_y = calculate_transfer_fee_excluded_amount(
+        &ctx.accounts
+            .token_y_mint
+            .to_account_info()
+            .try_borrow_data()?,
+        max_deposit_y_amount,
+    )?
+    .amount;
+
+    let lb_pair = ctx.accounts.lb_pair.load()?;
+    let params = ZapInRebalancingParams {
+        amount_x,
+        amount_y,
+        active_id: lb_pair.active_id,
+        bin_step: lb_pair.bin_step,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        strategy,
+    };
+    drop(lb_pair);
+
+    let adds = params.get_add_liquidity_params()?;
+
+    // add around active bin (after withdraw all if not done yet), empty bins at the sides allowed by shrink_mode are removed
+    ctx.accounts.rebalance_liquidity(
+        ctx.accounts.position.to_account_info(),
+        RebalanceLiquidityParams {
+            active_id,
+            max_active_bin_slippage,
+            should_claim_fee: false, // claimed before withdraw
+            should_claim_reward: false,
+            min_withdraw_x_amount: 0,
+            max_deposit_x_amount,
+            min_withdraw_y_amount: 0,
+            max_deposit_y_amount,
+            shrink_mode,
+            padding: [0; 31],
+            removes,
+            adds,
+        },
+        ctx.remaining_accounts,
+        remaining_accounts_info.clone(),
+    )?;
+
+    let post_user_amount_x = accessor::amount(&token_x_account_ai)?;
+    let post_user_amount_y = accessor::amount(&token_y_account_ai)?;
+
+    // no ledger account, leftover is the balance changes of user token accounts
+    let mut ledger = UserLedger {
+        owner: ctx.accounts.owner.key(),
+        amount_a: post_user_amount_x.safe_sub(pre_user_amount_x)?,
+        amount_b: post_user_amount_y.safe_sub(pre_user_amount_y)?,
+    };
+    let (leftover_swap_in_amount, leftover_swap_out_amount) = ctx.accounts.handle_leftover(
+        &mut ledger,
+        leftover_policy,
+        active_id,
+        max_active_bin_slippage,
+        max_price_deviation_bps,
+        ctx.remaining_accounts,
+        remaining_accounts_info,
+    )?;
+
+    // log will be truncated, shouldn't rely on that
+    msg!(
+        "withdrawn_amounts: {} {}, max_deposit_amounts: {} {}",
+        withdrawn_amount_x,
+        withdrawn_amount_y,
+        max_deposit_x_amount,
+        max_deposit_y_amount
+    );
+
+    Ok(RebalanceDlmmPositionResult {
+        amount_x_claimed: claimed_amount_x,
+        amount_y_claimed: claimed_amount_y,
+        amount_x_withdrawn: withdrawn_amount_x,
+        amount_y_withdrawn: withdrawn_amount_y,
+        swap_in_amount,
+        swap_out_amount,
+        amount_x_deposited: pre_deposit_amount_x.safe_sub(post_user_amount_x)?,
+        amount_y_deposited: pre_deposit_amount_y.safe_sub(post_user_amount_y)?,
+        liquidity_share: get_position_total_liquidity_share(&ctx.accounts.position)?,
+        remaining_amount_x: ledger.amount_a,
+        remaining_amount_y: ledger.amount_b,
+        leftover_swap_in_amount,
+        leftover_swap_out_amount,
+    })
+}
@@ -0,0 +1,356 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{token::accessor, token_interface::Mint};
+use damm_v2::{safe_math::SafeMath, token::calculate_transfer_fee_excluded_amount};
+use dlmm::{
+    accounts::LbPair,
+    types::{RebalanceLiquidityParams, RemainingAccountsInfo},
+};
+
+use crate::{
+    clip_add_liquidity_ranges, error::ZapError, get_position_liquidity_shares,
+    get_position_total_liquidity_share, new_transfer_fee_calculator,
+    validate_active_price_deviation, validate_position_ranges, DlmmZapInAccounts, LeftoverPolicy,
+    StrategyType, UserLedger, ZapInRebalancingParams, ZapInResult, NO_SHRINK_BOTH,
+};
+
+#[derive(Accounts)]
+pub struct ZapInDlmmForMultiplePositionsCtx<'info> {
+    #[account(mut, has_one = owner)]
+    pub ledger: AccountLoader<'info, UserLedger>,
+
+    /// lb pair
+    #[account(mut)]
+    pub lb_pair: AccountLoader<'info, LbPair>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub bin_array_bitmap_extension: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub user_token_x: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub user_token_y: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub reserve_x: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    #[account(mut)]
+    pub reserve_y: UncheckedAccount<'info>,
+
+    pub token_x_mint: InterfaceAccount<'info, Mint>,
+    pub token_y_mint: InterfaceAccount<'info, Mint>,
+
+    pub dlmm_program: Program<'info, dlmm::program::LbClmm>,
+
+    /// owner of positions
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub rent_payer: Signer<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    pub token_x_program: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    pub token_y_program: UncheckedAccount<'info>,
+
+    /// CHECK: will be validated in dlmm program
+    pub memo_program: UncheckedAccount<'info>,
+    /// CHECK: will be validated in dlmm program
+    pub system_program: UncheckedAccount<'info>,
+    /// CHECK: will be validated in dlmm program
+    pub dlmm_event_authority: UncheckedAccount<'info>,
+    /// CHECK: oracle of lb pair, only needed for swap leftover policy, will be validated in dlmm program
+    #[account(mut)]
+    pub oracle: Option<UncheckedAccount<'info>>,
+    /// CHECK: token x account receives leftover, only needed for transfer leftover policy, will be checked in token program
+    #[account(mut)]
+    pub leftover_token_x_account: Option<UncheckedAccount<'info>>,
+    /// CHECK: token y account receives leftover, only needed for transfer leftover policy, will be checked in token program
+    #[account(mut)]
+    pub leftover_token_y_account: Option<UncheckedAccount<'info>>,
+    /// CHECK: rent sysvar, only needed to initialize bin array bitmap extension, will be validated in dlmm program
+    pub rent: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> DlmmZapInAccounts<'info> for ZapInDlmmForMultiplePositionsCtx<'info> {
+    fn lb_pair(&self) -> &AccountLoader<'info, LbPair> {
+        &self.lb_pair
+    }
+
+    fn bin_array_bitmap_extension(&self) -> Option<AccountInfo<'info>> {
+        self.bin_array_bitmap_extension
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn user_token_x(&self) -> AccountInfo<'info> {
+        self.user_token_x.to_account_info()
+    }
+
+    fn user_token_y(&self) -> AccountInfo<'info> {
+        self.user_token_y.to_account_info()
+    }
+
+    fn reserve_x(&self) -> AccountInfo<'info> {
+        self.reserve_x.to_account_info()
+    }
+
+    fn reserve_y(&self) -> AccountInfo<'info> {
+        self.reserve_y.to_account_info()
+    }
+
+    fn token_x_mint(&self) -> &InterfaceAccount<'info, Mint> {
+        &self.token_x_mint
+    }
+
+    fn token_y_mint(&self) -> &InterfaceAccount<'info, Mint> {
+        &self.token_y_mint
+    }
+
+    fn dlmm_program(&self) -> AccountInfo<'info> {
+        self.dlmm_program.to_account_info()
+    }
+
+    fn owner(&self) -> AccountInfo<'info> {
+        self.owner.to_account_info()
+    }
+
+    fn rent_payer(&self) -> AccountInfo<'info> {
+        self.rent_payer.to_account_info()
+    }
+
+    fn token_x_program(&self) -> AccountInfo<'info> {
+        self.token_x_program.to_account_info()
+    }
+
+    fn token_y_program(&self) -> AccountInfo<'info> {
+        self.token_y_program.to_account_info()
+    }
+
+    fn memo_program(&self) -> AccountInfo<'info> {
+        self.memo_program.to_account_info()
+    }
+
+    fn system_program(&self) -> AccountInfo<'info> {
+        self.system_program.to_account_info()
+    }
+
+    fn dlmm_event_authority(&self) -> AccountInfo<'info> {
+        self.dlmm_event_authority.to_account_info()
+    }
+
+    fn oracle(&self) -> Option<AccountInfo<'info>> {
+        self.oracle
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn leftover_token_x_account(&self) -> Option<AccountInfo<'info>> {
+        self.leftover_token_x_account
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn leftover_token_y_account(&self) -> Option<AccountInfo<'info>> {
+        self.leftover_token_y_account
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn rent(&self) -> Option<AccountInfo<'info>> {
+        self.rent.as_ref().map(|account| account.to_account_info())
+    }
+}
+
+// first position_count remaining accounts are initialized positions of adjacent ranges in ascending order,
+// the rest are transfer hook accounts and bin arrays of the whole range as other dlmm zap in
+pub fn handle_zap_in_dlmm_for_multiple_positions<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, ZapInDlmmForMultiplePositionsCtx<'info>>,
+    active_id: i32,
+    max_active_bin_slippage: u16,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    favor_x_in_active_id: bool,
+    strategy: StrategyType,
+    remaining_accounts_info: RemainingAccountsInfo,
+    position_count: u8,
+    max_price_deviation_bps: Option<u16>,
+    leftover_policy: LeftoverPolicy,
+) -> Result<ZapInResult> {
+    require!(
+        min_delta_id <= max_delta_id,
+        ZapError::InvalidDlmmZapInParameters
+    );
+    let position_count = usize::from(position_count);
+    require!(
+        position_count > 0 && ctx.remaining_accounts.len() >= position_count,
+        ZapError::InvalidPositionRanges
+    );
+    let (positions, remaining_accounts) = ctx.remaining_accounts.split_at(position_count);
+    validate_active_price_deviation(&ctx.accounts.lb_pair, active_id, max_price_deviation_bps)?;
+
+    let mut ledger = ctx.accounts.ledger.load_mut()?;
+
+    let amount_x = calculate_transfer_fee_excluded_amount(
+        &ctx.accounts
+            .token_x_mint
+            .to_account_info()
+            .try_borrow_data()?,
+        ledger.amount_a,
+    )?
+    .amount;
+    let amount_y = calculate_transfer_fee_excluded_amount(
+        &ctx.accounts
+            .token_y_mint
+            .to_account_info()
+            .try_borrow_data()?,
+        ledger.amount_b,
+    )?
+    .amount;
+
+    let lb_pair = ctx.accounts.lb_pair.load()?;
+    let lb_pair_active_id = lb_pair.active_id;
+    let params = ZapInRebalancingParams {
+        amount_x,
+        amount_y,
+        active_id: lb_pair_active_id,
+        bin_step: lb_pair.bin_step,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        strategy,
+    };
+    drop(lb_pair);
+
+    // the whole range is distributed by strategy, then each position takes the bins it covers
+    let ranges = params.get_parsed_add_liquidity_ranges()?;
+
+    let mut position_ranges = Vec::with_capacity(position_count);
+    for position in positions.iter() {
+        let (lower_bin_id, liquidity_shares) = get_position_liquidity_shares(position)?;
+        let width = i32::try_from(liquidity_shares.len()).map_err(|_| ZapError::TypeCastFailed)?;
+        position_ranges.push((lower_bin_id, lower_bin_id.safe_add(width)?.safe_sub(1)?));
+    }
+    validate_position_ranges(
+        &position_ranges,
+        lb_pair_active_id.safe_add(min_delta_id)?,
+        lb_pair_active_id.safe_add(max_delta_id)?,
+    )?;
+    // create missing bin arrays of the deposit range
+    ctx.accounts.initialize_bin_arrays(
+        remaining_accounts,
+        lb_pair_active_id.safe_add(min_delta_id)?,
+        lb_pair_active_id.safe_add(max_delta_id)?,
+    )?;
+
+    let token_x_account_ai = ctx.accounts.user_token_x.to_account_info();
+    let token_y_account_ai = ctx.accounts.user_token_y.to_account_info();
+    let pre_user_amount_x = accessor::amount(&token_x_account_ai)?;
+    let pre_user_amount_y = accessor::amount(&token_y_account_ai)?;
+
+    let token_x_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_x_mint)?;
+    let token_y_transfer_fee_calculator = new_transfer_fee_calculator(&ctx.accounts.token_y_mint)?;
+
+    let mut liquidity_delta: u128 = 0;
+    for (position, (lower_bin_id, upper_bin_id)) in positions.iter().zip(position_ranges) {
+        let position_params = ZapInRebalancingParams {
+            min_delta_id: lower_bin_id.safe_sub(lb_pair_active_id)?,
+            max_delta_id: upper_bin_id.safe_sub(lb_pair_active_id)?,
+            strategy: StrategyType::Spot, // ranges are already parsed
+            ..params
+        };
+        let clipped_ranges = clip_add_liquidity_ranges(
+            &ranges,
+            position_params.min_delta_id,
+            position_params.max_delta_id,
+        );
+        let adds = params.to_add_liquidity_params(&clipped_ranges)?;
+        if adds.is_empty() {
+            continue;
+        }
+
+        // cap deposit of each position at what its bins take, so it can't consume amounts of other positions
+        let (position_amount_x, position_amount_y) =
+            position_params.get_total_amount_of_ranges(&clipped_ranges)?;
+        let max_deposit_x_amount = token_x_transfer_fee_calculator
+            .calculate_transfer_fee_included_amount(position_amount_x)?
+            .min(ledger.amount_a);
+        let max_deposit_y_amount = token_y_transfer_fee_calculator
+            .calculate_transfer_fee_included_amount(position_amount_y)?
+            .min(ledger.amount_b);
+
+        let pre_amount_x = accessor::amount(&token_x_account_ai)?;
+        let pre_amount_y = accessor::amount(&token_y_account_ai)?;
+        let pre_liquidity_share = get_position_total_liquidity_share(position)?;
+
+        ctx.accounts.rebalance_liquidity(
+            position.clone(),
+            RebalanceLiquidityParams {
+                active_id,
+                max_active_bin_slippage,
+                should_claim_fee: false,
+                should_claim_reward: false,
+                min_withdraw_x_amount: 0,
+                max_deposit_x_amount,
+                min_withdraw_y_amount: 0,
+                max_deposit_y_amount,
+                shrink_mode: NO_SHRINK_BOTH, // positions keep their ranges
+                padding: [0; 31],
+                removes: vec![],
+                adds,
+            },
+            remaining_accounts,
+            remaining_accounts_info.clone(),
+        )?;
+
+        ledger.update_ledger_balances(
+            pre_amount_x,
+            accessor::amount(&token_x_account_ai)?,
+            pre_amount_y,
+            accessor::amount(&token_y_account_ai)?,
+        )?;
+        liquidity_delta = liquidity_delta.safe_add(
+            get_position_total_liquidity_share(position)?.safe_sub(pre_liquidity_share)?,
+        )?;
+    }
+
+    let post_user_amount_x = accessor::amount(&token_x_account_ai)?;
+    let post_user_amount_y = accessor::amount(&token_y_account_ai)?;
+
+    // handle leftover, positions aren't passed to the swap
+    let (leftover_swap_in_amount, leftover_swap_out_amount) = ctx.accounts.handle_leftover(
+        &mut ledger,
+        leftover_policy,
+        active_id,
+        max_active_bin_slippage,
+        max_price_deviation_bps,
+        remaining_accounts,
+        remaining_accounts_info,
+    )?;
+
+    // log will be truncated, shouldn't rely on that
+    msg!(
+        "position_count: {}, remaining_amounts: {} {}",
+        position_count,
+        ledger.amount_a,
+        ledger.amount_b
+    );
+
+    Ok(ZapInResult {
+        liquidity_delta,
+        amount_a_deposited: pre_user_amount_x.safe_sub(post_user_amount_x)?,
+        amount_b_deposited: pre_user_amount_y.safe_sub(post_user_amount_y)?,
+        swap_in_amount: 0,
+        swap_out_amount: 0,
+        remaining_amount_a: ledger.amount_a,
+        remaining_amount_b: ledger.amount_b,
+        leftover_swap_in_amount,
+        leftover_swap_out_amount,
+    })
+}
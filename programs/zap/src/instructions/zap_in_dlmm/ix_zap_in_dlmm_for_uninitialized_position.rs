@@ -1,12 +1,15 @@
 use crate::{
-    error::ZapError, StrategyType, UnparsedAddLiquidityParams, UserLedger, ZapInRebalancingParams,
+    error::ZapError, get_delta_id_range, get_deposit_bin_range, get_dlmm_single_token_swap_amount,
+    get_position_total_liquidity_share, validate_active_price_deviation, DlmmZapInAccounts,
+    LeftoverPolicy, PriceRange, StrategyType, UserLedger, ZapInRebalancingParams, ZapInResult,
+    NO_SHRINK_BOTH,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{token::accessor, token_interface::Mint};
 use damm_v2::{safe_math::SafeMath, token::calculate_transfer_fee_excluded_amount};
 use dlmm::{
     accounts::LbPair,
-    types::{AddLiquidityParams, RebalanceLiquidityParams, RemainingAccountsInfo},
+    types::{RebalanceLiquidityParams, RemainingAccountsInfo},
 };
 
 #[derive(Accounts)]
@@ -68,6 +71,21 @@ pub struct ZapInDlmmForUnintializedPositionCtx<'info> {
 
     /// CHECK: will be validated in dlmm program
     pub dlmm_event_authority: UncheckedAccount<'info>,
+
+    /// CHECK: oracle of lb pair, only needed for single token swap and swap leftover policy, will be validated in dlmm program
+    #[account(mut)]
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: token x account receives leftover, only needed for transfer leftover policy, will be checked in token program
+    #[account(mut)]
+    pub leftover_token_x_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: token y account receives leftover, only needed for transfer leftover policy, will be checked in token program
+    #[account(mut)]
+    pub leftover_token_y_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: rent sysvar, only needed to initialize bin array bitmap extension, will be validated in dlmm program
+    pub rent: Option<UncheckedAccount<'info>>,
 }
 
 impl<'info> ZapInDlmmForUnintializedPositionCtx<'info> {
@@ -92,6 +110,96 @@ impl<'info> ZapInDlmmForUnintializedPositionCtx<'info> {
     }
 }
 
+impl<'info> DlmmZapInAccounts<'info> for ZapInDlmmForUnintializedPositionCtx<'info> {
+    fn lb_pair(&self) -> &AccountLoader<'info, LbPair> {
+        &self.lb_pair
+    }
+
+    fn bin_array_bitmap_extension(&self) -> Option<AccountInfo<'info>> {
+        self.bin_array_bitmap_extension
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn user_token_x(&self) -> AccountInfo<'info> {
+        self.user_token_x.to_account_info()
+    }
+
+    fn user_token_y(&self) -> AccountInfo<'info> {
+        self.user_token_y.to_account_info()
+    }
+
+    fn reserve_x(&self) -> AccountInfo<'info> {
+        self.reserve_x.to_account_info()
+    }
+
+    fn reserve_y(&self) -> AccountInfo<'info> {
+        self.reserve_y.to_account_info()
+    }
+
+    fn token_x_mint(&self) -> &InterfaceAccount<'info, Mint> {
+        &self.token_x_mint
+    }
+
+    fn token_y_mint(&self) -> &InterfaceAccount<'info, Mint> {
+        &self.token_y_mint
+    }
+
+    fn dlmm_program(&self) -> AccountInfo<'info> {
+        self.dlmm_program.to_account_info()
+    }
+
+    fn owner(&self) -> AccountInfo<'info> {
+        self.owner.to_account_info()
+    }
+
+    fn rent_payer(&self) -> AccountInfo<'info> {
+        self.rent_payer.to_account_info()
+    }
+
+    fn token_x_program(&self) -> AccountInfo<'info> {
+        self.token_x_program.to_account_info()
+    }
+
+    fn token_y_program(&self) -> AccountInfo<'info> {
+        self.token_y_program.to_account_info()
+    }
+
+    fn memo_program(&self) -> AccountInfo<'info> {
+        self.memo_program.to_account_info()
+    }
+
+    fn system_program(&self) -> AccountInfo<'info> {
+        self.system_program.to_account_info()
+    }
+
+    fn dlmm_event_authority(&self) -> AccountInfo<'info> {
+        self.dlmm_event_authority.to_account_info()
+    }
+
+    fn oracle(&self) -> Option<AccountInfo<'info>> {
+        self.oracle
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn leftover_token_x_account(&self) -> Option<AccountInfo<'info>> {
+        self.leftover_token_x_account
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn leftover_token_y_account(&self) -> Option<AccountInfo<'info>> {
+        self.leftover_token_y_account
+            .as_ref()
+            .map(|account| account.to_account_info())
+    }
+
+    fn rent(&self) -> Option<AccountInfo<'info>> {
+        self.rent.as_ref().map(|account| account.to_account_info())
+    }
+}
+
 pub fn handle_zap_in_dlmm_for_uninitialized_position<'c: 'info, 'info>(
     ctx: Context<'_, '_, 'c, 'info, ZapInDlmmForUnintializedPositionCtx<'info>>,
     min_delta_id: i32,
@@ -101,8 +209,77 @@ pub fn handle_zap_in_dlmm_for_uninitialized_position<'c: 'info, 'info>(
     favor_x_in_active_id: bool,
     strategy: StrategyType,
     remaining_accounts_info: RemainingAccountsInfo,
-) -> Result<()> {
+    leftover_policy: LeftoverPolicy,
+    single_token_swap: bool,
+    price_range: Option<PriceRange>,
+    max_price_deviation_bps: Option<u16>,
+    shrink_mode: u8,
+) -> Result<ZapInResult> {
+    require!(
+        shrink_mode <= NO_SHRINK_BOTH,
+        ZapError::InvalidDlmmZapInParameters
+    );
+    validate_active_price_deviation(&ctx.accounts.lb_pair, active_id, max_price_deviation_bps)?;
+    // bins of price range are fixed, delta ids are derived from active id before and after swap
+    let lb_pair = ctx.accounts.lb_pair.load()?;
+    let bin_range = price_range
+        .map(|price_range| {
+            price_range.get_bin_range(
+                lb_pair.bin_step,
+                ctx.accounts.token_x_mint.decimals,
+                ctx.accounts.token_y_mint.decimals,
+            )
+        })
+        .transpose()?;
+    let (min_delta_id, max_delta_id) =
+        get_delta_id_range(bin_range, min_delta_id, max_delta_id, lb_pair.active_id)?;
+    let (lower_bin_id, upper_bin_id) = get_deposit_bin_range(
+        bin_range,
+        min_delta_id,
+        max_delta_id,
+        lb_pair.active_id,
+        active_id,
+        max_active_bin_slippage,
+        single_token_swap,
+    )?;
+    drop(lb_pair);
+
+    // create missing bin arrays of the deposit range before any dlmm cpi
+    ctx.accounts
+        .initialize_bin_arrays(ctx.remaining_accounts, lower_bin_id, upper_bin_id)?;
+
     let mut ledger = ctx.accounts.ledger.load_mut()?;
+    // swap the only funded side to the ratio of strategy, the position is created after swap
+    let mut swap_in_amount = 0;
+    let mut swap_out_amount = 0;
+    if single_token_swap {
+        let (amount_in, swap_for_y) = get_dlmm_single_token_swap_amount(
+            &ctx.accounts.lb_pair,
+            ctx.remaining_accounts,
+            &ctx.accounts.token_x_mint,
+            &ctx.accounts.token_y_mint,
+            ledger.amount_a,
+            ledger.amount_b,
+            min_delta_id,
+            max_delta_id,
+            favor_x_in_active_id,
+            &strategy,
+        )?;
+        if amount_in > 0 {
+            swap_in_amount = amount_in;
+            swap_out_amount = ctx.accounts.swap_and_update_ledger(
+                &mut ledger,
+                amount_in,
+                swap_for_y,
+                active_id,
+                max_active_bin_slippage,
+                max_price_deviation_bps,
+                ctx.remaining_accounts,
+                remaining_accounts_info.clone(),
+            )?;
+        }
+    }
+
     let max_deposit_x_amount = ledger.amount_a;
     let max_deposit_y_amount = ledger.amount_b;
 
@@ -112,6 +289,8 @@ pub fn handle_zap_in_dlmm_for_uninitialized_position<'c: 'info, 'info>(
     let pre_user_amount_y = accessor::amount(&token_y_account_ai)?;
 
     let lb_pair = ctx.accounts.lb_pair.load()?;
+    let (min_delta_id, max_delta_id) =
+        get_delta_id_range(bin_range, min_delta_id, max_delta_id, lb_pair.active_id)?;
 
     // create position wth bin_delta in left side, and bin_delta in right side
     let lower_bin_id = lb_pair.active_id.safe_add(min_delta_id)?;
@@ -128,7 +307,6 @@ pub fn handle_zap_in_dlmm_for_uninitialized_position<'c: 'info, 'info>(
     ctx.accounts.initialize_position(lower_bin_id, width)?;
 
     // rebalancing
-    // TODO refactor to save more code with endpoint zap in dlmm for initialized position
     let lb_pair = ctx.accounts.lb_pair.load()?;
     let lb_pair_active_id = lb_pair.active_id;
 
@@ -165,77 +343,37 @@ pub fn handle_zap_in_dlmm_for_uninitialized_position<'c: 'info, 'info>(
         ZapError::InvalidDlmmZapInParameters
     );
 
-    let UnparsedAddLiquidityParams {
-        x0,
-        y0,
-        delta_x,
-        delta_y,
-        bit_flag,
-    } = params.get_rebalancing_params()?;
+    let adds = params.get_add_liquidity_params()?;
 
     let params = RebalanceLiquidityParams {
         active_id,
         max_active_bin_slippage,
-        should_claim_fee: false,
+        should_claim_fee: false, // new position has nothing to claim
         should_claim_reward: false,
         min_withdraw_x_amount: 0,
         max_deposit_x_amount,
         min_withdraw_y_amount: 0,
         max_deposit_y_amount,
-        shrink_mode: 0, // we allow to shrink in both side
+        shrink_mode,
         padding: [0; 31],
         removes: vec![],
-        adds: vec![AddLiquidityParams {
-            min_delta_id,
-            max_delta_id,
-            x0,
-            y0,
-            delta_x,
-            delta_y,
-            favor_x_in_active_id,
-            bit_flag,
-            ..Default::default()
-        }],
+        adds,
     };
 
     drop(lb_pair);
 
-    let bin_array_bitmap_extension = if let Some(value) = &ctx.accounts.bin_array_bitmap_extension {
-        Some(value.to_account_info())
-    } else {
-        None
-    };
+    let pre_liquidity_share = get_position_total_liquidity_share(&ctx.accounts.position)?;
 
-    dlmm::cpi::rebalance_liquidity(
-        CpiContext::new(
-            ctx.accounts.dlmm_program.to_account_info(),
-            dlmm::cpi::accounts::RebalanceLiquidity {
-                position: ctx.accounts.position.to_account_info(),
-                lb_pair: ctx.accounts.lb_pair.to_account_info(),
-                bin_array_bitmap_extension,
-                owner: ctx.accounts.owner.to_account_info(),
-                user_token_x: ctx.accounts.user_token_x.to_account_info(),
-                user_token_y: ctx.accounts.user_token_y.to_account_info(),
-                reserve_x: ctx.accounts.reserve_x.to_account_info(),
-                reserve_y: ctx.accounts.reserve_y.to_account_info(),
-                token_x_mint: ctx.accounts.token_x_mint.to_account_info(),
-                token_y_mint: ctx.accounts.token_y_mint.to_account_info(),
-                rent_payer: ctx.accounts.rent_payer.to_account_info(),
-                token_x_program: ctx.accounts.token_x_program.to_account_info(),
-                token_y_program: ctx.accounts.token_y_program.to_account_info(),
-                memo_program: ctx.accounts.memo_program.to_account_info(),
-                system_program: ctx.accounts.system_program.to_account_info(),
-                program: ctx.accounts.dlmm_program.to_account_info(),
-                event_authority: ctx.accounts.dlmm_event_authority.to_account_info(),
-            },
-        )
-        .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+    ctx.accounts.rebalance_liquidity(
+        ctx.accounts.position.to_account_info(),
         params,
-        remaining_accounts_info,
+        ctx.remaining_accounts,
+        remaining_accounts_info.clone(),
     )?;
 
     let post_user_amount_x = accessor::amount(&token_x_account_ai)?;
     let post_user_amount_y = accessor::amount(&token_y_account_ai)?;
+    let post_liquidity_share = get_position_total_liquidity_share(&ctx.accounts.position)?;
 
     ledger.update_ledger_balances(
         pre_user_amount_x,
@@ -244,6 +382,17 @@ pub fn handle_zap_in_dlmm_for_uninitialized_position<'c: 'info, 'info>(
         post_user_amount_y,
     )?;
 
+    // handle leftover
+    let (leftover_swap_in_amount, leftover_swap_out_amount) = ctx.accounts.handle_leftover(
+        &mut ledger,
+        leftover_policy,
+        active_id,
+        max_active_bin_slippage,
+        max_price_deviation_bps,
+        ctx.remaining_accounts,
+        remaining_accounts_info,
+    )?;
+
     // log will be truncated, shouldn't rely on that
     msg!(
         "max_deposit_amounts: {} {}, remaining_amounts: {} {}",
@@ -253,5 +402,15 @@ pub fn handle_zap_in_dlmm_for_uninitialized_position<'c: 'info, 'info>(
         ledger.amount_b
     );
 
-    Ok(())
+    Ok(ZapInResult {
+        liquidity_delta: post_liquidity_share.safe_sub(pre_liquidity_share)?,
+        amount_a_deposited: pre_user_amount_x.safe_sub(post_user_amount_x)?,
+        amount_b_deposited: pre_user_amount_y.safe_sub(post_user_amount_y)?,
+        swap_in_amount,
+        swap_out_amount,
+        remaining_amount_a: ledger.amount_a,
+        remaining_amount_b: ledger.amount_b,
+        leftover_swap_in_amount,
+        leftover_swap_out_amount,
+    })
 }
@@ -2,6 +2,26 @@
 pub mod ix_zap_in_dlmm_for_initialized_position;
 pub use ix_zap_in_dlmm_for_initialized_position::*;
 
+// deposit a wide range split across adjacent initialized positions
+pub mod ix_zap_in_dlmm_for_multiple_positions;
+pub use ix_zap_in_dlmm_for_multiple_positions::*;
+
 // create position and deposit in position
 pub mod ix_zap_in_dlmm_for_uninitialized_position;
 pub use ix_zap_in_dlmm_for_uninitialized_position::*;
+
+// quote bin distribution of zap in
+pub mod ix_quote_zap_in_dlmm;
+pub use ix_quote_zap_in_dlmm::*;
+
+// swap to the ratio of strategy before deposit
+pub mod single_token_swap;
+pub use single_token_swap::*;
+
+// withdraw, swap and add back position in one instruction
+pub mod ix_rebalance_dlmm_position;
+pub use ix_rebalance_dlmm_position::*;
+
+// accounts shared by dlmm zap in instructions, create missing bin arrays of the deposit range
+pub mod dlmm_zap_in_accounts;
+pub use dlmm_zap_in_accounts::*;
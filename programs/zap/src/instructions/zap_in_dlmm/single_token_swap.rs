@@ -0,0 +1,220 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use damm_v2::safe_math::SafeMath;
+use dlmm::accounts::{BinArray, LbPair};
+use std::{cell::Ref, mem::size_of};
+
+use crate::{
+    calculate_dlmm_swap_amount, error::ZapError, get_position_liquidity_shares,
+    get_position_withdraw_amounts, get_price_change_bps, new_transfer_fee_calculator,
+    price_math::get_price_from_id, DlmmSwapSimulator, StrategyType, ZapInRebalancingParams,
+};
+
+// (swap in amount, swap_for_y) to turn the only funded side into amounts matching the strategy,
+// bin arrays of the swap path are read from remaining accounts
+pub fn get_dlmm_single_token_swap_amount<'info>(
+    lb_pair_loader: &AccountLoader<'info, LbPair>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    token_x_mint: &InterfaceAccount<'info, Mint>,
+    token_y_mint: &InterfaceAccount<'info, Mint>,
+    amount_x: u64,
+    amount_y: u64,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    favor_x_in_active_id: bool,
+    strategy: &StrategyType,
+) -> Result<(u64, bool)> {
+    require!(
+        (amount_x == 0) != (amount_y == 0),
+        ZapError::InvalidDlmmZapInParameters
+    );
+    get_dlmm_swap_amount(
+        lb_pair_loader,
+        remaining_accounts,
+        token_x_mint,
+        token_y_mint,
+        amount_x,
+        amount_y,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        strategy,
+    )
+}
+
+// (swap in amount, swap_for_y) to swap the surplus side of given amounts to match the strategy,
+// bin arrays of the swap path are read from remaining accounts
+pub fn get_dlmm_swap_amount<'info>(
+    lb_pair_loader: &AccountLoader<'info, LbPair>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    token_x_mint: &InterfaceAccount<'info, Mint>,
+    token_y_mint: &InterfaceAccount<'info, Mint>,
+    amount_x: u64,
+    amount_y: u64,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    favor_x_in_active_id: bool,
+    strategy: &StrategyType,
+) -> Result<(u64, bool)> {
+    let bin_arrays = load_bin_arrays(lb_pair_loader, remaining_accounts)?;
+    let bin_arrays = bin_arrays
+        .iter()
+        .map(|bin_array| &**bin_array)
+        .collect::<Vec<_>>();
+
+    let lb_pair = lb_pair_loader.load()?;
+    let params = ZapInRebalancingParams {
+        amount_x,
+        amount_y,
+        active_id: lb_pair.active_id,
+        bin_step: lb_pair.bin_step,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        strategy: strategy.clone(),
+    };
+    let simulator = DlmmSwapSimulator::new(&lb_pair, &bin_arrays, Clock::get()?.unix_timestamp);
+    let (swap_in_amount, _swap_out_amount, swap_for_y) = calculate_dlmm_swap_amount(
+        &simulator,
+        &params,
+        &new_transfer_fee_calculator(token_x_mint)?,
+        &new_transfer_fee_calculator(token_y_mint)?,
+    )?;
+
+    Ok((swap_in_amount, swap_for_y))
+}
+
+// amount out of swapping amount_in through bin arrays in remaining accounts, after transfer fees,
+// 0 if the swap goes out of given bin arrays, so dust that swaps to nothing is kept
+pub fn get_dlmm_swap_out_amount<'info>(
+    lb_pair_loader: &AccountLoader<'info, LbPair>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    token_x_mint: &InterfaceAccount<'info, Mint>,
+    token_y_mint: &InterfaceAccount<'info, Mint>,
+    amount_in: u64,
+    swap_for_y: bool,
+) -> Result<u64> {
+    let bin_arrays = load_bin_arrays(lb_pair_loader, remaining_accounts)?;
+    let bin_arrays = bin_arrays
+        .iter()
+        .map(|bin_array| &**bin_array)
+        .collect::<Vec<_>>();
+
+    let (token_in_mint, token_out_mint) = if swap_for_y {
+        (token_x_mint, token_y_mint)
+    } else {
+        (token_y_mint, token_x_mint)
+    };
+    let amount_in = new_transfer_fee_calculator(token_in_mint)?
+        .calculate_transfer_fee_excluded_amount(amount_in)?
+        .amount;
+
+    let lb_pair = lb_pair_loader.load()?;
+    let simulator = DlmmSwapSimulator::new(&lb_pair, &bin_arrays, Clock::get()?.unix_timestamp);
+    let Some(swap_result) = simulator.simulate(amount_in, swap_for_y)? else {
+        return Ok(0);
+    };
+    let amount_out = swap_result.amount_out;
+    Ok(new_transfer_fee_calculator(token_out_mint)?
+        .calculate_transfer_fee_excluded_amount(amount_out)?
+        .amount)
+}
+
+// amounts received by removing all liquidity of position, after transfer fees,
+// bin arrays of the position range are read from remaining accounts
+pub fn get_dlmm_position_withdraw_amounts<'info>(
+    position: &AccountInfo<'info>,
+    lb_pair_loader: &AccountLoader<'info, LbPair>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    token_x_mint: &InterfaceAccount<'info, Mint>,
+    token_y_mint: &InterfaceAccount<'info, Mint>,
+) -> Result<(u64, u64)> {
+    let bin_arrays = load_bin_arrays(lb_pair_loader, remaining_accounts)?;
+    let bin_arrays = bin_arrays
+        .iter()
+        .map(|bin_array| &**bin_array)
+        .collect::<Vec<_>>();
+
+    let (lower_bin_id, liquidity_shares) = get_position_liquidity_shares(position)?;
+    let (amount_x, amount_y) =
+        get_position_withdraw_amounts(lower_bin_id, &liquidity_shares, &bin_arrays)?;
+    Ok((
+        new_transfer_fee_calculator(token_x_mint)?
+            .calculate_transfer_fee_excluded_amount(amount_x)?
+            .amount,
+        new_transfer_fee_calculator(token_y_mint)?
+            .calculate_transfer_fee_excluded_amount(amount_y)?
+            .amount,
+    ))
+}
+
+// bin arrays of the lb pair program in remaining accounts, other accounts are skipped,
+// all of them must belong to the lb pair
+fn load_bin_arrays<'info>(
+    lb_pair_loader: &AccountLoader<'info, LbPair>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<Vec<Ref<'info, BinArray>>> {
+    let disc_len = BinArray::DISCRIMINATOR.len();
+    let mut bin_arrays = vec![];
+    for account in remaining_accounts.iter() {
+        let data = account.try_borrow_data()?;
+        if account.owner != &dlmm::ID || !data.starts_with(BinArray::DISCRIMINATOR) {
+            continue;
+        }
+        let bin_array = Ref::filter_map(data, |data| {
+            data.get(disc_len..disc_len.checked_add(size_of::<BinArray>())?)
+                .map(bytemuck::from_bytes::<BinArray>)
+        })
+        .map_err(|_| ZapError::InvalidDlmmZapInParameters)?;
+        require!(
+            bin_array.lb_pair == lb_pair_loader.key(),
+            ZapError::InvalidDlmmZapInParameters
+        );
+        bin_arrays.push(bin_array);
+    }
+    Ok(bin_arrays)
+}
+
+// the same bound as dlmm applies to the active bin in rebalance liquidity
+pub fn validate_active_bin_slippage(
+    lb_pair_loader: &AccountLoader<LbPair>,
+    active_id: i32,
+    max_active_bin_slippage: u16,
+) -> Result<()> {
+    let lb_pair = lb_pair_loader.load()?;
+    require!(
+        lb_pair.active_id.safe_sub(active_id)?.unsigned_abs() <= u32::from(max_active_bin_slippage),
+        ZapError::ExceededSlippage
+    );
+    Ok(())
+}
+
+// deviation in bps between the price of the expected active id and the price of the pool active id
+pub fn get_active_price_deviation_bps(
+    expected_active_id: i32,
+    active_id: i32,
+    bin_step: u16,
+) -> Result<u32> {
+    let expected_price = get_price_from_id(expected_active_id, bin_step)?;
+    let price = get_price_from_id(active_id, bin_step)?;
+    get_price_change_bps(expected_price, price)
+}
+
+// unlike max_active_bin_slippage, the bound doesn't depend on the bin step of the pool, None skips the check
+pub fn validate_active_price_deviation(
+    lb_pair_loader: &AccountLoader<LbPair>,
+    active_id: i32,
+    max_price_deviation_bps: Option<u16>,
+) -> Result<()> {
+    let Some(max_price_deviation_bps) = max_price_deviation_bps else {
+        return Ok(());
+    };
+    let lb_pair = lb_pair_loader.load()?;
+    let price_deviation_bps =
+        get_active_price_deviation_bps(active_id, lb_pair.active_id, lb_pair.bin_step)?;
+    require!(
+        price_deviation_bps <= u32::from(max_price_deviation_bps),
+        ZapError::ExceededSlippage
+    );
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+
+/// Result of zap in, set as return data so CPI callers and simulation can read it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZapInResult {
+    /// liquidity added to position
+    pub liquidity_delta: u128,
+    /// amount of token a (x in dlmm) deposited to position
+    pub amount_a_deposited: u64,
+    /// amount of token b (y in dlmm) deposited to position
+    pub amount_b_deposited: u64,
+    /// amount of token sent in swap
+    pub swap_in_amount: u64,
+    /// amount of token received from swap
+    pub swap_out_amount: u64,
+    /// remaining token a balance in ledger
+    pub remaining_amount_a: u64,
+    /// remaining token b balance in ledger
+    pub remaining_amount_b: u64,
+    /// amount of token sent in leftover swap of SwapToTokenA/SwapToTokenB policy, not in swap_in_amount
+    pub leftover_swap_in_amount: u64,
+    /// amount of token received from leftover swap, not in swap_out_amount
+    pub leftover_swap_out_amount: u64,
+}
+
+/// Result of rebalance dlmm position, set as return data so CPI callers and simulation can read it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RebalanceDlmmPositionResult {
+    /// amount of token x claimed from fee and reward of position
+    pub amount_x_claimed: u64,
+    /// amount of token y claimed from fee and reward of position
+    pub amount_y_claimed: u64,
+    /// amount of token x withdrawn from position, including claimed amount if compounded
+    pub amount_x_withdrawn: u64,
+    /// amount of token y withdrawn from position, including claimed amount if compounded
+    pub amount_y_withdrawn: u64,
+    /// amount of token sent in swap
+    pub swap_in_amount: u64,
+    /// amount of token received from swap
+    pub swap_out_amount: u64,
+    /// amount of token x deposited back to position
+    pub amount_x_deposited: u64,
+    /// amount of token y deposited back to position
+    pub amount_y_deposited: u64,
+    /// total liquidity share of position after rebalance
+    pub liquidity_share: u128,
+    /// amount of token x left of rebalance in user token account
+    pub remaining_amount_x: u64,
+    /// amount of token y left of rebalance in user token account
+    pub remaining_amount_y: u64,
+    /// amount of token sent in leftover swap of SwapToTokenA/SwapToTokenB policy, not in swap_in_amount
+    pub leftover_swap_in_amount: u64,
+    /// amount of token received from leftover swap, not in swap_out_amount
+    pub leftover_swap_out_amount: u64,
+}
+
+/// Result of compound damm v2 position, set as return data so CPI callers and simulation can read it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompoundDammV2PositionResult {
+    /// amount of token a claimed from position fee
+    pub amount_a_claimed: u64,
+    /// amount of token b claimed from position fee
+    pub amount_b_claimed: u64,
+    /// liquidity added to position
+    pub liquidity_delta: u128,
+    /// amount of token a deposited to position
+    pub amount_a_deposited: u64,
+    /// amount of token b deposited to position
+    pub amount_b_deposited: u64,
+    /// amount of token sent in swap
+    pub swap_in_amount: u64,
+    /// amount of token received from swap
+    pub swap_out_amount: u64,
+    /// claimed token a left in user token account
+    pub remaining_amount_a: u64,
+    /// claimed token b left in user token account
+    pub remaining_amount_b: u64,
+}
@@ -61,8 +61,62 @@ pub mod zap {
         ctx: Context<'_, '_, 'c, 'info, ZapInDammv2Ctx<'info>>,
         pre_sqrt_price: u128,
         max_sqrt_price_change_bps: u32,
-    ) -> Result<()> {
-        instructions::handle_zap_in_damm_v2(ctx, pre_sqrt_price, max_sqrt_price_change_bps)
+        min_liquidity_delta: u128,
+        token_a_amount_threshold: u64,
+        token_b_amount_threshold: u64,
+        lock_mode: PositionLockMode,
+        max_rounds: u8,
+        dust_threshold: u64,
+        leftover_policy: LeftoverPolicy,
+    ) -> Result<ZapInResult> {
+        instructions::handle_zap_in_damm_v2(
+            ctx,
+            pre_sqrt_price,
+            max_sqrt_price_change_bps,
+            min_liquidity_delta,
+            token_a_amount_threshold,
+            token_b_amount_threshold,
+            lock_mode,
+            max_rounds,
+            dust_threshold,
+            leftover_policy,
+        )
+    }
+
+    pub fn quote_zap_in_damm_v2(
+        ctx: Context<QuoteZapInDammv2Ctx>,
+        amount_a: u64,
+        amount_b: u64,
+        has_referral: bool,
+        max_rounds: u8,
+        dust_threshold: u64,
+    ) -> Result<ZapInDammV2Quote> {
+        instructions::handle_quote_zap_in_damm_v2(
+            ctx,
+            amount_a,
+            amount_b,
+            has_referral,
+            max_rounds,
+            dust_threshold,
+        )
+    }
+
+    pub fn compound_damm_v2_position<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, CompoundDammV2PositionCtx<'info>>,
+        pre_sqrt_price: u128,
+        max_sqrt_price_change_bps: u32,
+        min_liquidity_delta: u128,
+        max_rounds: u8,
+        dust_threshold: u64,
+    ) -> Result<CompoundDammV2PositionResult> {
+        instructions::handle_compound_damm_v2_position(
+            ctx,
+            pre_sqrt_price,
+            max_sqrt_price_change_bps,
+            min_liquidity_delta,
+            max_rounds,
+            dust_threshold,
+        )
     }
 
     pub fn zap_in_dlmm_for_initialized_position<'c: 'info, 'info>(
@@ -74,7 +128,15 @@ pub mod zap {
         favor_x_in_active_id: bool,
         strategy: StrategyType,
         remaining_accounts_info: RemainingAccountsInfo,
-    ) -> Result<()> {
+        leftover_policy: LeftoverPolicy,
+        single_token_swap: bool,
+        should_claim_fee: bool,
+        should_claim_reward: bool,
+        compound: bool,
+        shrink_mode: u8,
+        price_range: Option<PriceRange>,
+        max_price_deviation_bps: Option<u16>,
+    ) -> Result<ZapInResult> {
         instructions::handle_zap_in_dlmm_for_initialized_position(
             ctx,
             active_id,
@@ -84,6 +146,42 @@ pub mod zap {
             favor_x_in_active_id,
             strategy,
             remaining_accounts_info,
+            leftover_policy,
+            single_token_swap,
+            should_claim_fee,
+            should_claim_reward,
+            compound,
+            shrink_mode,
+            price_range,
+            max_price_deviation_bps,
+        )
+    }
+
+    pub fn zap_in_dlmm_for_multiple_positions<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, ZapInDlmmForMultiplePositionsCtx<'info>>,
+        active_id: i32,
+        min_delta_id: i32,
+        max_delta_id: i32,
+        max_active_bin_slippage: u16,
+        favor_x_in_active_id: bool,
+        strategy: StrategyType,
+        remaining_accounts_info: RemainingAccountsInfo,
+        position_count: u8,
+        max_price_deviation_bps: Option<u16>,
+        leftover_policy: LeftoverPolicy,
+    ) -> Result<ZapInResult> {
+        instructions::handle_zap_in_dlmm_for_multiple_positions(
+            ctx,
+            active_id,
+            max_active_bin_slippage,
+            min_delta_id,
+            max_delta_id,
+            favor_x_in_active_id,
+            strategy,
+            remaining_accounts_info,
+            position_count,
+            max_price_deviation_bps,
+            leftover_policy,
         )
     }
 
@@ -96,7 +194,12 @@ pub mod zap {
         favor_x_in_active_id: bool,
         strategy: StrategyType,
         remaining_accounts_info: RemainingAccountsInfo,
-    ) -> Result<()> {
+        leftover_policy: LeftoverPolicy,
+        single_token_swap: bool,
+        price_range: Option<PriceRange>,
+        max_price_deviation_bps: Option<u16>,
+        shrink_mode: u8,
+    ) -> Result<ZapInResult> {
         instructions::handle_zap_in_dlmm_for_uninitialized_position(
             ctx,
             min_delta_id,
@@ -106,6 +209,65 @@ pub mod zap {
             favor_x_in_active_id,
             strategy,
             remaining_accounts_info,
+            leftover_policy,
+            single_token_swap,
+            price_range,
+            max_price_deviation_bps,
+            shrink_mode,
+        )
+    }
+
+    pub fn rebalance_dlmm_position<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, RebalanceDlmmPositionCtx<'info>>,
+        active_id: i32,
+        min_delta_id: i32,
+        max_delta_id: i32,
+        max_active_bin_slippage: u16,
+        favor_x_in_active_id: bool,
+        strategy: StrategyType,
+        remaining_accounts_info: RemainingAccountsInfo,
+        should_claim_fee: bool,
+        should_claim_reward: bool,
+        compound: bool,
+        max_price_deviation_bps: Option<u16>,
+        shrink_mode: u8,
+        leftover_policy: LeftoverPolicy,
+    ) -> Result<RebalanceDlmmPositionResult> {
+        instructions::handle_rebalance_dlmm_position(
+            ctx,
+            active_id,
+            max_active_bin_slippage,
+            min_delta_id,
+            max_delta_id,
+            favor_x_in_active_id,
+            strategy,
+            remaining_accounts_info,
+            should_claim_fee,
+            should_claim_reward,
+            compound,
+            max_price_deviation_bps,
+            shrink_mode,
+            leftover_policy,
+        )
+    }
+
+    pub fn quote_zap_in_dlmm(
+        ctx: Context<QuoteZapInDlmmCtx>,
+        amount_x: u64,
+        amount_y: u64,
+        min_delta_id: i32,
+        max_delta_id: i32,
+        favor_x_in_active_id: bool,
+        strategy: StrategyType,
+    ) -> Result<ZapInDlmmQuote> {
+        instructions::handle_quote_zap_in_dlmm(
+            ctx,
+            amount_x,
+            amount_y,
+            min_delta_id,
+            max_delta_id,
+            favor_x_in_active_id,
+            strategy,
         )
     }
 }
@@ -9,6 +9,10 @@ const SCALE_OFFSET: u8 = 64;
 pub const ONE: u128 = 1u128 << SCALE_OFFSET;
 const MAX_EXPONENTIAL: u32 = 0x80000; // 1048576
 
+// bin id bounds of dlmm, price of bin step 1 at these ids is about 2^64 and 2^-64
+pub const MAX_BIN_ID: i32 = 443_636;
+pub const MIN_BIN_ID: i32 = -443_636;
+
 // In Trader Joe, the active_id need to be shifted by 2 ** 23 to get the actual ID.
 // The reason is because they mint LP for each bin based on active_id using ERC1155, which the ID do not support negative
 
@@ -18,6 +22,37 @@ pub fn get_price_from_id(active_id: i32, bin_step: u16) -> Result<u128> {
     pow(base, active_id).ok_or_else(|| ZapError::MathOverflow.into())
 }
 
+/// Bin id of the given Q64.64 price, the largest id whose price <= price when rounding down, or the smallest id
+/// whose price >= price when rounding up. Binary search with get_price_from_id, so it's consistent with bin prices
+pub fn get_id_from_price(price: u128, bin_step: u16, round_up: bool) -> Result<i32> {
+    require!(price > 0, ZapError::InvalidPriceRange);
+    let base = get_price_base_factor(bin_step)?;
+    // price overflows Q64.64 above max bin id and rounds to 0 below min bin id
+    let is_price_lte = |id: i32| match pow(base, id) {
+        Some(bin_price) => bin_price <= price,
+        None => id < 0,
+    };
+
+    require!(is_price_lte(MIN_BIN_ID), ZapError::InvalidPriceRange);
+    let mut low = MIN_BIN_ID;
+    let mut high = MAX_BIN_ID;
+    while low < high {
+        let mid = low.safe_add(high.safe_sub(low)?.safe_add(1)?.safe_div(2)?)?;
+        if is_price_lte(mid) {
+            low = mid;
+        } else {
+            high = mid.safe_sub(1)?;
+        }
+    }
+
+    if round_up && pow(base, low) != Some(price) {
+        let id = low.safe_add(1)?;
+        require!(id <= MAX_BIN_ID, ZapError::InvalidPriceRange);
+        return Ok(id);
+    }
+    Ok(low)
+}
+
 // get price base factor
 pub fn get_price_base_factor(bin_step: u16) -> Result<u128> {
     // Make bin_step into Q64x64, and divided by MAX_BASIS_POINT. If bin_step = 1, we get 0.0001 in Q64x64
@@ -51,6 +51,18 @@ impl UserLedger {
         let amount_b = token_b_transfer_fee_calculator
             .calculate_transfer_fee_excluded_amount(self.amount_b)?
             .amount;
+        // pool is at a price range boundary, only one side could be deposited,
+        // so we deposit single sided and swap the other side to the usable one
+        if sqrt_price >= max_sqrt_price {
+            let liquidity_from_b =
+                get_liquidity_from_amount_b(amount_b, min_sqrt_price, sqrt_price)?;
+            return Ok((liquidity_from_b, TradeDirection::AtoB));
+        }
+        if sqrt_price <= min_sqrt_price {
+            let liquidity_from_a =
+                get_liquidity_from_amount_a(amount_a, max_sqrt_price, sqrt_price)?;
+            return Ok((liquidity_from_a, TradeDirection::BtoA));
+        }
         let liquidity_from_a = get_liquidity_from_amount_a(amount_a, max_sqrt_price, sqrt_price)?;
         let liquidity_from_b = get_liquidity_from_amount_b(amount_b, min_sqrt_price, sqrt_price)?;
         if liquidity_from_a > liquidity_from_b {
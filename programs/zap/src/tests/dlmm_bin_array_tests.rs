@@ -0,0 +1,92 @@
+use dlmm::accounts::BinArray;
+
+use crate::{
+    bin_id_to_bin_array_index, get_deposit_bin_range, get_position_withdraw_amounts,
+    is_overflow_default_bin_array_bitmap, BIN_ARRAY_BITMAP_SIZE, MAX_BIN_PER_ARRAY,
+};
+
+#[test]
+fn test_bin_id_to_bin_array_index() {
+    assert_eq!(bin_id_to_bin_array_index(0), 0);
+    assert_eq!(bin_id_to_bin_array_index(MAX_BIN_PER_ARRAY - 1), 0);
+    assert_eq!(bin_id_to_bin_array_index(MAX_BIN_PER_ARRAY), 1);
+    // negative bin ids round toward negative infinity
+    assert_eq!(bin_id_to_bin_array_index(-1), -1);
+    assert_eq!(bin_id_to_bin_array_index(-MAX_BIN_PER_ARRAY), -1);
+    assert_eq!(bin_id_to_bin_array_index(-MAX_BIN_PER_ARRAY - 1), -2);
+}
+
+#[test]
+fn test_is_overflow_default_bin_array_bitmap() {
+    assert!(!is_overflow_default_bin_array_bitmap(0));
+    assert!(!is_overflow_default_bin_array_bitmap(
+        -BIN_ARRAY_BITMAP_SIZE
+    ));
+    assert!(!is_overflow_default_bin_array_bitmap(
+        BIN_ARRAY_BITMAP_SIZE - 1
+    ));
+    assert!(is_overflow_default_bin_array_bitmap(
+        -BIN_ARRAY_BITMAP_SIZE - 1
+    ));
+    assert!(is_overflow_default_bin_array_bitmap(BIN_ARRAY_BITMAP_SIZE));
+
+    // last bin of the internal bitmap and first bin of the extension
+    let max_bin_id = i32::try_from(BIN_ARRAY_BITMAP_SIZE).unwrap() * MAX_BIN_PER_ARRAY;
+    assert!(!is_overflow_default_bin_array_bitmap(
+        bin_id_to_bin_array_index(max_bin_id - 1)
+    ));
+    assert!(is_overflow_default_bin_array_bitmap(
+        bin_id_to_bin_array_index(max_bin_id)
+    ));
+    assert!(!is_overflow_default_bin_array_bitmap(
+        bin_id_to_bin_array_index(-max_bin_id)
+    ));
+    assert!(is_overflow_default_bin_array_bitmap(
+        bin_id_to_bin_array_index(-max_bin_id - 1)
+    ));
+}
+
+#[test]
+fn test_get_deposit_bin_range() {
+    // price range fixes the bins
+    assert_eq!(
+        get_deposit_bin_range(Some((90, 110)), -5, 5, 100, 102, 3, true).unwrap(),
+        (90, 110)
+    );
+    // without swap the bins are around current active id
+    assert_eq!(
+        get_deposit_bin_range(None, -5, 5, 100, 102, 3, false).unwrap(),
+        (95, 105)
+    );
+    // single token swap can move active id within slippage of expected active id
+    assert_eq!(
+        get_deposit_bin_range(None, -5, 5, 100, 102, 3, true).unwrap(),
+        (94, 110)
+    );
+}
+
+#[test]
+fn test_get_position_withdraw_amounts() {
+    let mut lower_bin_array: Box<BinArray> = Box::new(bytemuck::Zeroable::zeroed());
+    lower_bin_array.index = -1;
+    let mut upper_bin_array: Box<BinArray> = Box::new(bytemuck::Zeroable::zeroed());
+    upper_bin_array.index = 0;
+    // bin -1 holds y, bin 0 holds both and bin 1 holds x
+    lower_bin_array.bins[69].amount_y = 1_000;
+    lower_bin_array.bins[69].liquidity_supply = 3_000;
+    upper_bin_array.bins[0].amount_x = 500;
+    upper_bin_array.bins[0].amount_y = 700;
+    upper_bin_array.bins[0].liquidity_supply = 1_000;
+    upper_bin_array.bins[1].amount_x = 999;
+    upper_bin_array.bins[1].liquidity_supply = 999;
+
+    // each bin is rounded down, bins without share are skipped
+    let (amount_x, amount_y) =
+        get_position_withdraw_amounts(-1, &[1_000, 500, 0], &[&lower_bin_array, &upper_bin_array])
+            .unwrap();
+    assert_eq!(amount_x, 250);
+    assert_eq!(amount_y, 333 + 350);
+
+    // bin array of a bin with share must be given
+    assert!(get_position_withdraw_amounts(-1, &[1_000, 500, 1], &[&lower_bin_array]).is_err());
+}
@@ -0,0 +1,144 @@
+use crate::{
+    clip_add_liquidity_ranges, validate_position_ranges, LinearStrategyType, StrategySegment,
+    StrategyType, ZapInRebalancingParams,
+};
+
+fn get_params(strategy: StrategyType) -> ZapInRebalancingParams {
+    ZapInRebalancingParams {
+        amount_x: 1_000_000_000,
+        amount_y: 2_000_000_000,
+        active_id: 100,
+        bin_step: 10,
+        min_delta_id: -100,
+        max_delta_id: 100,
+        favor_x_in_active_id: false,
+        strategy,
+    }
+}
+
+#[test]
+fn test_clipped_ranges_keep_amounts_in_bins() {
+    let mut weights = vec![1; 201];
+    weights[50..150].fill(3);
+    for strategy in [
+        StrategyType::Spot,
+        StrategyType::Curve,
+        StrategyType::BidAsk,
+        StrategyType::Custom { weights },
+        StrategyType::Piecewise {
+            segments: vec![
+                StrategySegment {
+                    min_delta_id: -100,
+                    max_delta_id: -11,
+                    strategy: LinearStrategyType::BidAsk,
+                    weight: 1,
+                },
+                StrategySegment {
+                    min_delta_id: -10,
+                    max_delta_id: 100,
+                    strategy: LinearStrategyType::Spot,
+                    weight: 2,
+                },
+            ],
+        },
+    ] {
+        let params = get_params(strategy);
+        let ranges = params.get_parsed_add_liquidity_ranges().unwrap();
+        let amounts_in_bins = params.get_amount_in_bins_of_ranges(&ranges).unwrap();
+
+        // three adjacent positions, the middle one has the active bin
+        let mut split_amounts_in_bins = amounts_in_bins.clone();
+        split_amounts_in_bins.iter_mut().for_each(|bin| {
+            bin.amount_x = 0;
+            bin.amount_y = 0;
+        });
+        for (min_delta_id, max_delta_id) in [(-100, -31), (-30, 39), (40, 100)] {
+            let clipped_ranges = clip_add_liquidity_ranges(&ranges, min_delta_id, max_delta_id);
+            assert!(clipped_ranges.iter().all(|range| {
+                range.min_delta_id >= min_delta_id && range.max_delta_id <= max_delta_id
+            }));
+            for (bin, amount_in_bin) in split_amounts_in_bins.iter_mut().zip(
+                params
+                    .get_amount_in_bins_of_ranges(&clipped_ranges)
+                    .unwrap(),
+            ) {
+                bin.amount_x += amount_in_bin.amount_x;
+                bin.amount_y += amount_in_bin.amount_y;
+            }
+            // adds of rebalance liquidity can be built for each position
+            assert_eq!(
+                params
+                    .to_add_liquidity_params(&clipped_ranges)
+                    .unwrap()
+                    .len(),
+                clipped_ranges.len()
+            );
+        }
+        assert_eq!(split_amounts_in_bins, amounts_in_bins);
+    }
+}
+
+#[test]
+fn test_max_deposit_of_positions() {
+    let params = get_params(StrategyType::Curve);
+    let ranges = params.get_parsed_add_liquidity_ranges().unwrap();
+    let (total_amount_x, total_amount_y) = params.get_total_amount_of_ranges(&ranges).unwrap();
+    assert!(total_amount_x <= params.amount_x && total_amount_y <= params.amount_y);
+
+    // each position is capped at the amounts of its own bins, caps add up to the whole range
+    let mut sum_amount_x = 0;
+    let mut sum_amount_y = 0;
+    for (min_delta_id, max_delta_id) in [(-100, -31), (-30, 39), (40, 100)] {
+        let position_params = ZapInRebalancingParams {
+            min_delta_id,
+            max_delta_id,
+            strategy: StrategyType::Spot,
+            ..params
+        };
+        let clipped_ranges = clip_add_liquidity_ranges(&ranges, min_delta_id, max_delta_id);
+        let (amount_x, amount_y) = position_params
+            .get_total_amount_of_ranges(&clipped_ranges)
+            .unwrap();
+        if max_delta_id < 0 {
+            assert_eq!(amount_x, 0);
+        }
+        if min_delta_id > 0 {
+            assert_eq!(amount_y, 0);
+        }
+        sum_amount_x += amount_x;
+        sum_amount_y += amount_y;
+    }
+    assert_eq!(sum_amount_x, total_amount_x);
+    assert_eq!(sum_amount_y, total_amount_y);
+}
+
+#[test]
+fn test_clip_ranges_outside_of_position() {
+    let params = get_params(StrategyType::Spot);
+    let ranges = params.get_parsed_add_liquidity_ranges().unwrap();
+    assert!(clip_add_liquidity_ranges(&ranges, 101, 170).is_empty());
+    assert!(clip_add_liquidity_ranges(&ranges, -170, -101).is_empty());
+    let clipped_ranges = clip_add_liquidity_ranges(&ranges, 100, 170);
+    assert_eq!(clipped_ranges.len(), 1);
+    assert_eq!(clipped_ranges[0].min_delta_id, 100);
+    assert_eq!(clipped_ranges[0].max_delta_id, 100);
+}
+
+#[test]
+fn test_validate_position_ranges() {
+    assert!(validate_position_ranges(&[(0, 69), (70, 139), (140, 209)], 0, 209).is_ok());
+    // positions can be wider than the range
+    assert!(validate_position_ranges(&[(-10, 69), (70, 139)], 0, 100).is_ok());
+    assert!(validate_position_ranges(&[(0, 69)], 10, 20).is_ok());
+
+    // gap or overlap between positions
+    assert!(validate_position_ranges(&[(0, 69), (71, 139)], 0, 139).is_err());
+    assert!(validate_position_ranges(&[(0, 69), (69, 139)], 0, 139).is_err());
+    // not ascending
+    assert!(validate_position_ranges(&[(70, 139), (0, 69)], 0, 139).is_err());
+    // range not covered
+    assert!(validate_position_ranges(&[(0, 69), (70, 139)], -1, 139).is_err());
+    assert!(validate_position_ranges(&[(0, 69), (70, 139)], 0, 140).is_err());
+    // no position
+    assert!(validate_position_ranges(&[], 0, 0).is_err());
+}
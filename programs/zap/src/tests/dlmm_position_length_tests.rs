@@ -0,0 +1,71 @@
+use crate::{
+    get_position_length_to_add, get_position_length_to_remove, NO_SHRINK_BOTH, NO_SHRINK_LOWER,
+    NO_SHRINK_UPPER, SHRINK_BOTH,
+};
+
+#[test]
+fn test_position_length_to_add() {
+    // requested range inside position
+    assert_eq!(get_position_length_to_add(-10, 10, -5, 5).unwrap(), (0, 0));
+    assert_eq!(
+        get_position_length_to_add(-10, 10, -10, 10).unwrap(),
+        (0, 0)
+    );
+
+    // requested range exceeds one or both sides
+    assert_eq!(get_position_length_to_add(-10, 10, -15, 5).unwrap(), (5, 0));
+    assert_eq!(get_position_length_to_add(-10, 10, 0, 30).unwrap(), (0, 20));
+    assert_eq!(
+        get_position_length_to_add(-10, 10, -11, 11).unwrap(),
+        (1, 1)
+    );
+
+    // requested range doesn't overlap position
+    assert_eq!(get_position_length_to_add(0, 9, 20, 29).unwrap(), (0, 20));
+    assert_eq!(get_position_length_to_add(0, 9, -30, -21).unwrap(), (30, 0));
+}
+
+#[test]
+fn test_position_length_to_remove() {
+    let liquidity_shares = [0, 0, 1, 2, 0, 3, 0];
+
+    assert_eq!(
+        get_position_length_to_remove(&liquidity_shares, SHRINK_BOTH).unwrap(),
+        (2, 1)
+    );
+    assert_eq!(
+        get_position_length_to_remove(&liquidity_shares, NO_SHRINK_LOWER).unwrap(),
+        (0, 1)
+    );
+    assert_eq!(
+        get_position_length_to_remove(&liquidity_shares, NO_SHRINK_UPPER).unwrap(),
+        (2, 0)
+    );
+    assert_eq!(
+        get_position_length_to_remove(&liquidity_shares, NO_SHRINK_BOTH).unwrap(),
+        (0, 0)
+    );
+
+    // fully used position
+    assert_eq!(
+        get_position_length_to_remove(&[1, 1, 1], SHRINK_BOTH).unwrap(),
+        (0, 0)
+    );
+
+    // empty position keeps one bin
+    assert_eq!(
+        get_position_length_to_remove(&[0; 5], SHRINK_BOTH).unwrap(),
+        (4, 0)
+    );
+    assert_eq!(
+        get_position_length_to_remove(&[0; 5], NO_SHRINK_UPPER).unwrap(),
+        (4, 0)
+    );
+    assert_eq!(
+        get_position_length_to_remove(&[0; 5], NO_SHRINK_LOWER).unwrap(),
+        (0, 4)
+    );
+
+    // invalid shrink mode
+    assert!(get_position_length_to_remove(&liquidity_shares, 4).is_err());
+}
@@ -0,0 +1,41 @@
+use crate::get_active_price_deviation_bps;
+
+#[test]
+fn test_price_deviation_of_same_active_id() {
+    for bin_step in [1, 10, 25, 100] {
+        for id in [-3_000, -1, 0, 1, 3_000] {
+            assert_eq!(get_active_price_deviation_bps(id, id, bin_step).unwrap(), 0);
+        }
+    }
+}
+
+#[test]
+fn test_price_deviation_of_one_bin() {
+    for bin_step in [1u16, 10, 25, 100] {
+        for id in [-3_000, -1, 0, 1, 3_000] {
+            // one bin above is bin_step bps, rounded up
+            let up = get_active_price_deviation_bps(id, id + 1, bin_step).unwrap();
+            assert!(up == u32::from(bin_step) || up == u32::from(bin_step) + 1);
+
+            // one bin below deviates less than one bin above
+            let down = get_active_price_deviation_bps(id, id - 1, bin_step).unwrap();
+            assert!(down <= up);
+            assert!(down > 0);
+        }
+    }
+}
+
+#[test]
+fn test_price_deviation_is_consistent_across_bin_steps() {
+    let max_price_deviation_bps = 100;
+
+    // 1 bin of bin step 100 is within 1%
+    assert!(get_active_price_deviation_bps(0, 1, 100).unwrap() <= max_price_deviation_bps);
+    assert!(get_active_price_deviation_bps(0, 2, 100).unwrap() > max_price_deviation_bps);
+
+    // 99 bins of bin step 1 are within 1%, 100 bins are above (1.0001^100 ~ 1.01005)
+    assert!(get_active_price_deviation_bps(0, 99, 1).unwrap() <= max_price_deviation_bps);
+    assert!(get_active_price_deviation_bps(0, 100, 1).unwrap() > max_price_deviation_bps);
+    assert!(get_active_price_deviation_bps(0, -100, 1).unwrap() <= max_price_deviation_bps);
+    assert!(get_active_price_deviation_bps(0, -101, 1).unwrap() > max_price_deviation_bps);
+}
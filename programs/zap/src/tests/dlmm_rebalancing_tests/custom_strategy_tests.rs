@@ -0,0 +1,175 @@
+use crate::{
+    tests::dlmm_rebalancing_tests::utils::{
+        assert_diff_amount, build_add_liquidity_params, get_bin_add_liquidity,
+        get_liquidity_distribution, get_total_amount, AmountInBin,
+    },
+    StrategyType, ZapInRebalancingParams,
+};
+
+#[test]
+fn test_strategy_bimodal_both_side() {
+    let active_id = 100;
+    let bin_step = 10;
+    let total_amount_x = 100_000;
+    let total_amount_y = 70_000;
+    let min_delta_id = -5;
+    let max_delta_id = 5;
+    let favor_x_in_active_id = false;
+    // two peaks at active_id - 3 and active_id + 3, nothing around active id
+    let weights = vec![1, 1, 4, 1, 0, 0, 0, 1, 4, 1, 1];
+
+    let params = build_add_liquidity_params(
+        total_amount_x,
+        total_amount_y,
+        active_id,
+        bin_step,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        StrategyType::Custom { weights },
+    );
+    // runs of equal weight: [-5,-4], [-3], [-2], [2], [3], [4,5]
+    assert_eq!(params.adds.len(), 6);
+
+    let amount_in_bins = get_bin_add_liquidity(&params, active_id, bin_step).unwrap();
+    let liquidity_distributions = get_liquidity_distribution(&amount_in_bins, bin_step);
+    println!("{:?}", liquidity_distributions);
+
+    for &AmountInBin {
+        bin_id,
+        amount_x,
+        amount_y,
+    } in amount_in_bins.iter()
+    {
+        if bin_id <= active_id {
+            assert_eq!(amount_x, 0);
+        } else {
+            assert_eq!(amount_y, 0);
+        }
+    }
+
+    // amounts follow weights in each side
+    let amount_y_in = |bin_id: i32| {
+        amount_in_bins
+            .iter()
+            .find(|bin| bin.bin_id == bin_id)
+            .unwrap()
+            .amount_y
+    };
+    assert_eq!(amount_y_in(active_id - 5), amount_y_in(active_id - 4));
+    assert_eq!(amount_y_in(active_id - 3), 4 * amount_y_in(active_id - 4));
+    // zero weight bins are not added
+    assert!(amount_in_bins
+        .iter()
+        .all(|bin| (bin.bin_id - active_id).abs() > 1));
+
+    let (amount_x, amount_y) = get_total_amount(&amount_in_bins);
+    assert!(amount_x <= total_amount_x);
+    assert!(amount_y <= total_amount_y);
+    assert_diff_amount(total_amount_x, amount_x, 10); // less than 10bps
+    assert_diff_amount(total_amount_y, amount_y, 10); // less than 10bps
+}
+
+#[test]
+fn test_strategy_skewed_only_ask_side() {
+    let active_id = 100;
+    let bin_step = 10;
+    let total_amount_x = 1_000_000;
+    let min_delta_id = 0;
+    let max_delta_id = 9;
+    let favor_x_in_active_id = true;
+    let weights: Vec<u16> = (1..=10).collect();
+
+    let params = build_add_liquidity_params(
+        total_amount_x,
+        0,
+        active_id,
+        bin_step,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        StrategyType::Custom { weights },
+    );
+    assert_eq!(params.adds.len(), 10);
+
+    let amount_in_bins = get_bin_add_liquidity(&params, active_id, bin_step).unwrap();
+    let liquidity_distributions = get_liquidity_distribution(&amount_in_bins, bin_step);
+    // value in bins is increasing with weights
+    for pair in liquidity_distributions.windows(2) {
+        assert!(pair[0] < pair[1]);
+    }
+
+    let (amount_x, amount_y) = get_total_amount(&amount_in_bins);
+    assert_eq!(amount_y, 0);
+    assert!(amount_x <= total_amount_x);
+    assert_diff_amount(total_amount_x, amount_x, 10); // less than 10bps
+}
+
+#[test]
+fn test_strategy_amount_in_bins_match_dlmm() {
+    let active_id = -300;
+    let bin_step = 25;
+    let min_delta_id = -20;
+    let max_delta_id = 20;
+    let weights: Vec<u16> = (min_delta_id..=max_delta_id)
+        .map(|delta_id: i32| (delta_id.unsigned_abs() % 7) as u16)
+        .collect();
+    let params = ZapInRebalancingParams {
+        amount_x: 12_345_678,
+        amount_y: 87_654_321,
+        active_id,
+        bin_step,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id: true,
+        strategy: StrategyType::Custom { weights },
+    };
+
+    let ranges = params.get_parsed_add_liquidity_ranges().unwrap();
+    let quoted_amount_in_bins = params.get_amount_in_bins_of_ranges(&ranges).unwrap();
+
+    let rebalance_params = build_add_liquidity_params(
+        params.amount_x,
+        params.amount_y,
+        active_id,
+        bin_step,
+        min_delta_id,
+        max_delta_id,
+        params.favor_x_in_active_id,
+        params.strategy.clone(),
+    );
+    let amount_in_bins = get_bin_add_liquidity(&rebalance_params, active_id, bin_step).unwrap();
+
+    for bin in amount_in_bins.iter() {
+        let quoted_bin = quoted_amount_in_bins
+            .iter()
+            .find(|quoted_bin| quoted_bin.bin_id == bin.bin_id)
+            .unwrap();
+        assert_eq!(quoted_bin, bin);
+    }
+    // zero weight bins are not added
+    assert_eq!(
+        quoted_amount_in_bins
+            .iter()
+            .filter(|bin| bin.amount_x + bin.amount_y > 0)
+            .count(),
+        amount_in_bins.len()
+    );
+}
+
+#[test]
+fn test_strategy_invalid_weights_length() {
+    let params = ZapInRebalancingParams {
+        amount_x: 100_000,
+        amount_y: 100_000,
+        active_id: 100,
+        bin_step: 10,
+        min_delta_id: -5,
+        max_delta_id: 5,
+        favor_x_in_active_id: false,
+        strategy: StrategyType::Custom {
+            weights: vec![1; 10],
+        },
+    };
+    assert!(params.get_add_liquidity_params().is_err());
+}
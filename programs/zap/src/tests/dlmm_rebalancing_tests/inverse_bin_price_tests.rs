@@ -0,0 +1,137 @@
+use std::ops::Neg;
+
+use ruint::aliases::U256;
+
+use crate::{
+    for_each_inverse_bin_price, price_math::get_price_from_id, StrategyType, ZapInRebalancingParams,
+};
+
+const AMOUNT_X: u64 = 1_000_000_000_000;
+
+// x0 and delta_x of strategy handlers with a full pow for every bin, as before incremental prices
+fn find_x0_and_delta_x_with_pow(
+    strategy: &StrategyType,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    bin_step: u16,
+    active_id: i32,
+) -> (i128, i128) {
+    let amount_x = U256::from(AMOUNT_X);
+    let prices = (min_delta_id..=max_delta_id)
+        .map(|m| {
+            (
+                m,
+                U256::from(get_price_from_id((active_id + m).neg(), bin_step).unwrap()),
+            )
+        })
+        .collect::<Vec<_>>();
+    match strategy {
+        StrategyType::Spot => {
+            let total_weight = prices.iter().fold(U256::ZERO, |sum, (_, pm)| sum + pm);
+            let x0 = (amount_x << 64) / total_weight;
+            (i128::try_from(x0).unwrap(), 0)
+        }
+        StrategyType::Curve => {
+            let b = prices.iter().fold(U256::ZERO, |sum, (_, pm)| sum + pm);
+            let c_numerator = prices
+                .iter()
+                .fold(U256::ZERO, |sum, (m, pm)| sum + U256::from(*m) * pm);
+            let c = c_numerator / U256::from(max_delta_id);
+            let x0 = i128::try_from((amount_x << 64) / (b - c)).unwrap();
+            let delta_x = -x0 / i128::from(max_delta_id);
+            (-(delta_x * i128::from(max_delta_id)), delta_x)
+        }
+        StrategyType::BidAsk => {
+            let m1 = U256::from(min_delta_id);
+            let b = prices.iter().fold(U256::ZERO, |sum, (_, pm)| sum + m1 * pm);
+            let c = prices
+                .iter()
+                .fold(U256::ZERO, |sum, (m, pm)| sum + U256::from(*m) * pm);
+            let delta_x = i128::try_from((amount_x << 64) / (c - b)).unwrap();
+            (delta_x * i128::from(min_delta_id).neg(), delta_x)
+        }
+        _ => unreachable!(),
+    }
+}
+
+// relative error of rounding in Q64.64 accumulated over bins, less than 1e-7 (0.001 bps)
+fn assert_within_tolerance(value: i128, expected: i128) {
+    let diff = value.abs_diff(expected);
+    assert!(
+        diff <= expected.unsigned_abs() / 10_000_000,
+        "{} {}",
+        value,
+        expected
+    );
+}
+
+#[test]
+fn test_incremental_price_matches_pow() {
+    for strategy in [
+        StrategyType::Spot,
+        StrategyType::Curve,
+        StrategyType::BidAsk,
+    ] {
+        for bin_step in [1, 10, 25, 100, 400] {
+            for active_id in [-20_000i32, -1_000, 0, 1_000, 20_000] {
+                for (min_delta_id, max_delta_id) in [(0, 1), (0, 69), (1, 200), (0, 1_399)] {
+                    // bin price must be in Q64.64 range, about 440_000 bins of bin step 1
+                    if (active_id.abs() + max_delta_id) * i32::from(bin_step) > 400_000 {
+                        continue;
+                    }
+                    let params = ZapInRebalancingParams {
+                        amount_x: AMOUNT_X,
+                        amount_y: 0,
+                        active_id,
+                        bin_step,
+                        min_delta_id,
+                        max_delta_id,
+                        favor_x_in_active_id: false,
+                        strategy: strategy.clone(),
+                    };
+                    let (x0, delta_x) = params
+                        .get_strategy_handler()
+                        .unwrap()
+                        .find_x0_and_delta_x(
+                            AMOUNT_X,
+                            min_delta_id,
+                            max_delta_id,
+                            bin_step,
+                            active_id,
+                        )
+                        .unwrap();
+                    let (expected_x0, expected_delta_x) = find_x0_and_delta_x_with_pow(
+                        &strategy,
+                        min_delta_id,
+                        max_delta_id,
+                        bin_step,
+                        active_id,
+                    );
+                    assert_within_tolerance(x0, expected_x0);
+                    assert_within_tolerance(delta_x, expected_delta_x);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_inverse_bin_prices() {
+    for bin_step in [1, 10, 100] {
+        let mut bin_ids = vec![];
+        for_each_inverse_bin_price(-100, 100, bin_step, |bin_id, price| {
+            bin_ids.push(bin_id);
+            let expected_price = U256::from(get_price_from_id(bin_id.neg(), bin_step).unwrap());
+            let diff = if price > expected_price {
+                price - expected_price
+            } else {
+                expected_price - price
+            };
+            // relative error of Q64.64 rounding accumulated over 200 bins
+            assert!(diff * U256::from(1_000_000_000_000u64) <= expected_price);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(bin_ids, (-100..=100).rev().collect::<Vec<_>>());
+    }
+}
@@ -8,4 +8,13 @@ mod curve_strategy_tests;
 mod bid_ask_strategy_tests;
 
 #[cfg(test)]
-mod utils;
+mod custom_strategy_tests;
+
+#[cfg(test)]
+mod piecewise_strategy_tests;
+
+#[cfg(test)]
+mod inverse_bin_price_tests;
+
+#[cfg(test)]
+pub mod utils;
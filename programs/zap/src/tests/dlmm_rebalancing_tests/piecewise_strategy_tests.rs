@@ -0,0 +1,177 @@
+use crate::{
+    tests::dlmm_rebalancing_tests::utils::{
+        assert_diff_amount, build_add_liquidity_params, get_bin_add_liquidity,
+        get_liquidity_distribution, get_total_amount, AmountInBin,
+    },
+    LinearStrategyType, StrategySegment, StrategyType, ZapInRebalancingParams,
+};
+
+fn spot_with_bid_ask_tails() -> StrategyType {
+    StrategyType::Piecewise {
+        segments: vec![
+            StrategySegment {
+                min_delta_id: -10,
+                max_delta_id: -4,
+                strategy: LinearStrategyType::BidAsk,
+                weight: 1,
+            },
+            StrategySegment {
+                min_delta_id: -3,
+                max_delta_id: 3,
+                strategy: LinearStrategyType::Spot,
+                weight: 2,
+            },
+            StrategySegment {
+                min_delta_id: 4,
+                max_delta_id: 10,
+                strategy: LinearStrategyType::BidAsk,
+                weight: 1,
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_strategy_spot_with_bid_ask_tails() {
+    let active_id = 100;
+    let bin_step = 10;
+    let total_amount_x = 300_000;
+    let total_amount_y = 300_000;
+    let min_delta_id = -10;
+    let max_delta_id = 10;
+    let favor_x_in_active_id = false;
+
+    let params = build_add_liquidity_params(
+        total_amount_x,
+        total_amount_y,
+        active_id,
+        bin_step,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        spot_with_bid_ask_tails(),
+    );
+    assert_eq!(params.adds.len(), 3);
+
+    let amount_in_bins = get_bin_add_liquidity(&params, active_id, bin_step).unwrap();
+    let liquidity_distributions = get_liquidity_distribution(&amount_in_bins, bin_step);
+    println!("{:?}", liquidity_distributions);
+
+    assert_eq!(amount_in_bins.len(), 21);
+    for &AmountInBin {
+        bin_id,
+        amount_x,
+        amount_y,
+    } in amount_in_bins.iter()
+    {
+        // bid ask is empty in the bin nearest to active id
+        let is_tail_end = (bin_id - active_id).abs() == 4;
+        if bin_id <= active_id {
+            assert_eq!(amount_x, 0);
+            assert!(amount_y > 0 || is_tail_end);
+        } else {
+            assert!(amount_x > 0 || is_tail_end);
+            assert_eq!(amount_y, 0);
+        }
+    }
+
+    // y of tail segment is half of y of spot segment, tail grows away from active id
+    let amount_y_in = |bin_id: i32| {
+        amount_in_bins
+            .iter()
+            .find(|bin| bin.bin_id == bin_id)
+            .unwrap()
+            .amount_y
+    };
+    let tail_amount_y: u64 = (active_id - 10..=active_id - 4).map(amount_y_in).sum();
+    let spot_amount_y: u64 = (active_id - 3..=active_id).map(amount_y_in).sum();
+    assert_diff_amount(total_amount_y / 3, tail_amount_y, 10);
+    assert_diff_amount(total_amount_y * 2 / 3, spot_amount_y, 10);
+    assert!(amount_y_in(active_id - 10) > amount_y_in(active_id - 4));
+    assert_eq!(amount_y_in(active_id - 3), amount_y_in(active_id));
+
+    let (amount_x, amount_y) = get_total_amount(&amount_in_bins);
+    assert!(amount_x <= total_amount_x);
+    assert!(amount_y <= total_amount_y);
+    assert_diff_amount(total_amount_x, amount_x, 10); // less than 10bps
+    assert_diff_amount(total_amount_y, amount_y, 10); // less than 10bps
+}
+
+#[test]
+fn test_strategy_amount_in_bins_match_dlmm() {
+    let active_id = 2_000;
+    let bin_step = 80;
+    let params = ZapInRebalancingParams {
+        amount_x: 1_234_567,
+        amount_y: 7_654_321,
+        active_id,
+        bin_step,
+        min_delta_id: -12,
+        max_delta_id: 12,
+        favor_x_in_active_id: true,
+        strategy: spot_with_bid_ask_tails(),
+    };
+
+    let ranges = params.get_parsed_add_liquidity_ranges().unwrap();
+    let quoted_amount_in_bins = params.get_amount_in_bins_of_ranges(&ranges).unwrap();
+
+    let rebalance_params = build_add_liquidity_params(
+        params.amount_x,
+        params.amount_y,
+        active_id,
+        bin_step,
+        params.min_delta_id,
+        params.max_delta_id,
+        params.favor_x_in_active_id,
+        params.strategy.clone(),
+    );
+    let amount_in_bins = get_bin_add_liquidity(&rebalance_params, active_id, bin_step).unwrap();
+
+    for bin in amount_in_bins.iter() {
+        let quoted_bin = quoted_amount_in_bins
+            .iter()
+            .find(|quoted_bin| quoted_bin.bin_id == bin.bin_id)
+            .unwrap();
+        assert_eq!(quoted_bin, bin);
+    }
+    // bins out of segments are empty
+    for bin in quoted_amount_in_bins.iter() {
+        if (bin.bin_id - active_id).abs() > 10 {
+            assert_eq!(bin.amount_x + bin.amount_y, 0);
+        }
+    }
+}
+
+#[test]
+fn test_strategy_invalid_segments() {
+    let segment = |min_delta_id: i32, max_delta_id: i32| StrategySegment {
+        min_delta_id,
+        max_delta_id,
+        strategy: LinearStrategyType::Spot,
+        weight: 1,
+    };
+    let invalid_segments = [
+        vec![],
+        // overlapping
+        vec![segment(-5, 0), segment(0, 5)],
+        // descending
+        vec![segment(1, 5), segment(-5, 0)],
+        // out of range
+        vec![segment(-6, 0), segment(1, 5)],
+        vec![segment(-5, 0), segment(1, 6)],
+    ];
+
+    for segments in invalid_segments {
+        let params = ZapInRebalancingParams {
+            amount_x: 100_000,
+            amount_y: 100_000,
+            active_id: 100,
+            bin_step: 10,
+            min_delta_id: -5,
+            max_delta_id: 5,
+            favor_x_in_active_id: false,
+            strategy: StrategyType::Piecewise { segments },
+        };
+        assert!(params.get_add_liquidity_params().is_err());
+    }
+}
@@ -1,13 +1,12 @@
 use anchor_lang::prelude::*;
-use dlmm::types::{AddLiquidityParams as DlmmAddLiquidityParams, RebalanceLiquidityParams};
+use dlmm::types::RebalanceLiquidityParams;
 use ruint::aliases::U256;
 use std::ops::Neg;
 
+pub use crate::AmountInBin;
 use crate::{
-    error::ZapError,
-    price_math::{get_price_base_factor, get_price_from_id, pow},
-    safe_math::SafeMath,
-    StrategyType, UnparsedAddLiquidityParams, ZapInRebalancingParams,
+    error::ZapError, get_amount_in_bins_ask_side, get_amount_in_bins_bid_side,
+    price_math::get_price_from_id, safe_math::SafeMath, StrategyType, ZapInRebalancingParams,
 };
 
 pub fn build_add_liquidity_params(
@@ -31,13 +30,7 @@ pub fn build_add_liquidity_params(
         strategy,
     };
 
-    let UnparsedAddLiquidityParams {
-        x0,
-        y0,
-        delta_x,
-        delta_y,
-        bit_flag,
-    } = params.get_rebalancing_params().unwrap();
+    let adds = params.get_add_liquidity_params().unwrap();
 
     // bid side
     let params = RebalanceLiquidityParams {
@@ -52,26 +45,10 @@ pub fn build_add_liquidity_params(
         shrink_mode: 3, // we dont allow to shrink in both side
         padding: [0; 31],
         removes: vec![],
-        adds: vec![DlmmAddLiquidityParams {
-            min_delta_id,
-            max_delta_id,
-            x0,
-            y0,
-            delta_x,
-            delta_y,
-            favor_x_in_active_id,
-            bit_flag,
-            ..Default::default()
-        }],
+        adds,
     };
     params
 }
-#[derive(Debug, Clone, Copy, Default)]
-pub struct AmountInBin {
-    pub bin_id: i32,
-    pub amount_x: u64,
-    pub amount_y: u64,
-}
 
 pub fn get_liquidity_distribution(amount_in_bins: &[AmountInBin], bin_step: u16) -> Vec<u64> {
     let mut liquidity_distributions = vec![];
@@ -307,96 +284,3 @@ pub struct ParsedAddLiquidityParams {
 //         })
 //     }
 // }
-
-pub fn get_amount_in_bins_bid_side(
-    active_id: i32,
-    min_delta_id: i32,
-    max_delta_id: i32,
-    delta_y: i128,
-    y0: i128,
-) -> Result<Vec<AmountInBin>> {
-    // This won't be negative because already validated min_bin_id <= max_bin_id at validate_and_get_bin_range
-    let bin_count = max_delta_id.safe_sub(min_delta_id)?.safe_add(1)?;
-    let mut amounts_in_bin = vec![AmountInBin::default(); bin_count as usize];
-
-    let min_bin_id = active_id.safe_add(min_delta_id)?;
-    let max_bin_id = active_id.safe_add(max_delta_id)?;
-
-    for (idx, bin_id) in (min_bin_id..=max_bin_id).enumerate() {
-        let delta_bin = active_id.safe_sub(bin_id)?;
-
-        let total_delta_y = delta_y.safe_mul(delta_bin.into())?;
-
-        let amount_y = y0
-            .safe_add(total_delta_y)?
-            .try_into()
-            .map_err(|_| ZapError::TypeCastFailed)?;
-
-        amounts_in_bin[idx] = AmountInBin {
-            bin_id,
-            amount_x: 0,
-            amount_y,
-        };
-    }
-
-    Ok(amounts_in_bin)
-}
-
-pub fn get_amount_in_bins_ask_side(
-    active_id: i32,
-    bin_step: u16,
-    min_delta_id: i32,
-    max_delta_id: i32,
-    delta_x: i128,
-    x0: i128,
-) -> Result<Vec<AmountInBin>> {
-    // This won't be negative because already validated min_bin_id <= max_bin_id at validate_and_get_bin_range
-    let bin_count = max_delta_id.safe_sub(min_delta_id)?.safe_add(1)?;
-
-    let base_u128 = get_price_base_factor(bin_step)?;
-    let base = U256::from(base_u128);
-
-    let max_bin_id = active_id.safe_add(max_delta_id)?;
-    let min_bin_id = active_id.safe_add(min_delta_id)?;
-
-    // we use inverse base price to avoid safe_div (safe_mul can save more CU)
-    let mut inverse_base_price =
-        U256::from(pow(base_u128, max_bin_id.neg().into()).ok_or_else(|| ZapError::MathOverflow)?);
-
-    let mut amounts_in_bin = vec![AmountInBin::default(); bin_count as usize];
-    let mut current_bin_id = max_bin_id;
-
-    loop {
-        if current_bin_id < min_bin_id {
-            break;
-        }
-
-        let delta_bin = current_bin_id.safe_sub(active_id)?;
-
-        let total_delta_x = delta_x.safe_mul(delta_bin.into())?;
-
-        let amount_x =
-            U256::try_from(x0.safe_add(total_delta_x)?).map_err(|_| ZapError::TypeCastFailed)?;
-
-        let (amount_x, _) = amount_x.safe_mul(inverse_base_price)?.overflowing_shr(64);
-
-        let amount_x = u64::try_from(amount_x).map_err(|_| ZapError::TypeCastFailed)?;
-
-        let idx: usize = current_bin_id
-            .safe_sub(min_bin_id)?
-            .try_into()
-            .map_err(|_| ZapError::TypeCastFailed)?;
-
-        amounts_in_bin[idx] = AmountInBin {
-            bin_id: current_bin_id,
-            amount_x,
-            amount_y: 0,
-        };
-
-        (inverse_base_price, _) = inverse_base_price.safe_mul(base)?.overflowing_shr(64);
-
-        current_bin_id = current_bin_id.safe_sub(1)?;
-    }
-
-    Ok(amounts_in_bin)
-}
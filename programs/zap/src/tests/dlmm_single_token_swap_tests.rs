@@ -0,0 +1,360 @@
+use dlmm::accounts::{BinArray, LbPair};
+
+use crate::{
+    calculate_dlmm_single_token_swap_amount, calculate_dlmm_swap_amount,
+    price_math::get_price_from_id,
+    tests::dlmm_rebalancing_tests::utils::get_liquidity_distribution, DlmmSwapSimulator,
+    LinearStrategyType, StrategySegment, StrategyType, TransferFeeCalculator,
+    ZapInRebalancingParams,
+};
+
+const BIN_STEP: u16 = 10;
+const CURRENT_TIMESTAMP: i64 = 1_762_837_786;
+
+fn get_lb_pair(active_id: i32) -> LbPair {
+    let mut lb_pair: LbPair = bytemuck::Zeroable::zeroed();
+    lb_pair.active_id = active_id;
+    lb_pair.bin_step = BIN_STEP;
+    // 0.1% base fee
+    lb_pair.parameters.base_factor = 10_000;
+    lb_pair.parameters.filter_period = 30;
+    lb_pair.parameters.decay_period = 600;
+    lb_pair.parameters.reduction_factor = 5_000;
+    lb_pair.parameters.max_volatility_accumulator = 350_000;
+    lb_pair.parameters.min_bin_id = -443_636;
+    lb_pair.parameters.max_bin_id = 443_636;
+    lb_pair.v_parameters.last_update_timestamp = CURRENT_TIMESTAMP;
+    lb_pair
+}
+
+// same amount in each bin, y in bins lower than active id and x in bins higher than active id
+fn get_bin_array(lb_pair: &LbPair, index: i64, amount_per_bin: u64) -> Box<BinArray> {
+    let mut bin_array: Box<BinArray> = Box::new(bytemuck::Zeroable::zeroed());
+    bin_array.index = index;
+    for (i, bin) in bin_array.bins.iter_mut().enumerate() {
+        let bin_id = i32::try_from(index * 70 + i as i64).unwrap();
+        bin.price = get_price_from_id(bin_id, lb_pair.bin_step).unwrap();
+        if bin_id <= lb_pair.active_id {
+            bin.amount_y = amount_per_bin;
+        }
+        if bin_id >= lb_pair.active_id {
+            bin.amount_x = amount_per_bin;
+        }
+    }
+    bin_array
+}
+
+fn get_params(lb_pair: &LbPair, strategy: StrategyType) -> ZapInRebalancingParams {
+    ZapInRebalancingParams {
+        amount_x: 0,
+        amount_y: 0,
+        active_id: lb_pair.active_id,
+        bin_step: lb_pair.bin_step,
+        min_delta_id: -10,
+        max_delta_id: 10,
+        favor_x_in_active_id: false,
+        strategy,
+    }
+}
+
+#[test]
+fn test_simulate_swap_in_active_bin() {
+    let lb_pair = get_lb_pair(10);
+    let bin_array = get_bin_array(&lb_pair, 0, 1_000_000_000_000);
+    let simulator = DlmmSwapSimulator::new(&lb_pair, &[&bin_array], CURRENT_TIMESTAMP);
+
+    let amount_in = 1_000_000;
+    let swap_result = simulator.simulate(amount_in, true).unwrap().unwrap();
+    assert_eq!(swap_result.end_active_id, lb_pair.active_id);
+
+    // out = (in - 0.1% fee) * price
+    let price = get_price_from_id(lb_pair.active_id, BIN_STEP).unwrap();
+    let expected_amount_out = (u128::from(amount_in - 1_000) * price) >> 64;
+    assert_eq!(u128::from(swap_result.amount_out), expected_amount_out);
+}
+
+#[test]
+fn test_simulate_swap_across_bins() {
+    let lb_pair = get_lb_pair(0);
+    let bin_arrays = [
+        get_bin_array(&lb_pair, -1, 1_000_000),
+        get_bin_array(&lb_pair, 0, 1_000_000),
+    ];
+    let bin_arrays = bin_arrays
+        .iter()
+        .map(|bin_array| &**bin_array)
+        .collect::<Vec<_>>();
+    let simulator = DlmmSwapSimulator::new(&lb_pair, &bin_arrays, CURRENT_TIMESTAMP);
+
+    // y to x moves active id up, x to y moves active id down
+    let swap_result = simulator.simulate(10_500_000, false).unwrap().unwrap();
+    assert!(swap_result.end_active_id >= 9);
+    assert!(swap_result.amount_out > 9_000_000);
+
+    let swap_result = simulator.simulate(10_500_000, true).unwrap().unwrap();
+    assert!(swap_result.end_active_id <= -9);
+
+    // not enough liquidity in given bin arrays
+    assert!(simulator.simulate(100_000_000, false).unwrap().is_none());
+}
+
+#[test]
+fn test_single_token_swap_amount_matches_strategy() {
+    let lb_pair = get_lb_pair(35);
+    let bin_array = get_bin_array(&lb_pair, 0, 100_000_000);
+    let simulator = DlmmSwapSimulator::new(&lb_pair, &[&bin_array], CURRENT_TIMESTAMP);
+    let transfer_fee_calculator = TransferFeeCalculator::no_transfer_fee();
+
+    for (strategy, swap_for_y) in [
+        (StrategyType::Spot, false),
+        (StrategyType::Spot, true),
+        (StrategyType::Curve, false),
+        (StrategyType::BidAsk, true),
+        (
+            StrategyType::Custom {
+                weights: vec![1; 21],
+            },
+            false,
+        ),
+    ] {
+        let params = get_params(&lb_pair, strategy.clone());
+        let amount_in = 1_000_000_000;
+        let (swap_in_amount, swap_out_amount) = calculate_dlmm_single_token_swap_amount(
+            &simulator,
+            &params,
+            &transfer_fee_calculator,
+            &transfer_fee_calculator,
+            amount_in,
+            swap_for_y,
+        )
+        .unwrap();
+        assert!(swap_in_amount > 0 && swap_in_amount < amount_in);
+
+        // deposit with amounts after swap around the new active id
+        let swap_result = simulator
+            .simulate(swap_in_amount, swap_for_y)
+            .unwrap()
+            .unwrap();
+        assert_eq!(swap_result.amount_out, swap_out_amount);
+        let remaining_amount = amount_in - swap_in_amount;
+        let (amount_x, amount_y) = if swap_for_y {
+            (remaining_amount, swap_out_amount)
+        } else {
+            (swap_out_amount, remaining_amount)
+        };
+        let params = ZapInRebalancingParams {
+            amount_x,
+            amount_y,
+            active_id: swap_result.end_active_id,
+            ..get_params(&lb_pair, strategy)
+        };
+        let ranges = params.get_parsed_add_liquidity_ranges().unwrap();
+        let amount_in_bins = params.get_amount_in_bins_of_ranges(&ranges).unwrap();
+        let liquidity_distributions = get_liquidity_distribution(&amount_in_bins, BIN_STEP);
+        println!("{:?}", liquidity_distributions);
+
+        // all strategies are symmetric around the active bin, bins next to it have the same value
+        let bid_bin_value = liquidity_distributions[9];
+        // except bid ask which is empty in the ask bin next to active id
+        let ask_bin_value = if params.strategy == StrategyType::BidAsk {
+            liquidity_distributions[12]
+        } else {
+            liquidity_distributions[11]
+        };
+        assert!(
+            bid_bin_value.abs_diff(ask_bin_value) <= bid_bin_value / 1_000, // less than 10bps
+            "{:?} {} {}",
+            params.strategy,
+            bid_bin_value,
+            ask_bin_value
+        );
+    }
+}
+
+#[test]
+fn test_single_token_swap_amount_one_side_range() {
+    let lb_pair = get_lb_pair(35);
+    let bin_array = get_bin_array(&lb_pair, 0, 100_000_000);
+    let simulator = DlmmSwapSimulator::new(&lb_pair, &[&bin_array], CURRENT_TIMESTAMP);
+    let transfer_fee_calculator = TransferFeeCalculator::no_transfer_fee();
+    // only bid side bins
+    let params = ZapInRebalancingParams {
+        max_delta_id: 0,
+        ..get_params(&lb_pair, StrategyType::Spot)
+    };
+    let amount_in = 10_000_000;
+
+    // all x is swapped to y
+    let (swap_in_amount, swap_out_amount) = calculate_dlmm_single_token_swap_amount(
+        &simulator,
+        &params,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        amount_in,
+        true,
+    )
+    .unwrap();
+    assert_eq!(swap_in_amount, amount_in);
+    assert!(swap_out_amount > 0);
+
+    // y isn't swapped
+    let (swap_in_amount, swap_out_amount) = calculate_dlmm_single_token_swap_amount(
+        &simulator,
+        &params,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        amount_in,
+        false,
+    )
+    .unwrap();
+    assert_eq!(swap_in_amount, 0);
+    assert_eq!(swap_out_amount, 0);
+}
+
+#[test]
+fn test_swap_amount_of_both_sides() {
+    let lb_pair = get_lb_pair(35);
+    let bin_array = get_bin_array(&lb_pair, 0, 100_000_000);
+    let simulator = DlmmSwapSimulator::new(&lb_pair, &[&bin_array], CURRENT_TIMESTAMP);
+    let transfer_fee_calculator = TransferFeeCalculator::no_transfer_fee();
+    // spot has 11 bid side bins with the same y, and 10 ask side bins with the same x * price of bin
+    let get_expected_amount_y = |amount_x: u64, active_id: i32| {
+        let price = get_price_from_id(active_id, BIN_STEP).unwrap();
+        let ask_side_bins: f64 = (1..=10).map(|i| 1.001f64.powi(-i)).sum();
+        let value_of_x = ((u128::from(amount_x) * price) >> 64) as f64;
+        (value_of_x * 11.0 / ask_side_bins) as u64
+    };
+
+    // x is more than needed, swap x to y
+    let params = ZapInRebalancingParams {
+        amount_x: 800_000_000,
+        amount_y: 100_000_000,
+        ..get_params(&lb_pair, StrategyType::Spot)
+    };
+    let (swap_in_amount, swap_out_amount, swap_for_y) = calculate_dlmm_swap_amount(
+        &simulator,
+        &params,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+    )
+    .unwrap();
+    assert!(swap_for_y);
+    assert!(swap_in_amount > 0 && swap_in_amount < params.amount_x);
+    let amount_x = params.amount_x - swap_in_amount;
+    let amount_y = params.amount_y + swap_out_amount;
+    // around active id after swap
+    let end_active_id = simulator
+        .simulate(swap_in_amount, swap_for_y)
+        .unwrap()
+        .unwrap()
+        .end_active_id;
+    assert!(end_active_id < lb_pair.active_id);
+    let expected_amount_y = get_expected_amount_y(amount_x, end_active_id);
+    assert!(amount_y.abs_diff(expected_amount_y) <= expected_amount_y / 1_000); // less than 10bps
+
+    // amounts already match the strategy
+    let amount_x = 100_000_000;
+    let params = ZapInRebalancingParams {
+        amount_x,
+        amount_y: get_expected_amount_y(amount_x, lb_pair.active_id),
+        ..get_params(&lb_pair, StrategyType::Spot)
+    };
+    let (swap_in_amount, _swap_out_amount, _swap_for_y) = calculate_dlmm_swap_amount(
+        &simulator,
+        &params,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+    )
+    .unwrap();
+    assert!(swap_in_amount <= amount_x / 1_000);
+}
+
+fn get_segment(
+    min_delta_id: i32,
+    max_delta_id: i32,
+    strategy: LinearStrategyType,
+    weight: u16,
+) -> StrategySegment {
+    StrategySegment {
+        min_delta_id,
+        max_delta_id,
+        strategy,
+        weight,
+    }
+}
+
+#[test]
+fn test_single_token_swap_amount_piecewise() {
+    let lb_pair = get_lb_pair(35);
+    let bin_array = get_bin_array(&lb_pair, 0, 100_000_000);
+    let simulator = DlmmSwapSimulator::new(&lb_pair, &[&bin_array], CURRENT_TIMESTAMP);
+    let transfer_fee_calculator = TransferFeeCalculator::no_transfer_fee();
+
+    for (segments, is_spanning_active_id) in [
+        // spot segment around active id with bid ask tails
+        (
+            vec![
+                get_segment(-10, -6, LinearStrategyType::BidAsk, 1),
+                get_segment(-5, 5, LinearStrategyType::Spot, 2),
+                get_segment(6, 10, LinearStrategyType::BidAsk, 1),
+            ],
+            true,
+        ),
+        // no segment has both side bins
+        (
+            vec![
+                get_segment(-10, 0, LinearStrategyType::Spot, 1),
+                get_segment(1, 10, LinearStrategyType::Spot, 1),
+            ],
+            false,
+        ),
+    ] {
+        let strategy = StrategyType::Piecewise { segments };
+        let params = get_params(&lb_pair, strategy.clone());
+        let amount_in = 1_000_000_000;
+        let (swap_in_amount, swap_out_amount) = calculate_dlmm_single_token_swap_amount(
+            &simulator,
+            &params,
+            &transfer_fee_calculator,
+            &transfer_fee_calculator,
+            amount_in,
+            true,
+        )
+        .unwrap();
+        assert!(swap_in_amount > 0 && swap_in_amount < amount_in);
+
+        let end_active_id = simulator
+            .simulate(swap_in_amount, true)
+            .unwrap()
+            .unwrap()
+            .end_active_id;
+        let params = ZapInRebalancingParams {
+            amount_x: amount_in - swap_in_amount,
+            amount_y: swap_out_amount,
+            active_id: end_active_id,
+            ..get_params(&lb_pair, strategy)
+        };
+        let ranges = params.get_parsed_add_liquidity_ranges().unwrap();
+        let amount_in_bins = params.get_amount_in_bins_of_ranges(&ranges).unwrap();
+        let liquidity_distributions = get_liquidity_distribution(&amount_in_bins, BIN_STEP);
+        println!("{:?}", liquidity_distributions);
+
+        let (bid_value, ask_value) = if is_spanning_active_id {
+            // spot bins next to active id have the same value
+            (liquidity_distributions[9], liquidity_distributions[11])
+        } else {
+            // segments of the same weight have the same value
+            (
+                liquidity_distributions[..=10].iter().sum::<u64>(),
+                liquidity_distributions[11..].iter().sum::<u64>(),
+            )
+        };
+        assert!(
+            bid_value.abs_diff(ask_value) <= bid_value / 1_000, // less than 10bps
+            "{:?} {} {}",
+            params.strategy,
+            bid_value,
+            ask_value
+        );
+    }
+}
@@ -6,3 +6,21 @@ mod dlmm_rebalancing_tests;
 
 #[cfg(test)]
 mod zap_in_damm_v2_tests;
+
+#[cfg(test)]
+mod dlmm_single_token_swap_tests;
+
+#[cfg(test)]
+mod dlmm_position_length_tests;
+
+#[cfg(test)]
+mod dlmm_multiple_positions_tests;
+
+#[cfg(test)]
+mod price_math_tests;
+
+#[cfg(test)]
+mod dlmm_price_deviation_tests;
+
+#[cfg(test)]
+mod dlmm_bin_array_tests;
@@ -0,0 +1,98 @@
+use crate::{
+    get_delta_id_range, get_q64_price_from_decimal_price,
+    price_math::{get_id_from_price, get_price_from_id, MAX_BIN_ID, MIN_BIN_ID},
+    PriceRange,
+};
+
+#[test]
+fn test_get_id_from_price_of_bin_price() {
+    for bin_step in [1, 10, 25, 100] {
+        for id in [-3_000, -70, -1, 0, 1, 69, 3_000] {
+            let price = get_price_from_id(id, bin_step).unwrap();
+            assert_eq!(get_id_from_price(price, bin_step, false).unwrap(), id);
+            assert_eq!(get_id_from_price(price, bin_step, true).unwrap(), id);
+
+            // price between bins is rounded to the bin below or above
+            assert_eq!(get_id_from_price(price + 1, bin_step, false).unwrap(), id);
+            assert_eq!(
+                get_id_from_price(price + 1, bin_step, true).unwrap(),
+                id + 1
+            );
+            assert_eq!(
+                get_id_from_price(price - 1, bin_step, false).unwrap(),
+                id - 1
+            );
+            assert_eq!(get_id_from_price(price - 1, bin_step, true).unwrap(), id);
+        }
+    }
+}
+
+#[test]
+fn test_get_id_from_price_bounds() {
+    assert!(get_id_from_price(0, 10, false).is_err());
+
+    // huge and tiny prices are clamped by the price range of bin step
+    let id = get_id_from_price(u128::MAX, 100, false).unwrap();
+    assert!(get_price_from_id(id, 100).is_ok());
+    assert!(get_price_from_id(id + 1, 100).is_err());
+    assert!(id < MAX_BIN_ID);
+
+    let id = get_id_from_price(1, 100, true).unwrap();
+    assert!(get_price_from_id(id, 100).unwrap() >= 1);
+    assert!(id > MIN_BIN_ID);
+}
+
+#[test]
+fn test_decimal_price_range() {
+    // SOL (9 decimals) - USDC (6 decimals), 150 USDC per SOL is 0.15 USDC lamport per SOL lamport
+    let price = get_q64_price_from_decimal_price(150_000_000, 6, 9, 6).unwrap();
+    assert_eq!(price, (150u128 << 64) / 1_000);
+
+    let bin_step = 10;
+    let (lower_bin_id, upper_bin_id) = PriceRange::Decimal {
+        lower_price: 140_000_000,
+        upper_price: 160_000_000,
+        decimals: 6,
+    }
+    .get_bin_range(bin_step, 9, 6)
+    .unwrap();
+    // ln(0.14) / ln(1.001) = -1967.1, ln(0.16) / ln(1.001) = -1833.5
+    assert_eq!(lower_bin_id, -1968);
+    assert_eq!(upper_bin_id, -1833);
+    assert!(get_price_from_id(lower_bin_id, bin_step).unwrap() <= (140u128 << 64) / 1_000);
+    assert!(get_price_from_id(upper_bin_id, bin_step).unwrap() >= (160u128 << 64) / 1_000);
+
+    // the same range in Q64.64
+    assert_eq!(
+        PriceRange::Q64 {
+            lower_price: get_q64_price_from_decimal_price(140_000_000, 6, 9, 6).unwrap(),
+            upper_price: get_q64_price_from_decimal_price(160_000_000, 6, 9, 6).unwrap(),
+        }
+        .get_bin_range(bin_step, 0, 0)
+        .unwrap(),
+        (lower_bin_id, upper_bin_id)
+    );
+
+    // lower price above upper price
+    assert!(PriceRange::Decimal {
+        lower_price: 160_000_000,
+        upper_price: 140_000_000,
+        decimals: 6,
+    }
+    .get_bin_range(bin_step, 9, 6)
+    .is_err());
+}
+
+#[test]
+fn test_delta_id_range() {
+    assert_eq!(get_delta_id_range(None, -10, 10, 500).unwrap(), (-10, 10));
+    // price range follows active id
+    assert_eq!(
+        get_delta_id_range(Some((490, 520)), -10, 10, 500).unwrap(),
+        (-10, 20)
+    );
+    assert_eq!(
+        get_delta_id_range(Some((490, 520)), -10, 10, 505).unwrap(),
+        (-15, 15)
+    );
+}
@@ -3,7 +3,10 @@ use std::fs;
 use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFee;
 use damm_v2::{params::swap::TradeDirection, state::Pool};
 
-use crate::{calculate_swap_amount, TransferFeeCalculator};
+use crate::{
+    calculate_swap_amount, calculate_swap_amount_by_binary_search, get_zap_in_damm_v2_quote,
+    TransferFeeCalculator, UserLedger,
+};
 
 pub const SOL_USDC_CL_ADDRESS: &str = "8Pm2kZpnxD3hoMmt4bjStX2Pw2Z9abpbHzZxMPqxPmie";
 
@@ -35,6 +38,7 @@ fn test_calculate_swap_result() {
         remaining_amount,
         trade_direction,
         current_point,
+        false,
     )
     .unwrap();
 
@@ -42,3 +46,322 @@ fn test_calculate_swap_result() {
     // https://app.meteora.ag/dammv2/8Pm2kZpnxD3hoMmt4bjStX2Pw2Z9abpbHzZxMPqxPmie?referrer=home
     println!("swap_amount: {}", swap_amount);
 }
+
+#[test]
+fn test_calculate_swap_amount_closed_form_matches_binary_search() {
+    let pool = get_pool_account(SOL_USDC_CL_ADDRESS);
+
+    let current_point = 1762837786;
+    let transfer_fee_calculator = TransferFeeCalculator {
+        epoch_transfer_fee: TransferFee::default(),
+        no_transfer_fee_extension: true,
+    };
+
+    for (trade_direction, remaining_amount) in [
+        (TradeDirection::AtoB, 1_000_000_000),  // 1 sol
+        (TradeDirection::AtoB, 50_000_000_000), // 50 sol
+        (TradeDirection::BtoA, 150_000_000),    // 150 usdc
+        (TradeDirection::BtoA, 10_000_000_000), // 10k usdc
+    ] {
+        let (swap_amount, swap_out_amount) = calculate_swap_amount(
+            &pool,
+            &transfer_fee_calculator,
+            &transfer_fee_calculator,
+            remaining_amount,
+            trade_direction,
+            current_point,
+            false,
+        )
+        .unwrap();
+
+        let (binary_search_swap_amount, _binary_search_swap_out_amount) =
+            calculate_swap_amount_by_binary_search(
+                &pool,
+                &transfer_fee_calculator,
+                &transfer_fee_calculator,
+                remaining_amount,
+                trade_direction,
+                current_point,
+                false,
+            )
+            .unwrap();
+
+        println!(
+            "swap_amount: {} {} binary_search_swap_amount: {}",
+            swap_amount, swap_out_amount, binary_search_swap_amount
+        );
+
+        assert!(swap_amount > 0 && swap_out_amount > 0);
+        // both results are within 0.1% tolerance of the expected ratio
+        let diff = swap_amount.abs_diff(binary_search_swap_amount);
+        assert!(u128::from(diff) * 1000 <= u128::from(remaining_amount));
+    }
+}
+
+#[test]
+fn test_quote_zap_in_damm_v2() {
+    let pool = get_pool_account(SOL_USDC_CL_ADDRESS);
+
+    let current_point = 1762837786;
+    let current_timestamp = current_point;
+    let transfer_fee_calculator = TransferFeeCalculator {
+        epoch_transfer_fee: TransferFee::default(),
+        no_transfer_fee_extension: true,
+    };
+
+    // single token zap in, all liquidity comes from the swap
+    let quote = get_zap_in_damm_v2_quote(
+        &pool,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        1_000_000_000, // 1 sol
+        0,
+        current_point,
+        current_timestamp,
+        false,
+        1,
+        0,
+    )
+    .unwrap();
+    println!("{:?}", quote);
+    assert_eq!(quote.first_liquidity_delta, 0);
+    assert_eq!(quote.trade_direction, TradeDirection::AtoB as u8);
+    assert!(quote.swap_in_amount > 0 && quote.swap_out_amount > 0);
+    assert!(quote.post_sqrt_price < pool.sqrt_price);
+    assert!(quote.liquidity_delta > 0);
+
+    let quote = get_zap_in_damm_v2_quote(
+        &pool,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        0,
+        150_000_000, // 150 usdc
+        current_point,
+        current_timestamp,
+        false,
+        1,
+        0,
+    )
+    .unwrap();
+    println!("{:?}", quote);
+    assert_eq!(quote.trade_direction, TradeDirection::BtoA as u8);
+    assert!(quote.swap_in_amount > 0 && quote.swap_out_amount > 0);
+    assert!(quote.post_sqrt_price > pool.sqrt_price);
+    assert!(quote.liquidity_delta > 0);
+}
+
+#[test]
+fn test_quote_zap_in_damm_v2_multiple_rounds() {
+    let pool = get_pool_account(SOL_USDC_CL_ADDRESS);
+
+    let current_point = 1762837786;
+    let current_timestamp = current_point;
+    let transfer_fee_calculator = TransferFeeCalculator {
+        epoch_transfer_fee: TransferFee::default(),
+        no_transfer_fee_extension: true,
+    };
+
+    let single_round_quote = get_zap_in_damm_v2_quote(
+        &pool,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        50_000_000_000, // 50 sol
+        0,
+        current_point,
+        current_timestamp,
+        false,
+        1,
+        0,
+    )
+    .unwrap();
+
+    let multiple_rounds_quote = get_zap_in_damm_v2_quote(
+        &pool,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        50_000_000_000, // 50 sol
+        0,
+        current_point,
+        current_timestamp,
+        false,
+        5,
+        0,
+    )
+    .unwrap();
+    println!("{:?} {:?}", single_round_quote, multiple_rounds_quote);
+
+    // more rounds never add less liquidity
+    assert!(multiple_rounds_quote.liquidity_delta >= single_round_quote.liquidity_delta);
+    assert!(multiple_rounds_quote.swap_in_amount >= single_round_quote.swap_in_amount);
+
+    // remaining value is below dust threshold, so swap is skipped
+    let quote = get_zap_in_damm_v2_quote(
+        &pool,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        1_000, // 0.000001 sol
+        0,
+        current_point,
+        current_timestamp,
+        false,
+        5,
+        1_000_000, // 1 usdc
+    )
+    .unwrap();
+    assert_eq!(quote.swap_in_amount, 0);
+    assert_eq!(quote.liquidity_delta, 0);
+}
+
+#[test]
+fn test_get_liquidity_at_price_range_boundaries() {
+    let pool = get_pool_account(SOL_USDC_CL_ADDRESS);
+
+    let transfer_fee_calculator = TransferFeeCalculator {
+        epoch_transfer_fee: TransferFee::default(),
+        no_transfer_fee_extension: true,
+    };
+    let ledger = UserLedger {
+        amount_a: 1_000_000_000, // 1 sol
+        amount_b: 150_000_000,   // 150 usdc
+        ..Default::default()
+    };
+
+    // pool at max price, only token b could be deposited
+    let (liquidity, trade_direction) = ledger
+        .get_liquidity_from_amounts_and_trade_direction(
+            &transfer_fee_calculator,
+            &transfer_fee_calculator,
+            pool.sqrt_max_price,
+            pool.sqrt_min_price,
+            pool.sqrt_max_price,
+        )
+        .unwrap();
+    assert!(liquidity > 0);
+    assert_eq!(trade_direction, TradeDirection::AtoB);
+
+    // pool at min price, only token a could be deposited
+    let (liquidity, trade_direction) = ledger
+        .get_liquidity_from_amounts_and_trade_direction(
+            &transfer_fee_calculator,
+            &transfer_fee_calculator,
+            pool.sqrt_min_price,
+            pool.sqrt_min_price,
+            pool.sqrt_max_price,
+        )
+        .unwrap();
+    assert!(liquidity > 0);
+    assert_eq!(trade_direction, TradeDirection::BtoA);
+
+    // unusable side only, nothing to deposit before swap
+    let ledger = UserLedger {
+        amount_a: 1_000_000_000,
+        ..Default::default()
+    };
+    let (liquidity, trade_direction) = ledger
+        .get_liquidity_from_amounts_and_trade_direction(
+            &transfer_fee_calculator,
+            &transfer_fee_calculator,
+            pool.sqrt_max_price,
+            pool.sqrt_min_price,
+            pool.sqrt_max_price,
+        )
+        .unwrap();
+    assert_eq!(liquidity, 0);
+    assert_eq!(trade_direction, TradeDirection::AtoB);
+}
+
+#[test]
+fn test_quote_zap_in_damm_v2_at_price_range_boundaries() {
+    let pool = get_pool_account(SOL_USDC_CL_ADDRESS);
+
+    let current_point = 1762837786;
+    let current_timestamp = current_point;
+    let transfer_fee_calculator = TransferFeeCalculator {
+        epoch_transfer_fee: TransferFee::default(),
+        no_transfer_fee_extension: true,
+    };
+
+    // pool at max price, token a is swapped to token b before adding liquidity
+    let mut pool_at_max_price = pool;
+    pool_at_max_price.sqrt_price = pool.sqrt_max_price;
+    let quote = get_zap_in_damm_v2_quote(
+        &pool_at_max_price,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        1_000_000_000, // 1 sol
+        0,
+        current_point,
+        current_timestamp,
+        false,
+        1,
+        0,
+    )
+    .unwrap();
+    println!("{:?}", quote);
+    assert_eq!(quote.first_liquidity_delta, 0);
+    assert_eq!(quote.trade_direction, TradeDirection::AtoB as u8);
+    assert!(quote.swap_in_amount > 0 && quote.swap_out_amount > 0);
+    assert!(quote.post_sqrt_price < pool.sqrt_max_price);
+    assert!(quote.liquidity_delta > 0);
+
+    // token b only, single sided deposit without swap
+    let quote = get_zap_in_damm_v2_quote(
+        &pool_at_max_price,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        0,
+        150_000_000, // 150 usdc
+        current_point,
+        current_timestamp,
+        false,
+        1,
+        0,
+    )
+    .unwrap();
+    println!("{:?}", quote);
+    assert!(quote.first_liquidity_delta > 0);
+    assert_eq!(quote.swap_in_amount, 0);
+    assert_eq!(quote.liquidity_delta, quote.first_liquidity_delta);
+
+    // pool at min price, token b is swapped to token a before adding liquidity
+    let mut pool_at_min_price = pool;
+    pool_at_min_price.sqrt_price = pool.sqrt_min_price;
+    let quote = get_zap_in_damm_v2_quote(
+        &pool_at_min_price,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        0,
+        150_000_000, // 150 usdc
+        current_point,
+        current_timestamp,
+        false,
+        1,
+        0,
+    )
+    .unwrap();
+    println!("{:?}", quote);
+    assert_eq!(quote.first_liquidity_delta, 0);
+    assert_eq!(quote.trade_direction, TradeDirection::BtoA as u8);
+    assert!(quote.swap_in_amount > 0 && quote.swap_out_amount > 0);
+    assert!(quote.post_sqrt_price > pool.sqrt_min_price);
+    assert!(quote.liquidity_delta > 0);
+
+    // token a only, single sided deposit without swap
+    let quote = get_zap_in_damm_v2_quote(
+        &pool_at_min_price,
+        &transfer_fee_calculator,
+        &transfer_fee_calculator,
+        1_000_000_000, // 1 sol
+        0,
+        current_point,
+        current_timestamp,
+        false,
+        1,
+        0,
+    )
+    .unwrap();
+    println!("{:?}", quote);
+    assert!(quote.first_liquidity_delta > 0);
+    assert_eq!(quote.swap_in_amount, 0);
+    assert_eq!(quote.liquidity_delta, quote.first_liquidity_delta);
+}
@@ -4,7 +4,7 @@ use damm_v2::{
         fee_rate_limiter::PodAlignedFeeRateLimiter, BaseFeeEnumReader, BaseFeeHandler,
         BaseFeeHandlerBuilder,
     },
-    constants::fee::get_max_fee_numerator,
+    constants::fee::{get_max_fee_numerator, FEE_DENOMINATOR},
     curve::{
         get_delta_amount_a_unsigned, get_delta_amount_b_unsigned, get_next_sqrt_price_from_input,
     },
@@ -20,10 +20,12 @@ use ruint::aliases::{U192, U256, U512};
 
 use crate::{
     constants::MAX_BASIS_POINT, error::ZapError, safe_math::SafeMath, TransferFeeCalculator,
+    UserLedger,
 };
 
 struct SwapAmountFromInput {
     output_amount: u64,
+    next_sqrt_price: u128,
 }
 
 pub fn get_swap_result_status(
@@ -91,7 +93,10 @@ fn calculate_a_to_b_from_amount_in(pool: &Pool, amount_in: u64) -> Result<SwapAm
         Rounding::Down,
     )?;
 
-    Ok(SwapAmountFromInput { output_amount })
+    Ok(SwapAmountFromInput {
+        output_amount,
+        next_sqrt_price,
+    })
 }
 
 fn calculate_b_to_a_from_amount_in(pool: &Pool, amount_in: u64) -> Result<SwapAmountFromInput> {
@@ -110,7 +115,10 @@ fn calculate_b_to_a_from_amount_in(pool: &Pool, amount_in: u64) -> Result<SwapAm
         Rounding::Down,
     )?;
 
-    Ok(SwapAmountFromInput { output_amount })
+    Ok(SwapAmountFromInput {
+        output_amount,
+        next_sqrt_price,
+    })
 }
 
 struct SimulateSwapResult {
@@ -118,6 +126,7 @@ struct SimulateSwapResult {
     user_amount_out: u64,
     pool_amount_in: u64,
     pool_amount_out: u64,
+    next_sqrt_price: u128,
 }
 
 /// Replicate exactly how swap_exact_in work
@@ -160,7 +169,10 @@ fn calculate_swap_result(
     } else {
         excluded_fee_amount_in
     };
-    let SwapAmountFromInput { output_amount, .. } = match trade_direction {
+    let SwapAmountFromInput {
+        output_amount,
+        next_sqrt_price,
+    } = match trade_direction {
         TradeDirection::AtoB => calculate_a_to_b_from_amount_in(pool, actual_amount_in),
         TradeDirection::BtoA => calculate_b_to_a_from_amount_in(pool, actual_amount_in),
     }?;
@@ -192,6 +204,7 @@ fn calculate_swap_result(
         user_amount_out: amount_in,
         pool_amount_in: actual_amount_in,
         pool_amount_out: output_amount,
+        next_sqrt_price,
     })
 }
 
@@ -218,6 +231,7 @@ fn validate_swap_result(
         user_amount_out,
         pool_amount_in,
         pool_amount_out,
+        ..
     } = swap_result;
     // apply swap result
     if trade_direction == TradeDirection::AtoB {
@@ -367,7 +381,6 @@ fn get_total_fee_numerator(
         Ok(total_fee_numerator)
     }
 }
-// we will use binary search
 pub fn calculate_swap_amount(
     pool: &Pool,
     token_a_transfer_fee_calculator: &TransferFeeCalculator,
@@ -375,16 +388,347 @@ pub fn calculate_swap_amount(
     remaining_amount: u64,
     trade_direction: TradeDirection,
     current_point: u64,
+    has_referral: bool,
+) -> Result<(u64, u64)> {
+    let fee_handler = get_fee_handler(pool, current_point, trade_direction)?;
+
+    let fee_mode = FeeMode::get_fee_mode(pool.collect_fee_mode, trade_direction, has_referral)?;
+
+    // closed form only works when trade fee doesn't depend on swap amount and tokens don't have transfer fee,
+    // we still fallback to binary search if the result is not good enough
+    if !fee_handler.is_rate_limiter
+        && token_a_transfer_fee_calculator.no_transfer_fee_extension
+        && token_b_transfer_fee_calculator.no_transfer_fee_extension
+    {
+        if let Ok(Some(swap_amount)) = calculate_swap_amount_by_closed_form(
+            pool,
+            token_a_transfer_fee_calculator,
+            token_b_transfer_fee_calculator,
+            remaining_amount,
+            trade_direction,
+            current_point,
+            &fee_handler,
+            &fee_mode,
+        ) {
+            return Ok(swap_amount);
+        }
+    }
+
+    binary_search_swap_amount(
+        pool,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
+        remaining_amount,
+        trade_direction,
+        current_point,
+        &fee_handler,
+        &fee_mode,
+    )
+}
+
+// we will use binary search
+pub fn calculate_swap_amount_by_binary_search(
+    pool: &Pool,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    remaining_amount: u64,
+    trade_direction: TradeDirection,
+    current_point: u64,
+    has_referral: bool,
+) -> Result<(u64, u64)> {
+    let fee_handler = get_fee_handler(pool, current_point, trade_direction)?;
+
+    let fee_mode = FeeMode::get_fee_mode(pool.collect_fee_mode, trade_direction, has_referral)?;
+
+    binary_search_swap_amount(
+        pool,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
+        remaining_amount,
+        trade_direction,
+        current_point,
+        &fee_handler,
+        &fee_mode,
+    )
+}
+
+fn simulate_swap(
+    pool: &Pool,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    amount_in: u64,
+    trade_direction: TradeDirection,
+    current_point: u64,
+    has_referral: bool,
+) -> Result<SimulateSwapResult> {
+    let fee_handler = get_fee_handler(pool, current_point, trade_direction)?;
+
+    let fee_mode = FeeMode::get_fee_mode(pool.collect_fee_mode, trade_direction, has_referral)?;
+
+    calculate_swap_result(
+        pool,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
+        current_point,
+        amount_in,
+        trade_direction,
+        &fee_handler,
+        &fee_mode,
+    )
+}
+
+/// Amount user receives after swapping amount_in, replicate how swap_exact_in work
+pub fn get_swap_out_amount(
+    pool: &Pool,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    amount_in: u64,
+    trade_direction: TradeDirection,
+    current_point: u64,
+    has_referral: bool,
+) -> Result<u64> {
+    let swap_result = simulate_swap(
+        pool,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
+        amount_in,
+        trade_direction,
+        current_point,
+        has_referral,
+    )?;
+
+    Ok(swap_result.user_amount_in)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZapInDammV2Quote {
+    /// liquidity added in the first add liquidity
+    pub first_liquidity_delta: u128,
+    /// 0: AtoB, 1: BtoA
+    pub trade_direction: u8,
+    pub swap_in_amount: u64,
+    pub swap_out_amount: u64,
+    /// pool sqrt price after the last swap, equal to current sqrt price if swap is skipped
+    pub post_sqrt_price: u128,
+    /// total liquidity added in zap in
+    pub liquidity_delta: u128,
+}
+
+/// Simulate zap_in_damm_v2 with the given amounts, without ledger and transfers
+pub fn get_zap_in_damm_v2_quote(
+    pool: &Pool,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    amount_a: u64,
+    amount_b: u64,
+    current_point: u64,
+    current_timestamp: u64,
+    has_referral: bool,
+    max_rounds: u8,
+    dust_threshold: u64,
+) -> Result<ZapInDammV2Quote> {
+    let mut pool = *pool;
+    let mut ledger = UserLedger {
+        amount_a,
+        amount_b,
+        ..Default::default()
+    };
+
+    // 1. first add liquidity
+    let (first_liquidity_delta, trade_direction) = ledger
+        .get_liquidity_from_amounts_and_trade_direction(
+            token_a_transfer_fee_calculator,
+            token_b_transfer_fee_calculator,
+            pool.sqrt_price,
+            pool.sqrt_min_price,
+            pool.sqrt_max_price,
+        )?;
+
+    deposit_liquidity(
+        &mut pool,
+        &mut ledger,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
+        first_liquidity_delta,
+    )?;
+
+    let mut quote = ZapInDammV2Quote {
+        first_liquidity_delta,
+        trade_direction: trade_direction as u8,
+        post_sqrt_price: pool.sqrt_price,
+        liquidity_delta: first_liquidity_delta,
+        ..Default::default()
+    };
+
+    // 2. swap remaining amount and add liquidity again, same rounds as zap in
+    for _round in 0..max_rounds.max(1) {
+        let remaining_amount = if trade_direction == TradeDirection::AtoB {
+            ledger.amount_a
+        } else {
+            ledger.amount_b
+        };
+        if remaining_amount == 0 {
+            break;
+        }
+        if dust_threshold > 0 {
+            let remaining_value = if trade_direction == TradeDirection::AtoB {
+                get_amount_a_value_in_token_b(remaining_amount, pool.sqrt_price)?
+            } else {
+                remaining_amount.into()
+            };
+            if remaining_value <= dust_threshold.into() {
+                break;
+            }
+        }
+
+        let (swap_in_amount, _) = match calculate_swap_amount(
+            &pool,
+            token_a_transfer_fee_calculator,
+            token_b_transfer_fee_calculator,
+            remaining_amount,
+            trade_direction,
+            current_point,
+            has_referral,
+        ) {
+            Ok((swap_in_amount, swap_out_amount)) if swap_in_amount > 0 && swap_out_amount > 0 => {
+                (swap_in_amount, swap_out_amount)
+            }
+            // zap in skips swap and add liquidity
+            _ => break,
+        };
+
+        let swap_out_amount = apply_swap(
+            &mut pool,
+            &mut ledger,
+            token_a_transfer_fee_calculator,
+            token_b_transfer_fee_calculator,
+            swap_in_amount,
+            trade_direction,
+            current_point,
+            current_timestamp,
+            has_referral,
+        )?;
+
+        // 3. add liquidity again
+        let (liquidity, next_trade_direction) = ledger
+            .get_liquidity_from_amounts_and_trade_direction(
+                token_a_transfer_fee_calculator,
+                token_b_transfer_fee_calculator,
+                pool.sqrt_price,
+                pool.sqrt_min_price,
+                pool.sqrt_max_price,
+            )?;
+        deposit_liquidity(
+            &mut pool,
+            &mut ledger,
+            token_a_transfer_fee_calculator,
+            token_b_transfer_fee_calculator,
+            liquidity,
+        )?;
+
+        quote.swap_in_amount = quote.swap_in_amount.safe_add(swap_in_amount)?;
+        quote.swap_out_amount = quote.swap_out_amount.safe_add(swap_out_amount)?;
+        quote.post_sqrt_price = pool.sqrt_price;
+        quote.liquidity_delta = quote.liquidity_delta.safe_add(liquidity)?;
+
+        if next_trade_direction != trade_direction {
+            break;
+        }
+    }
+
+    Ok(quote)
+}
+
+// apply swap_exact_in to simulated pool and ledger, the pool state is updated as damm v2 swap does,
+// so later rounds quote against the same fees as zap in, returns amount out
+fn apply_swap(
+    pool: &mut Pool,
+    ledger: &mut UserLedger,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    amount_in: u64,
+    trade_direction: TradeDirection,
+    current_point: u64,
+    current_timestamp: u64,
+    has_referral: bool,
+) -> Result<u64> {
+    pool.update_pre_swap(current_timestamp)?;
+    let SimulateSwapResult {
+        user_amount_in,
+        user_amount_out,
+        next_sqrt_price,
+        ..
+    } = simulate_swap(
+        pool,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
+        amount_in,
+        trade_direction,
+        current_point,
+        has_referral,
+    )?;
+    let old_sqrt_price = pool.sqrt_price;
+    pool.sqrt_price = next_sqrt_price;
+    pool.update_post_swap(old_sqrt_price, current_timestamp)?;
+
+    if trade_direction == TradeDirection::AtoB {
+        ledger.amount_a = ledger.amount_a.safe_sub(user_amount_out)?;
+        ledger.amount_b = ledger.amount_b.safe_add(user_amount_in)?;
+    } else {
+        ledger.amount_b = ledger.amount_b.safe_sub(user_amount_out)?;
+        ledger.amount_a = ledger.amount_a.safe_add(user_amount_in)?;
+    }
+    Ok(user_amount_in)
+}
+
+// apply add liquidity to simulated pool and ledger
+fn deposit_liquidity(
+    pool: &mut Pool,
+    ledger: &mut UserLedger,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    liquidity: u128,
+) -> Result<()> {
+    if liquidity == 0 {
+        return Ok(());
+    }
+    let deposit_amount_a = get_delta_amount_a_unsigned(
+        pool.sqrt_price,
+        pool.sqrt_max_price,
+        liquidity,
+        Rounding::Up,
+    )?;
+    let deposit_amount_b = get_delta_amount_b_unsigned(
+        pool.sqrt_min_price,
+        pool.sqrt_price,
+        liquidity,
+        Rounding::Up,
+    )?;
+    ledger.amount_a = ledger.amount_a.safe_sub(
+        token_a_transfer_fee_calculator.calculate_transfer_fee_included_amount(deposit_amount_a)?,
+    )?;
+    ledger.amount_b = ledger.amount_b.safe_sub(
+        token_b_transfer_fee_calculator.calculate_transfer_fee_included_amount(deposit_amount_b)?,
+    )?;
+    pool.liquidity = pool.liquidity.safe_add(liquidity)?;
+    Ok(())
+}
+
+fn binary_search_swap_amount(
+    pool: &Pool,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    remaining_amount: u64,
+    trade_direction: TradeDirection,
+    current_point: u64,
+    fee_handler: &FeeHandler,
+    fee_mode: &FeeMode,
 ) -> Result<(u64, u64)> {
     let mut max_swap_amount = remaining_amount;
     let mut min_swap_amount = 0;
     let mut swap_in_amount = 0;
     let mut swap_out_amount = 0;
 
-    let fee_handler = get_fee_handler(pool, current_point, trade_direction)?;
-
-    let fee_mode = FeeMode::get_fee_mode(pool.collect_fee_mode, trade_direction, false)?;
-
     let (pool_amount_a, pool_amount_b) = pool.get_reserves_amount()?;
 
     // max 20 loops
@@ -405,8 +749,8 @@ pub fn calculate_swap_amount(
             current_point,
             amount_in,
             trade_direction,
-            &fee_handler,
-            &fee_mode,
+            fee_handler,
+            fee_mode,
         )?;
 
         // update swap amount
@@ -453,6 +797,200 @@ pub fn calculate_swap_amount(
     Ok((swap_in_amount, swap_out_amount))
 }
 
+// Solve the swap amount directly instead of binary search, only used when trade fee is constant
+// Noted: y is the amount goes into the curve (fee excluded), R is remaining amount, A and B are pool reserves
+// g = 1 - fee if fee is charged on output token, otherwise g = 1
+// h = 1 - fee if fee is charged on input token, otherwise h = 1
+// AtoB:
+// √P' = √P * L / (L + y * √P), B - B' = L * (√P - √P')
+// user amounts must have the same ratio with pool reserves after swap:
+// (R - y / h) * B' = g * (B - B') * (A + y)
+// => (g - √P_lower / (h * √P)) * y^2 + (g * A + R * √P_lower / √P + B / (h * P)) * y - R * B / P = 0
+// BtoA:
+// √P' = √P + y / L, A - A' = y / (√P * √P')
+// (R - y / h) * A' = g * (A - A') * (B + y)
+// => (g - √P / (h * √P_upper)) * y^2 + (g * B + R * √P / √P_upper + A * P / h) * y - R * A * P = 0
+// Both have form: α * y^2 + β * y - γ = 0, with β > 0, γ >= 0
+// y = 2 * γ / (β + sqrt(β^2 + 4 * α * γ)), that is stable for both sign of α
+fn calculate_swap_amount_by_closed_form(
+    pool: &Pool,
+    token_a_transfer_fee_calculator: &TransferFeeCalculator,
+    token_b_transfer_fee_calculator: &TransferFeeCalculator,
+    remaining_amount: u64,
+    trade_direction: TradeDirection,
+    current_point: u64,
+    fee_handler: &FeeHandler,
+    fee_mode: &FeeMode,
+) -> Result<Option<(u64, u64)>> {
+    let (pool_amount_a, pool_amount_b) = pool.get_reserves_amount()?;
+
+    let trade_fee_numerator = fee_handler.get_trade_fee_numerator(
+        remaining_amount,
+        current_point,
+        pool.activation_point,
+        trade_direction,
+        pool.pool_fees.init_sqrt_price,
+        pool.sqrt_price,
+    )?;
+
+    // scale all coefficients with denominator, so g = g_numerator / denominator and 1 / h = h_inv_numerator / denominator
+    let denominator = if fee_mode.fees_on_input {
+        U512::from(FEE_DENOMINATOR.safe_sub(trade_fee_numerator)?)
+    } else {
+        U512::from(FEE_DENOMINATOR)
+    };
+    let g_numerator = U512::from(FEE_DENOMINATOR.safe_sub(trade_fee_numerator)?);
+    let h_inv_numerator = U512::from(FEE_DENOMINATOR);
+
+    let remaining_amount_u512 = U512::from(remaining_amount);
+    let sqrt_price = U512::from(pool.sqrt_price);
+
+    // all coefficients are in Q64
+    let (alpha_positive, alpha_negative, beta, gamma) = if trade_direction == TradeDirection::AtoB {
+        let sqrt_min_price = U512::from(pool.sqrt_min_price);
+        let price = sqrt_price.safe_mul(sqrt_price)?;
+
+        let alpha_positive = g_numerator.safe_shl(64)?;
+        let alpha_negative = h_inv_numerator
+            .safe_mul(sqrt_min_price)?
+            .safe_shl(64)?
+            .safe_div(sqrt_price)?;
+
+        let beta = denominator
+            .safe_mul(remaining_amount_u512)?
+            .safe_mul(sqrt_min_price)?
+            .safe_shl(64)?
+            .safe_div(sqrt_price)?
+            .safe_add(
+                h_inv_numerator
+                    .safe_mul(U512::from(pool_amount_b))?
+                    .safe_shl(192)?
+                    .safe_div(price)?,
+            )?
+            .safe_add(
+                g_numerator
+                    .safe_mul(U512::from(pool_amount_a))?
+                    .safe_shl(64)?,
+            )?;
+
+        let gamma = denominator
+            .safe_mul(remaining_amount_u512)?
+            .safe_mul(U512::from(pool_amount_b))?
+            .safe_shl(192)?
+            .safe_div(price)?;
+
+        (alpha_positive, alpha_negative, beta, gamma)
+    } else {
+        let sqrt_max_price = U512::from(pool.sqrt_max_price);
+        let price = sqrt_price.safe_mul(sqrt_price)?;
+
+        let alpha_positive = g_numerator.safe_shl(64)?;
+        let alpha_negative = h_inv_numerator
+            .safe_mul(sqrt_price)?
+            .safe_shl(64)?
+            .safe_div(sqrt_max_price)?;
+
+        let beta = denominator
+            .safe_mul(remaining_amount_u512)?
+            .safe_mul(sqrt_price)?
+            .safe_shl(64)?
+            .safe_div(sqrt_max_price)?
+            .safe_add(
+                h_inv_numerator
+                    .safe_mul(U512::from(pool_amount_a))?
+                    .safe_mul(price)?
+                    >> 64,
+            )?
+            .safe_add(
+                g_numerator
+                    .safe_mul(U512::from(pool_amount_b))?
+                    .safe_shl(64)?,
+            )?;
+
+        let gamma = denominator
+            .safe_mul(remaining_amount_u512)?
+            .safe_mul(U512::from(pool_amount_a))?
+            .safe_mul(price)?
+            >> 64;
+
+        (alpha_positive, alpha_negative, beta, gamma)
+    };
+
+    let beta_square = beta.safe_mul(beta)?;
+    let discriminant = if alpha_positive >= alpha_negative {
+        let four_alpha_gamma = alpha_positive
+            .safe_sub(alpha_negative)?
+            .safe_mul(gamma)?
+            .safe_shl(2)?;
+        beta_square.safe_add(four_alpha_gamma)?
+    } else {
+        let four_alpha_gamma = alpha_negative
+            .safe_sub(alpha_positive)?
+            .safe_mul(gamma)?
+            .safe_shl(2)?;
+        beta_square.safe_sub(four_alpha_gamma)?
+    };
+
+    let curve_amount_in = gamma
+        .safe_shl(1)?
+        .safe_div(beta.safe_add(sqrt_u512(discriminant)?)?)?;
+
+    // convert back to amount that user send (fee included)
+    let amount_in = curve_amount_in
+        .safe_mul(h_inv_numerator)?
+        .safe_div(denominator)?;
+    let amount_in: u64 = amount_in.try_into().map_err(|_| ZapError::TypeCastFailed)?;
+    let amount_in = amount_in.min(remaining_amount);
+
+    if amount_in == 0 {
+        return Ok(None);
+    }
+
+    // validate the result with exactly how swap works, in case precision loss
+    let swap_result = calculate_swap_result(
+        pool,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
+        current_point,
+        amount_in,
+        trade_direction,
+        fee_handler,
+        fee_mode,
+    )?;
+
+    let status = validate_swap_result(
+        &swap_result,
+        token_a_transfer_fee_calculator,
+        token_b_transfer_fee_calculator,
+        remaining_amount,
+        pool_amount_a,
+        pool_amount_b,
+        trade_direction,
+    )?;
+
+    if status == SwapResultStatus::Done {
+        Ok(Some((amount_in, swap_result.user_amount_in)))
+    } else {
+        Ok(None)
+    }
+}
+
+// Babylonian method, initial guess is always greater than or equal to the square root
+fn sqrt_u512(value: U512) -> Result<U512> {
+    if value.is_zero() {
+        return Ok(value);
+    }
+    let bit_len = value.bit_len();
+    let mut result = U512::from(1).safe_shl(bit_len.safe_add(1)? >> 1)?;
+    loop {
+        let next_result = result.safe_add(value.safe_div(result)?)? >> 1;
+        if next_result >= result {
+            return Ok(result);
+        }
+        result = next_result;
+    }
+}
+
 // Δa = L * (1 / √P_lower - 1 / √P_upper) => L = Δa / (1 / √P_lower - 1 / √P_upper)
 pub fn get_liquidity_from_amount_a(
     amount_a: u64,
@@ -479,6 +1017,13 @@ pub fn get_liquidity_from_amount_b(
     return Ok(liquidity.try_into().map_err(|_| ZapError::TypeCastFailed)?);
 }
 
+// value of amount a in token b, b = a * P
+pub fn get_amount_a_value_in_token_b(amount_a: u64, sqrt_price: u128) -> Result<u128> {
+    let price = U256::from(sqrt_price).safe_mul(U256::from(sqrt_price))?;
+    let value: U256 = U256::from(amount_a).safe_mul(price)? >> 128;
+    Ok(value.try_into().map_err(|_| ZapError::TypeCastFailed)?)
+}
+
 // u32::MAX == 4_294_967_295, so we dont allow price change to go over 4_294_967_295 * 100 / 10_000 = 42_949_672 (%)
 pub fn get_price_change_bps(pre_sqrt_price: u128, post_sqrt_price: u128) -> Result<u32> {
     let price_diff = if pre_sqrt_price > post_sqrt_price {
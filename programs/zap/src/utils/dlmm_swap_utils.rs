@@ -0,0 +1,360 @@
+use anchor_lang::prelude::*;
+use std::collections::BTreeMap;
+
+use dlmm::accounts::{BinArray, LbPair};
+use ruint::aliases::U256;
+
+use crate::{
+    constants::MAX_BASIS_POINT, error::ZapError, price_math::get_price_from_id,
+    safe_math::SafeMath, TransferFeeCalculator, ZapInRebalancingParams, MAX_BIN_PER_ARRAY,
+};
+
+const FEE_PRECISION: u128 = 1_000_000_000;
+const MAX_FEE_RATE: u128 = 100_000_000;
+
+pub struct DlmmSwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub end_active_id: i32,
+}
+
+// replicate dlmm swap over bins of the given bin arrays
+pub struct DlmmSwapSimulator<'a> {
+    lb_pair: &'a LbPair,
+    bin_arrays: BTreeMap<i64, &'a BinArray>,
+    current_timestamp: i64,
+}
+
+impl<'a> DlmmSwapSimulator<'a> {
+    pub fn new(lb_pair: &'a LbPair, bin_arrays: &[&'a BinArray], current_timestamp: i64) -> Self {
+        Self {
+            lb_pair,
+            bin_arrays: bin_arrays
+                .iter()
+                .map(|bin_array| (bin_array.index, *bin_array))
+                .collect(),
+            current_timestamp,
+        }
+    }
+
+    // same as base fee + variable fee of dlmm, in FEE_PRECISION
+    fn get_total_fee_rate(&self, volatility_accumulator: u32) -> Result<u128> {
+        let parameters = &self.lb_pair.parameters;
+        let bin_step = u128::from(self.lb_pair.bin_step);
+        let base_fee_rate = u128::from(parameters.base_factor)
+            .safe_mul(bin_step)?
+            .safe_mul(10)?
+            .safe_mul(10u128.pow(parameters.base_fee_power_factor.into()))?;
+        let variable_fee_rate = if parameters.variable_fee_control > 0 {
+            let square_vfa_bin = u128::from(volatility_accumulator)
+                .safe_mul(bin_step)?
+                .pow(2);
+            u128::from(parameters.variable_fee_control)
+                .safe_mul(square_vfa_bin)?
+                .safe_add(99_999_999_999)?
+                .safe_div(100_000_000_000)?
+        } else {
+            0
+        };
+        Ok(base_fee_rate.safe_add(variable_fee_rate)?.min(MAX_FEE_RATE))
+    }
+
+    // returns None if the swap goes out of the given bin arrays
+    pub fn simulate(&self, amount_in: u64, swap_for_y: bool) -> Result<Option<DlmmSwapResult>> {
+        let parameters = &self.lb_pair.parameters;
+        let v_parameters = &self.lb_pair.v_parameters;
+        let mut active_id = self.lb_pair.active_id;
+
+        // dlmm updates references once before swap
+        let mut index_reference = v_parameters.index_reference;
+        let mut volatility_reference = v_parameters.volatility_reference;
+        let elapsed = self
+            .current_timestamp
+            .safe_sub(v_parameters.last_update_timestamp)?;
+        if elapsed >= parameters.filter_period.into() {
+            index_reference = active_id;
+            volatility_reference = if elapsed < parameters.decay_period.into() {
+                u32::try_from(
+                    u64::from(v_parameters.volatility_accumulator)
+                        .safe_mul(parameters.reduction_factor.into())?
+                        .safe_div(MAX_BASIS_POINT.into())?,
+                )
+                .map_err(|_| ZapError::TypeCastFailed)?
+            } else {
+                0
+            };
+        }
+
+        let mut amount_left = amount_in;
+        let mut amount_out: u64 = 0;
+        while amount_left > 0 {
+            if active_id < parameters.min_bin_id || active_id > parameters.max_bin_id {
+                return Ok(None);
+            }
+            let bin_array_index = i64::from(active_id.div_euclid(MAX_BIN_PER_ARRAY));
+            let Some(bin_array) = self.bin_arrays.get(&bin_array_index) else {
+                // dlmm skips bin arrays without liquidity, continue from the next given bin array
+                let next_bin_array = if swap_for_y {
+                    self.bin_arrays.range(..bin_array_index).next_back()
+                } else {
+                    self.bin_arrays.range(bin_array_index.safe_add(1)?..).next()
+                };
+                let Some((&next_bin_array_index, _)) = next_bin_array else {
+                    return Ok(None);
+                };
+                let lower_bin_id = i32::try_from(next_bin_array_index)
+                    .map_err(|_| ZapError::TypeCastFailed)?
+                    .safe_mul(MAX_BIN_PER_ARRAY)?;
+                active_id = if swap_for_y {
+                    lower_bin_id.safe_add(MAX_BIN_PER_ARRAY - 1)?
+                } else {
+                    lower_bin_id
+                };
+                continue;
+            };
+            let bin_idx = usize::try_from(
+                i64::from(active_id)
+                    .safe_sub(bin_array_index.safe_mul(MAX_BIN_PER_ARRAY.into())?)?,
+            )
+            .map_err(|_| ZapError::TypeCastFailed)?;
+            let bin = &bin_array.bins[bin_idx];
+
+            let max_amount_out = if swap_for_y {
+                bin.amount_y
+            } else {
+                bin.amount_x
+            };
+            if max_amount_out > 0 {
+                let volatility_accumulator = u64::from(volatility_reference)
+                    .safe_add(
+                        u64::from(index_reference.abs_diff(active_id))
+                            .safe_mul(MAX_BASIS_POINT.into())?,
+                    )?
+                    .min(parameters.max_volatility_accumulator.into());
+                let total_fee_rate = self.get_total_fee_rate(
+                    u32::try_from(volatility_accumulator).map_err(|_| ZapError::TypeCastFailed)?,
+                )?;
+                let price = if bin.price > 0 {
+                    bin.price
+                } else {
+                    get_price_from_id(active_id, self.lb_pair.bin_step)?
+                };
+                let (used_amount_in, bin_amount_out) = swap_in_bin(
+                    amount_left,
+                    max_amount_out,
+                    price,
+                    total_fee_rate,
+                    swap_for_y,
+                )?;
+                amount_left = amount_left.safe_sub(used_amount_in)?;
+                amount_out = amount_out.safe_add(bin_amount_out)?;
+            }
+
+            if amount_left > 0 {
+                active_id = if swap_for_y {
+                    active_id.safe_sub(1)?
+                } else {
+                    active_id.safe_add(1)?
+                };
+            }
+        }
+
+        Ok(Some(DlmmSwapResult {
+            amount_in,
+            amount_out,
+            end_active_id: active_id,
+        }))
+    }
+}
+
+// (amount in including fee, amount out) of swap in a bin, same rounding as dlmm
+fn swap_in_bin(
+    amount_in: u64,
+    max_amount_out: u64,
+    price: u128,
+    total_fee_rate: u128,
+    swap_for_y: bool,
+) -> Result<(u64, u64)> {
+    let price = U256::from(price);
+    // price is y per x in Q64
+    let max_amount_in = if swap_for_y {
+        U256::from(max_amount_out).safe_shl(64)?.div_ceil(price)
+    } else {
+        U256::from(max_amount_out)
+            .safe_mul(price)?
+            .safe_add(U256::from(u64::MAX))?
+            >> 64
+    };
+    let max_amount_in = u128::try_from(max_amount_in).map_err(|_| ZapError::TypeCastFailed)?;
+    let max_fee = max_amount_in
+        .safe_mul(total_fee_rate)?
+        .div_ceil(FEE_PRECISION.safe_sub(total_fee_rate)?);
+    let max_amount_in_with_fee = max_amount_in.safe_add(max_fee)?;
+
+    if u128::from(amount_in) >= max_amount_in_with_fee {
+        let used_amount_in =
+            u64::try_from(max_amount_in_with_fee).map_err(|_| ZapError::TypeCastFailed)?;
+        return Ok((used_amount_in, max_amount_out));
+    }
+
+    let fee = u128::from(amount_in)
+        .safe_mul(total_fee_rate)?
+        .div_ceil(FEE_PRECISION);
+    let amount_in_after_fee = U256::from(u128::from(amount_in).safe_sub(fee)?);
+    let amount_out: U256 = if swap_for_y {
+        amount_in_after_fee.safe_mul(price)? >> 64
+    } else {
+        amount_in_after_fee.safe_shl(64)?.safe_div(price)?
+    };
+    let amount_out = u64::try_from(amount_out)
+        .map_err(|_| ZapError::TypeCastFailed)?
+        .min(max_amount_out);
+    Ok((amount_in, amount_out))
+}
+
+// (swap in amount, swap out amount) that turns single token into amounts matching the strategy shape after swap,
+// params amounts are ignored and active_id should be the lb pair active id
+pub fn calculate_dlmm_single_token_swap_amount(
+    simulator: &DlmmSwapSimulator,
+    params: &ZapInRebalancingParams,
+    token_x_transfer_fee_calculator: &TransferFeeCalculator,
+    token_y_transfer_fee_calculator: &TransferFeeCalculator,
+    amount_in: u64,
+    swap_for_y: bool,
+) -> Result<(u64, u64)> {
+    let (amount_x, amount_y) = if swap_for_y {
+        (amount_in, 0)
+    } else {
+        (0, amount_in)
+    };
+    let params = ZapInRebalancingParams {
+        amount_x,
+        amount_y,
+        strategy: params.strategy.clone(),
+        ..*params
+    };
+    let (swap_in_amount, swap_out_amount, _swap_for_y) = calculate_dlmm_swap_amount(
+        simulator,
+        &params,
+        token_x_transfer_fee_calculator,
+        token_y_transfer_fee_calculator,
+    )?;
+    Ok((swap_in_amount, swap_out_amount))
+}
+
+// (swap in amount, swap out amount, swap_for_y) that swaps the surplus side of params amounts to match the strategy shape after swap,
+// active_id should be the lb pair active id
+pub fn calculate_dlmm_swap_amount(
+    simulator: &DlmmSwapSimulator,
+    params: &ZapInRebalancingParams,
+    token_x_transfer_fee_calculator: &TransferFeeCalculator,
+    token_y_transfer_fee_calculator: &TransferFeeCalculator,
+) -> Result<(u64, u64, bool)> {
+    let (bid_side_weight, ask_side_weight) = params.get_strategy_side_weights()?;
+    let bid_side_weight = U256::from(bid_side_weight).safe_shl(64)?;
+    // amount_y * ask_side_weight >= amount_x * bid_side_weight
+    let is_y_enough = |amount_x: U256, amount_y: U256, ask_side_weight: U256| -> Result<bool> {
+        Ok(amount_y.safe_mul(ask_side_weight)? >= amount_x.safe_mul(bid_side_weight)?)
+    };
+    // swap x to y if x is more than needed
+    let swap_for_y = !is_y_enough(
+        U256::from(params.amount_x),
+        U256::from(params.amount_y),
+        ask_side_weight,
+    )?;
+    let (
+        amount_in,
+        amount_other,
+        token_in_transfer_fee_calculator,
+        token_out_transfer_fee_calculator,
+    ) = if swap_for_y {
+        (
+            params.amount_x,
+            params.amount_y,
+            token_x_transfer_fee_calculator,
+            token_y_transfer_fee_calculator,
+        )
+    } else {
+        (
+            params.amount_y,
+            params.amount_x,
+            token_y_transfer_fee_calculator,
+            token_x_transfer_fee_calculator,
+        )
+    };
+
+    // amount out, or None if remaining amount in is less than needed
+    let get_amount_out_if_not_exceeded = |swap_amount: u64| -> Result<Option<u64>> {
+        let swap_amount_excluded_fee = token_in_transfer_fee_calculator
+            .calculate_transfer_fee_excluded_amount(swap_amount)?
+            .amount;
+        let Some(swap_result) = simulator.simulate(swap_amount_excluded_fee, swap_for_y)? else {
+            return Ok(None);
+        };
+        let amount_out = token_out_transfer_fee_calculator
+            .calculate_transfer_fee_excluded_amount(swap_result.amount_out)?
+            .amount;
+        // p(m) of ask side bins move with active id
+        let ask_side_weight = ask_side_weight.safe_mul(U256::from(get_price_from_id(
+            params.active_id.safe_sub(swap_result.end_active_id)?,
+            params.bin_step,
+        )?))?
+            >> 64;
+        let remaining_amount = U256::from(amount_in.safe_sub(swap_amount)?);
+        let amount_other = U256::from(amount_other).safe_add(U256::from(amount_out))?;
+        let (amount_x, amount_y) = if swap_for_y {
+            (remaining_amount, amount_other)
+        } else {
+            (amount_other, remaining_amount)
+        };
+        // swap more while the token in is still more than needed
+        Ok((is_y_enough(amount_x, amount_y, ask_side_weight)? != swap_for_y).then_some(amount_out))
+    };
+
+    // range doesn't have bins of token in, swap all of it
+    let is_token_in_unused = if swap_for_y {
+        ask_side_weight == U256::ZERO
+    } else {
+        bid_side_weight == U256::ZERO
+    };
+    if is_token_in_unused && amount_in > 0 {
+        let swap_amount_excluded_fee = token_in_transfer_fee_calculator
+            .calculate_transfer_fee_excluded_amount(amount_in)?
+            .amount;
+        if let Some(swap_result) = simulator.simulate(swap_amount_excluded_fee, swap_for_y)? {
+            let amount_out = token_out_transfer_fee_calculator
+                .calculate_transfer_fee_excluded_amount(swap_result.amount_out)?
+                .amount;
+            return Ok((amount_in, amount_out, swap_for_y));
+        }
+    }
+
+    let mut min_swap_amount = 0;
+    let mut max_swap_amount = amount_in;
+    let mut swap_in_amount = 0;
+    let mut swap_out_amount = 0;
+
+    // max 20 loops, the same as damm v2 single token zap in
+    for _i in 0..20 {
+        if min_swap_amount > max_swap_amount {
+            break;
+        }
+        let swap_amount =
+            min_swap_amount.safe_add(max_swap_amount.safe_sub(min_swap_amount)? >> 1)?;
+        match get_amount_out_if_not_exceeded(swap_amount)? {
+            Some(amount_out) => {
+                swap_in_amount = swap_amount;
+                swap_out_amount = amount_out;
+                min_swap_amount = swap_amount.safe_add(1)?;
+            }
+            None => {
+                if swap_amount == 0 {
+                    break;
+                }
+                max_swap_amount = swap_amount.safe_sub(1)?;
+            }
+        }
+    }
+
+    Ok((swap_in_amount, swap_out_amount, swap_for_y))
+}
@@ -1,11 +1,21 @@
 use anchor_lang::prelude::*;
 
-use std::ops::Neg;
+use std::{
+    mem::{offset_of, size_of},
+    ops::Neg,
+};
 
 use damm_v2::safe_math::SafeMath;
+use dlmm::{
+    accounts::{BinArray, LbPair, PositionV2},
+    types::{AddLiquidityParams, PositionBinData, RebalanceLiquidityParams},
+};
 use ruint::aliases::U256;
 
-use crate::{error::ZapError, price_math::get_price_from_id};
+use crate::{
+    error::ZapError,
+    price_math::{get_id_from_price, get_price_base_factor, get_price_from_id, pow},
+};
 #[derive(AnchorSerialize, AnchorDeserialize, Eq, PartialEq, Clone, Debug)]
 pub enum StrategyType {
     // spot
@@ -14,8 +24,166 @@ pub enum StrategyType {
     Curve,
     // bidAsk
     BidAsk,
+    // weight of each bin from min_delta_id to max_delta_id, value in bins is proportional to the weight
+    // like add_liquidity_by_weight in dlmm, bins with zero weight are skipped
+    Custom { weights: Vec<u16> },
+    // ascending and non-overlapping segments in [min_delta_id, max_delta_id], each with its own strategy,
+    // amounts are split across segments by weight, e.g. spot around active id plus bid ask tails
+    Piecewise { segments: Vec<StrategySegment> },
 }
 
+// strategies that a segment of piecewise strategy can use
+#[derive(AnchorSerialize, AnchorDeserialize, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum LinearStrategyType {
+    Spot,
+    Curve,
+    BidAsk,
+}
+
+impl From<LinearStrategyType> for StrategyType {
+    fn from(strategy: LinearStrategyType) -> Self {
+        match strategy {
+            LinearStrategyType::Spot => StrategyType::Spot,
+            LinearStrategyType::Curve => StrategyType::Curve,
+            LinearStrategyType::BidAsk => StrategyType::BidAsk,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Eq, PartialEq, Clone, Debug)]
+pub struct StrategySegment {
+    pub min_delta_id: i32,
+    pub max_delta_id: i32,
+    pub strategy: LinearStrategyType,
+    // share of amount x (or y) among segments having ask (or bid) side bins
+    pub weight: u16,
+}
+
+// price range of zap in instead of delta ids, converted to bin ids on chain so it doesn't depend on active id
+#[derive(AnchorSerialize, AnchorDeserialize, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum PriceRange {
+    // Q64.64 price of token x in token y, in smallest units
+    Q64 {
+        lower_price: u128,
+        upper_price: u128,
+    },
+    // ui price with `decimals` decimal places, e.g. 1800_000000 with decimals 6 is 1800 token y per token x,
+    // adjusted by decimals of the mints
+    Decimal {
+        lower_price: u64,
+        upper_price: u64,
+        decimals: u8,
+    },
+}
+
+impl PriceRange {
+    /// (lower_bin_id, upper_bin_id) covering the price range, lower price is rounded down and upper price is rounded up
+    pub fn get_bin_range(
+        &self,
+        bin_step: u16,
+        token_x_decimals: u8,
+        token_y_decimals: u8,
+    ) -> Result<(i32, i32)> {
+        let (lower_price, upper_price) = match *self {
+            PriceRange::Q64 {
+                lower_price,
+                upper_price,
+            } => (lower_price, upper_price),
+            PriceRange::Decimal {
+                lower_price,
+                upper_price,
+                decimals,
+            } => (
+                get_q64_price_from_decimal_price(
+                    lower_price,
+                    decimals,
+                    token_x_decimals,
+                    token_y_decimals,
+                )?,
+                get_q64_price_from_decimal_price(
+                    upper_price,
+                    decimals,
+                    token_x_decimals,
+                    token_y_decimals,
+                )?,
+            ),
+        };
+        require!(lower_price <= upper_price, ZapError::InvalidPriceRange);
+        Ok((
+            get_id_from_price(lower_price, bin_step, false)?,
+            get_id_from_price(upper_price, bin_step, true)?,
+        ))
+    }
+}
+
+/// Q64.64 price in smallest units of ui price with `decimals` decimal places
+pub fn get_q64_price_from_decimal_price(
+    price: u64,
+    decimals: u8,
+    token_x_decimals: u8,
+    token_y_decimals: u8,
+) -> Result<u128> {
+    let pow10 = |exp: u8| -> Result<U256> {
+        Ok(U256::from(
+            10u128
+                .checked_pow(exp.into())
+                .ok_or(ZapError::MathOverflow)?,
+        ))
+    };
+    // price / 10^decimals * 10^token_y_decimals / 10^token_x_decimals
+    let q64_price = U256::from(price)
+        .safe_shl(64)?
+        .safe_mul(pow10(token_y_decimals)?)?
+        .safe_div(pow10(decimals)?.safe_mul(pow10(token_x_decimals)?)?)?;
+    u128::try_from(q64_price).map_err(|_| ZapError::InvalidPriceRange.into())
+}
+
+/// (min_delta_id, max_delta_id) relative to active id, from bin range of price range if any
+pub fn get_delta_id_range(
+    bin_range: Option<(i32, i32)>,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    active_id: i32,
+) -> Result<(i32, i32)> {
+    match bin_range {
+        Some((lower_bin_id, upper_bin_id)) => Ok((
+            lower_bin_id.safe_sub(active_id)?,
+            upper_bin_id.safe_sub(active_id)?,
+        )),
+        None => Ok((min_delta_id, max_delta_id)),
+    }
+}
+
+/// (lower_bin_id, upper_bin_id) the deposit can cover, without price range the bins move with active bin
+/// in single token swap, which is bounded by max_active_bin_slippage from expected_active_id
+pub fn get_deposit_bin_range(
+    bin_range: Option<(i32, i32)>,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    active_id: i32,
+    expected_active_id: i32,
+    max_active_bin_slippage: u16,
+    single_token_swap: bool,
+) -> Result<(i32, i32)> {
+    if let Some(bin_range) = bin_range {
+        return Ok(bin_range);
+    }
+    let (lower_active_id, upper_active_id) = if single_token_swap {
+        let slippage = i32::from(max_active_bin_slippage);
+        (
+            expected_active_id.safe_sub(slippage)?.min(active_id),
+            expected_active_id.safe_add(slippage)?.max(active_id),
+        )
+    } else {
+        (active_id, active_id)
+    };
+    Ok((
+        lower_active_id.safe_add(min_delta_id)?,
+        upper_active_id.safe_add(max_delta_id)?,
+    ))
+}
+
+#[derive(Clone, Copy)]
 pub struct ParsedAddLiquidityParams {
     pub x0: i128,
     pub y0: i128,
@@ -23,6 +191,7 @@ pub struct ParsedAddLiquidityParams {
     pub delta_y: i128,
 }
 
+#[derive(Default)]
 pub struct UnparsedAddLiquidityParams {
     pub x0: u64,
     pub y0: u64,
@@ -31,6 +200,274 @@ pub struct UnparsedAddLiquidityParams {
     pub bit_flag: u8,
 }
 
+// bin range of one add liquidity params in rebalance_liquidity
+#[derive(Clone, Copy)]
+pub struct ParsedAddLiquidityRange {
+    pub min_delta_id: i32,
+    pub max_delta_id: i32,
+    pub params: ParsedAddLiquidityParams,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AmountInBin {
+    pub bin_id: i32,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+// number of bins stored in the fixed part of position account, the rest is stored in the extended part
+pub const POSITION_MAX_LENGTH: usize = 70;
+
+/// Lower bin id and liquidity share of each bin of dlmm position, including extended bins of dynamic position
+pub fn get_position_liquidity_shares(position: &AccountInfo) -> Result<(i32, Vec<u128>)> {
+    let data = position.try_borrow_data()?;
+    require!(
+        position.owner == &dlmm::ID && data.starts_with(PositionV2::DISCRIMINATOR),
+        ZapError::InvalidPosition
+    );
+    let read_bytes = |offset: usize, len: usize| -> Result<&[u8]> {
+        data.get(offset..offset.safe_add(len)?)
+            .ok_or_else(|| ZapError::InvalidPosition.into())
+    };
+    let read_i32 = |offset: usize| -> Result<i32> {
+        let bytes = read_bytes(offset, size_of::<i32>())?;
+        Ok(i32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let read_u128 = |offset: usize| -> Result<u128> {
+        let bytes = read_bytes(offset, size_of::<u128>())?;
+        Ok(u128::from_le_bytes(bytes.try_into().unwrap()))
+    };
+
+    let disc_len = PositionV2::DISCRIMINATOR.len();
+    let lower_bin_id = read_i32(disc_len.safe_add(offset_of!(PositionV2, lower_bin_id))?)?;
+    let upper_bin_id = read_i32(disc_len.safe_add(offset_of!(PositionV2, upper_bin_id))?)?;
+    let width = usize::try_from(upper_bin_id.safe_sub(lower_bin_id)?.safe_add(1)?)
+        .map_err(|_| ZapError::TypeCastFailed)?;
+
+    let liquidity_shares_offset = disc_len.safe_add(offset_of!(PositionV2, liquidity_shares))?;
+    let extended_offset = disc_len.safe_add(size_of::<PositionV2>())?;
+
+    let mut liquidity_shares = Vec::with_capacity(width);
+    for i in 0..width {
+        let offset = if i < POSITION_MAX_LENGTH {
+            liquidity_shares_offset.safe_add(i.safe_mul(size_of::<u128>())?)?
+        } else {
+            // liquidity share is the first field of position bin data
+            extended_offset.safe_add(
+                i.safe_sub(POSITION_MAX_LENGTH)?
+                    .safe_mul(size_of::<PositionBinData>())?,
+            )?
+        };
+        liquidity_shares.push(read_u128(offset)?);
+    }
+
+    Ok((lower_bin_id, liquidity_shares))
+}
+
+/// Sum of liquidity shares in all bins of dlmm position
+pub fn get_position_total_liquidity_share(position: &AccountInfo) -> Result<u128> {
+    let (_lower_bin_id, liquidity_shares) = get_position_liquidity_shares(position)?;
+    let mut total_liquidity_share: u128 = 0;
+    for liquidity_share in liquidity_shares {
+        total_liquidity_share = total_liquidity_share.safe_add(liquidity_share)?;
+    }
+    Ok(total_liquidity_share)
+}
+
+/// Amounts (x, y) withdrawn by removing all liquidity shares of position, before transfer fee,
+/// rounded down per bin as dlmm does, bins of the position must be in given bin arrays
+pub fn get_position_withdraw_amounts(
+    lower_bin_id: i32,
+    liquidity_shares: &[u128],
+    bin_arrays: &[&BinArray],
+) -> Result<(u64, u64)> {
+    let mut amount_x: u64 = 0;
+    let mut amount_y: u64 = 0;
+    for (bin_id, &liquidity_share) in (lower_bin_id..).zip(liquidity_shares) {
+        if liquidity_share == 0 {
+            continue;
+        }
+        let bin_array_index = bin_id_to_bin_array_index(bin_id);
+        let bin_array = bin_arrays
+            .iter()
+            .find(|bin_array| bin_array.index == bin_array_index)
+            .ok_or(ZapError::InvalidDlmmZapInParameters)?;
+        let bin_index = usize::try_from(
+            bin_id.safe_sub(
+                i32::try_from(bin_array_index)
+                    .map_err(|_| ZapError::TypeCastFailed)?
+                    .safe_mul(MAX_BIN_PER_ARRAY)?,
+            )?,
+        )
+        .map_err(|_| ZapError::TypeCastFailed)?;
+        let bin = &bin_array.bins[bin_index];
+        let get_out_amount = |bin_amount: u64| -> Result<u64> {
+            let out_amount = U256::from(liquidity_share)
+                .safe_mul(U256::from(bin_amount))?
+                .safe_div(U256::from(bin.liquidity_supply))?;
+            Ok(u64::try_from(out_amount).map_err(|_| ZapError::TypeCastFailed)?)
+        };
+        amount_x = amount_x.safe_add(get_out_amount(bin.amount_x)?)?;
+        amount_y = amount_y.safe_add(get_out_amount(bin.amount_y)?)?;
+    }
+    Ok((amount_x, amount_y))
+}
+
+/// Parts of ranges within [min_delta_id, max_delta_id], amount in each bin is unchanged since params are
+/// relative to active id, so a wide range can be split across adjacent positions
+pub fn clip_add_liquidity_ranges(
+    ranges: &[ParsedAddLiquidityRange],
+    min_delta_id: i32,
+    max_delta_id: i32,
+) -> Vec<ParsedAddLiquidityRange> {
+    ranges
+        .iter()
+        .filter_map(|range| {
+            let clipped_min_delta_id = range.min_delta_id.max(min_delta_id);
+            let clipped_max_delta_id = range.max_delta_id.min(max_delta_id);
+            (clipped_min_delta_id <= clipped_max_delta_id).then_some(ParsedAddLiquidityRange {
+                min_delta_id: clipped_min_delta_id,
+                max_delta_id: clipped_max_delta_id,
+                params: range.params,
+            })
+        })
+        .collect()
+}
+
+/// Positions given as (lower_bin_id, upper_bin_id) must be ascending, adjacent and cover [min_bin_id, max_bin_id]
+pub fn validate_position_ranges(
+    position_ranges: &[(i32, i32)],
+    min_bin_id: i32,
+    max_bin_id: i32,
+) -> Result<()> {
+    let (Some(first), Some(last)) = (position_ranges.first(), position_ranges.last()) else {
+        return Err(ZapError::InvalidPositionRanges.into());
+    };
+    require!(
+        first.0 <= min_bin_id && last.1 >= max_bin_id,
+        ZapError::InvalidPositionRanges
+    );
+    for (i, (lower_bin_id, upper_bin_id)) in position_ranges.iter().enumerate() {
+        require!(
+            lower_bin_id <= upper_bin_id,
+            ZapError::InvalidPositionRanges
+        );
+        if i > 0 {
+            require!(
+                *lower_bin_id == position_ranges[i - 1].1.safe_add(1)?,
+                ZapError::InvalidPositionRanges
+            );
+        }
+    }
+    Ok(())
+}
+
+// dlmm shrink modes, lower side is the left side of position
+pub const SHRINK_BOTH: u8 = 0;
+pub const NO_SHRINK_LOWER: u8 = 1;
+pub const NO_SHRINK_UPPER: u8 = 2;
+pub const NO_SHRINK_BOTH: u8 = 3;
+
+/// Length to add on (lower, upper) side of position so that it covers [min_bin_id, max_bin_id]
+pub fn get_position_length_to_add(
+    lower_bin_id: i32,
+    upper_bin_id: i32,
+    min_bin_id: i32,
+    max_bin_id: i32,
+) -> Result<(u16, u16)> {
+    let lower_length = lower_bin_id.safe_sub(min_bin_id)?.max(0);
+    let upper_length = max_bin_id.safe_sub(upper_bin_id)?.max(0);
+    Ok((
+        u16::try_from(lower_length).map_err(|_| ZapError::TypeCastFailed)?,
+        u16::try_from(upper_length).map_err(|_| ZapError::TypeCastFailed)?,
+    ))
+}
+
+/// Length to remove on (lower, upper) side of position, empty bins at the sides that shrink mode allows,
+/// at least one bin is kept
+pub fn get_position_length_to_remove(
+    liquidity_shares: &[u128],
+    shrink_mode: u8,
+) -> Result<(u16, u16)> {
+    require!(
+        shrink_mode <= NO_SHRINK_BOTH,
+        ZapError::InvalidDlmmZapInParameters
+    );
+    let shrink_lower = shrink_mode == SHRINK_BOTH || shrink_mode == NO_SHRINK_UPPER;
+    let shrink_upper = shrink_mode == SHRINK_BOTH || shrink_mode == NO_SHRINK_LOWER;
+    let width = liquidity_shares.len();
+
+    let (lower_length, upper_length) = match (
+        liquidity_shares.iter().position(|share| *share > 0),
+        liquidity_shares.iter().rposition(|share| *share > 0),
+    ) {
+        (Some(first), Some(last)) => (
+            if shrink_lower { first } else { 0 },
+            if shrink_upper { width - 1 - last } else { 0 },
+        ),
+        // empty position, keep the bin at the side that can't be shrunk
+        _ => {
+            let removable = width.saturating_sub(1);
+            if shrink_lower {
+                (removable, 0)
+            } else if shrink_upper {
+                (0, removable)
+            } else {
+                (0, 0)
+            }
+        }
+    };
+    Ok((
+        u16::try_from(lower_length).map_err(|_| ZapError::TypeCastFailed)?,
+        u16::try_from(upper_length).map_err(|_| ZapError::TypeCastFailed)?,
+    ))
+}
+
+/// Rebalance liquidity params that only claim fee and reward of position, without changing liquidity
+pub fn get_claim_fee_and_reward_params(
+    active_id: i32,
+    max_active_bin_slippage: u16,
+    should_claim_fee: bool,
+    should_claim_reward: bool,
+) -> RebalanceLiquidityParams {
+    RebalanceLiquidityParams {
+        active_id,
+        max_active_bin_slippage,
+        should_claim_fee,
+        should_claim_reward,
+        min_withdraw_x_amount: 0,
+        max_deposit_x_amount: 0,
+        min_withdraw_y_amount: 0,
+        max_deposit_y_amount: 0,
+        shrink_mode: NO_SHRINK_BOTH,
+        padding: [0; 31],
+        removes: vec![],
+        adds: vec![],
+    }
+}
+
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+pub const BIN_ARRAY_BITMAP_SIZE: i64 = 512;
+const BIN_ARRAY_SEED: &[u8] = b"bin_array";
+
+/// Bin array index of the bin, rounding toward negative infinity like dlmm
+pub fn bin_id_to_bin_array_index(bin_id: i32) -> i64 {
+    bin_id.div_euclid(MAX_BIN_PER_ARRAY).into()
+}
+
+/// Bin arrays out of the internal bitmap of lb pair are tracked in the bitmap extension
+pub fn is_overflow_default_bin_array_bitmap(bin_array_index: i64) -> bool {
+    !(-BIN_ARRAY_BITMAP_SIZE..BIN_ARRAY_BITMAP_SIZE).contains(&bin_array_index)
+}
+
+pub fn derive_bin_array(lb_pair: &Pubkey, index: i64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[BIN_ARRAY_SEED, lb_pair.as_ref(), &index.to_le_bytes()],
+        &dlmm::ID,
+    )
+    .0
+}
+
 pub const X0_NEG_FLAG: u8 = 0b1;
 pub const Y0_NEG_FLAG: u8 = 0b10;
 pub const DELTA_X_NEG_FLAG: u8 = 0b100;
@@ -82,14 +519,155 @@ pub struct ZapInRebalancingParams {
 }
 
 impl ZapInRebalancingParams {
-    pub fn get_rebalancing_params(&self) -> Result<UnparsedAddLiquidityParams> {
-        let strategy_handler: Box<dyn StrategyHandler> = match self.strategy {
-            StrategyType::Spot => Box::new(SpotHandler),
-            StrategyType::Curve => Box::new(CurveHandler),
-            StrategyType::BidAsk => Box::new(BidAskHandler),
+    // custom and piecewise strategies aren't linear, so they don't have a handler
+    pub fn get_strategy_handler(&self) -> Option<Box<dyn StrategyHandler>> {
+        match self.strategy {
+            StrategyType::Spot => Some(Box::new(SpotHandler)),
+            StrategyType::Curve => Some(Box::new(CurveHandler)),
+            StrategyType::BidAsk => Some(Box::new(BidAskHandler)),
+            StrategyType::Custom { .. } | StrategyType::Piecewise { .. } => None,
+        }
+    }
+
+    // linear strategies are a single range, custom strategy is a range per run of equal weights,
+    // piecewise strategy is a range per segment
+    pub fn get_parsed_add_liquidity_ranges(&self) -> Result<Vec<ParsedAddLiquidityRange>> {
+        match &self.strategy {
+            StrategyType::Custom { weights } => {
+                return self.get_custom_add_liquidity_ranges(weights);
+            }
+            StrategyType::Piecewise { segments } => {
+                return self.get_piecewise_add_liquidity_ranges(segments);
+            }
+            _ => {}
+        }
+        let strategy_handler = self
+            .get_strategy_handler()
+            .ok_or(ZapError::InvalidDlmmZapInParameters)?;
+        Ok(vec![ParsedAddLiquidityRange {
+            min_delta_id: self.min_delta_id,
+            max_delta_id: self.max_delta_id,
+            params: self.get_parsed_rebalancing_params(&strategy_handler)?,
+        }])
+    }
+
+    // adds of rebalance_liquidity
+    pub fn get_add_liquidity_params(&self) -> Result<Vec<AddLiquidityParams>> {
+        self.to_add_liquidity_params(&self.get_parsed_add_liquidity_ranges()?)
+    }
+
+    // adds of rebalance_liquidity for given ranges, e.g. ranges clipped to one position
+    pub fn to_add_liquidity_params(
+        &self,
+        ranges: &[ParsedAddLiquidityRange],
+    ) -> Result<Vec<AddLiquidityParams>> {
+        ranges
+            .iter()
+            .map(|range| {
+                let UnparsedAddLiquidityParams {
+                    x0,
+                    y0,
+                    delta_x,
+                    delta_y,
+                    bit_flag,
+                } = range.params.unparse()?;
+                Ok(AddLiquidityParams {
+                    min_delta_id: range.min_delta_id,
+                    max_delta_id: range.max_delta_id,
+                    x0,
+                    y0,
+                    delta_x,
+                    delta_y,
+                    favor_x_in_active_id: self.favor_x_in_active_id,
+                    bit_flag,
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    // bid side: amount_y in bin = amount_y * weight / sum(weight)
+    // ask side: x0 of bin = amount_x * weight / sum(weight * p(m)), same unit as x0 in spot strategy
+    // so amount_x in bin = x0 * p(m) where p(m) = (1+b)^-(active_id + m)
+    fn get_custom_add_liquidity_ranges(
+        &self,
+        weights: &[u16],
+    ) -> Result<Vec<ParsedAddLiquidityRange>> {
+        let bin_count = self.max_delta_id.safe_sub(self.min_delta_id)?.safe_add(1)?;
+        require!(
+            usize::try_from(bin_count).map_err(|_| ZapError::TypeCastFailed)? == weights.len(),
+            ZapError::InvalidStrategyWeights
+        );
+        let get_weight = |delta_id: i32| -> Result<u16> {
+            let idx = usize::try_from(delta_id.safe_sub(self.min_delta_id)?)
+                .map_err(|_| ZapError::TypeCastFailed)?;
+            Ok(weights[idx])
         };
-        let parsed_params = self.get_parsed_rebalancing_params(&strategy_handler)?;
-        parsed_params.unparse()
+
+        let (bid_side_end_delta_id, ask_side_start_delta_id) = self.get_max_delta_id_both_side();
+        let bid_side_end_delta_id = bid_side_end_delta_id.min(self.max_delta_id);
+        let ask_side_start_delta_id = ask_side_start_delta_id.max(self.min_delta_id);
+
+        let mut ranges = vec![];
+
+        let mut total_weight_y: u128 = 0;
+        for delta_id in self.min_delta_id..=bid_side_end_delta_id {
+            total_weight_y = total_weight_y.safe_add(get_weight(delta_id)?.into())?;
+        }
+        if self.amount_y > 0 && total_weight_y > 0 {
+            for (min_delta_id, max_delta_id, weight) in
+                get_weight_runs(&get_weight, self.min_delta_id, bid_side_end_delta_id)?
+            {
+                let y0 = u128::from(self.amount_y)
+                    .safe_mul(weight.into())?
+                    .safe_div(total_weight_y)?;
+                ranges.push(ParsedAddLiquidityRange {
+                    min_delta_id,
+                    max_delta_id,
+                    params: ParsedAddLiquidityParams {
+                        x0: 0,
+                        y0: i128::try_from(y0).map_err(|_| ZapError::TypeCastFailed)?,
+                        delta_x: 0,
+                        delta_y: 0,
+                    },
+                });
+            }
+        }
+
+        let mut total_weight_x = U256::ZERO;
+        for_each_inverse_bin_price(
+            self.active_id.safe_add(ask_side_start_delta_id)?,
+            self.active_id.safe_add(self.max_delta_id)?,
+            self.bin_step,
+            |bin_id, base_price| {
+                let delta_id = bin_id.safe_sub(self.active_id)?;
+                total_weight_x = total_weight_x
+                    .safe_add(base_price.safe_mul(U256::from(get_weight(delta_id)?))?)?;
+                Ok(())
+            },
+        )?;
+        if self.amount_x > 0 && total_weight_x > U256::ZERO {
+            for (min_delta_id, max_delta_id, weight) in
+                get_weight_runs(&get_weight, ask_side_start_delta_id, self.max_delta_id)?
+            {
+                let x0 = U256::from(self.amount_x)
+                    .safe_shl(64)?
+                    .safe_mul(U256::from(weight))?
+                    .safe_div(total_weight_x)?;
+                ranges.push(ParsedAddLiquidityRange {
+                    min_delta_id,
+                    max_delta_id,
+                    params: ParsedAddLiquidityParams {
+                        x0: i128::try_from(x0).map_err(|_| ZapError::TypeCastFailed)?,
+                        y0: 0,
+                        delta_x: 0,
+                        delta_y: 0,
+                    },
+                });
+            }
+        }
+
+        Ok(ranges)
     }
 
     pub fn get_max_delta_id_both_side(&self) -> (i32, i32) {
@@ -166,6 +744,310 @@ impl ZapInRebalancingParams {
             delta_y,
         })
     }
+
+    // (sum of bid side bin values, sum of ask side bin values * p(m)) of the strategy shape in unit of value,
+    // amount_y : amount_x = bid_side_weight : (ask_side_weight >> 64) keeps the shape continuous around active id:
+    // spot is flat, curve peaks at active id and goes to zero at both ends, bid ask grows from zero by one per bin
+    // on both sides, custom uses the weights, piecewise follows the weight split of its segments
+    pub fn get_strategy_side_weights(&self) -> Result<(u128, U256)> {
+        if let StrategyType::Piecewise { segments } = &self.strategy {
+            return self.get_piecewise_side_weights(segments);
+        }
+        let (bid_side_weight, ask_side_weight, _) = self.get_side_weights_and_ask_side_value()?;
+        Ok((bid_side_weight, ask_side_weight))
+    }
+
+    // (bid side weight, ask side weight, sum of ask side bin values) of a non piecewise strategy
+    fn get_side_weights_and_ask_side_value(&self) -> Result<(u128, U256, u128)> {
+        let (bid_side_end_delta_id, ask_side_start_delta_id) = self.get_max_delta_id_both_side();
+        let bid_side_end_delta_id = bid_side_end_delta_id.min(self.max_delta_id);
+        let ask_side_start_delta_id = ask_side_start_delta_id.max(self.min_delta_id);
+        let is_single_bid_bin = self.min_delta_id == bid_side_end_delta_id;
+        let is_single_ask_bin = ask_side_start_delta_id == self.max_delta_id;
+        // curve goes to zero at min_delta_id and max_delta_id, scale both sides to the same peak
+        let m1 = u128::from(self.min_delta_id.neg().max(1).unsigned_abs());
+        let m2 = u128::from(self.max_delta_id.max(1).unsigned_abs());
+
+        let get_bin_weight = |delta_id: i32| -> Result<u128> {
+            let is_bid_side = delta_id <= bid_side_end_delta_id;
+            let distance = u128::from(delta_id.unsigned_abs());
+            let weight = match &self.strategy {
+                StrategyType::Spot => 1,
+                StrategyType::Curve => match (is_bid_side, is_single_bid_bin, is_single_ask_bin) {
+                    (true, true, _) | (false, _, true) => m1.safe_mul(m2)?,
+                    (true, false, _) => m1.saturating_sub(distance).safe_mul(m2)?,
+                    (false, _, false) => m2.saturating_sub(distance).safe_mul(m1)?,
+                },
+                StrategyType::BidAsk => match (is_bid_side, is_single_bid_bin, is_single_ask_bin) {
+                    (true, true, _) | (false, _, true) => 1,
+                    (true, false, _) => {
+                        u128::from(bid_side_end_delta_id.safe_sub(delta_id)?.unsigned_abs())
+                    }
+                    (false, _, false) => {
+                        u128::from(delta_id.safe_sub(ask_side_start_delta_id)?.unsigned_abs())
+                    }
+                },
+                StrategyType::Custom { weights } => {
+                    require!(
+                        usize::try_from(
+                            self.max_delta_id.safe_sub(self.min_delta_id)?.safe_add(1)?
+                        )
+                        .map_err(|_| ZapError::TypeCastFailed)?
+                            == weights.len(),
+                        ZapError::InvalidStrategyWeights
+                    );
+                    let idx = usize::try_from(delta_id.safe_sub(self.min_delta_id)?)
+                        .map_err(|_| ZapError::TypeCastFailed)?;
+                    weights[idx].into()
+                }
+                StrategyType::Piecewise { .. } => {
+                    return Err(ZapError::InvalidStrategySegments.into());
+                }
+            };
+            Ok(weight)
+        };
+
+        let mut bid_side_weight: u128 = 0;
+        for delta_id in self.min_delta_id..=bid_side_end_delta_id {
+            bid_side_weight = bid_side_weight.safe_add(get_bin_weight(delta_id)?)?;
+        }
+        let mut ask_side_weight = U256::ZERO;
+        let mut ask_side_value: u128 = 0;
+        for_each_inverse_bin_price(
+            self.active_id.safe_add(ask_side_start_delta_id)?,
+            self.active_id.safe_add(self.max_delta_id)?,
+            self.bin_step,
+            |bin_id, base_price| {
+                let bin_weight = get_bin_weight(bin_id.safe_sub(self.active_id)?)?;
+                ask_side_weight =
+                    ask_side_weight.safe_add(base_price.safe_mul(U256::from(bin_weight))?)?;
+                ask_side_value = ask_side_value.safe_add(bin_weight)?;
+                Ok(())
+            },
+        )?;
+
+        Ok((bid_side_weight, ask_side_weight, ask_side_value))
+    }
+
+    // (total weight of segments having ask side bins, total weight of segments having bid side bins),
+    // a segment takes y if it has bid side bins and takes x if it has ask side bins
+    fn get_segments_total_weights(&self, segments: &[StrategySegment]) -> Result<(u128, u128)> {
+        require!(!segments.is_empty(), ZapError::InvalidStrategySegments);
+        let mut next_min_delta_id = self.min_delta_id;
+        for segment in segments.iter() {
+            require!(
+                segment.min_delta_id >= next_min_delta_id
+                    && segment.min_delta_id <= segment.max_delta_id
+                    && segment.max_delta_id <= self.max_delta_id,
+                ZapError::InvalidStrategySegments
+            );
+            next_min_delta_id = segment.max_delta_id.safe_add(1)?;
+        }
+
+        let (bid_side_end_delta_id, ask_side_start_delta_id) = self.get_max_delta_id_both_side();
+        let mut total_weight_x: u128 = 0;
+        let mut total_weight_y: u128 = 0;
+        for segment in segments.iter() {
+            if segment.min_delta_id <= bid_side_end_delta_id {
+                total_weight_y = total_weight_y.safe_add(segment.weight.into())?;
+            }
+            if segment.max_delta_id >= ask_side_start_delta_id {
+                total_weight_x = total_weight_x.safe_add(segment.weight.into())?;
+            }
+        }
+        Ok((total_weight_x, total_weight_y))
+    }
+
+    // amount x (y) is split by weight across segments, so the segment having both side bins fixes the ratio:
+    // amount_y / total_weight_y : amount_x / total_weight_x = its bid side weight : its ask side weight.
+    // Without such segment each side is fully deposited at any ratio, each segment then takes the value of its weight
+    fn get_piecewise_side_weights(&self, segments: &[StrategySegment]) -> Result<(u128, U256)> {
+        let (total_weight_x, total_weight_y) = self.get_segments_total_weights(segments)?;
+        let (bid_side_end_delta_id, ask_side_start_delta_id) = self.get_max_delta_id_both_side();
+        let get_segment_params = |segment: &StrategySegment| ZapInRebalancingParams {
+            min_delta_id: segment.min_delta_id,
+            max_delta_id: segment.max_delta_id,
+            strategy: segment.strategy.into(),
+            ..*self
+        };
+
+        if let Some(segment) = segments.iter().find(|segment| {
+            segment.min_delta_id <= bid_side_end_delta_id
+                && segment.max_delta_id >= ask_side_start_delta_id
+        }) {
+            let (bid_side_weight, ask_side_weight, _) =
+                get_segment_params(segment).get_side_weights_and_ask_side_value()?;
+            return Ok((
+                bid_side_weight.safe_mul(total_weight_y)?,
+                ask_side_weight.safe_mul(U256::from(total_weight_x))?,
+            ));
+        }
+
+        let mut bid_side_weight: u128 = 0;
+        let mut ask_side_weight = U256::ZERO;
+        for segment in segments.iter() {
+            if segment.min_delta_id <= bid_side_end_delta_id {
+                bid_side_weight = bid_side_weight.safe_add(segment.weight.into())?;
+            } else {
+                // ask side weight of the segment per unit of value
+                let (_, segment_ask_side_weight, segment_ask_side_value) =
+                    get_segment_params(segment).get_side_weights_and_ask_side_value()?;
+                if segment_ask_side_value == 0 {
+                    continue;
+                }
+                ask_side_weight = ask_side_weight.safe_add(
+                    segment_ask_side_weight
+                        .safe_mul(U256::from(segment.weight))?
+                        .safe_div(U256::from(segment_ask_side_value))?,
+                )?;
+            }
+        }
+        Ok((bid_side_weight, ask_side_weight))
+    }
+
+    fn get_piecewise_add_liquidity_ranges(
+        &self,
+        segments: &[StrategySegment],
+    ) -> Result<Vec<ParsedAddLiquidityRange>> {
+        let (total_weight_x, total_weight_y) = self.get_segments_total_weights(segments)?;
+        let (bid_side_end_delta_id, ask_side_start_delta_id) = self.get_max_delta_id_both_side();
+        let split_amount = |amount: u64, weight: u16, total_weight: u128| -> Result<u64> {
+            if total_weight == 0 {
+                return Ok(0);
+            }
+            let amount = u128::from(amount)
+                .safe_mul(weight.into())?
+                .safe_div(total_weight)?;
+            Ok(u64::try_from(amount).map_err(|_| ZapError::TypeCastFailed)?)
+        };
+
+        let mut ranges = vec![];
+        for segment in segments.iter() {
+            let amount_x = if segment.max_delta_id >= ask_side_start_delta_id {
+                split_amount(self.amount_x, segment.weight, total_weight_x)?
+            } else {
+                0
+            };
+            let amount_y = if segment.min_delta_id <= bid_side_end_delta_id {
+                split_amount(self.amount_y, segment.weight, total_weight_y)?
+            } else {
+                0
+            };
+            if amount_x == 0 && amount_y == 0 {
+                continue;
+            }
+            let params = ZapInRebalancingParams {
+                amount_x,
+                amount_y,
+                min_delta_id: segment.min_delta_id,
+                max_delta_id: segment.max_delta_id,
+                strategy: segment.strategy.into(),
+                ..*self
+            };
+            ranges.append(&mut params.get_parsed_add_liquidity_ranges()?);
+        }
+
+        Ok(ranges)
+    }
+
+    // amounts of all bins from min_delta_id to max_delta_id, bins not in any range are empty
+    pub fn get_amount_in_bins_of_ranges(
+        &self,
+        ranges: &[ParsedAddLiquidityRange],
+    ) -> Result<Vec<AmountInBin>> {
+        let min_bin_id = self.active_id.safe_add(self.min_delta_id)?;
+        let max_bin_id = self.active_id.safe_add(self.max_delta_id)?;
+        let mut amounts_in_bin: Vec<AmountInBin> = (min_bin_id..=max_bin_id)
+            .map(|bin_id| AmountInBin {
+                bin_id,
+                ..Default::default()
+            })
+            .collect();
+
+        for range in ranges.iter() {
+            let params = ZapInRebalancingParams {
+                min_delta_id: range.min_delta_id,
+                max_delta_id: range.max_delta_id,
+                strategy: StrategyType::Spot,
+                ..*self
+            };
+            for amount_in_bin in params.get_amount_in_bins(&range.params)? {
+                let idx = usize::try_from(amount_in_bin.bin_id.safe_sub(min_bin_id)?)
+                    .map_err(|_| ZapError::TypeCastFailed)?;
+                let bin = amounts_in_bin
+                    .get_mut(idx)
+                    .ok_or(ZapError::InvalidDlmmZapInParameters)?;
+                bin.amount_x = bin.amount_x.safe_add(amount_in_bin.amount_x)?;
+                bin.amount_y = bin.amount_y.safe_add(amount_in_bin.amount_y)?;
+            }
+        }
+
+        Ok(amounts_in_bin)
+    }
+
+    // total (amount_x, amount_y) dlmm takes to add the ranges, before transfer fee
+    pub fn get_total_amount_of_ranges(
+        &self,
+        ranges: &[ParsedAddLiquidityRange],
+    ) -> Result<(u64, u64)> {
+        let mut amount_x: u64 = 0;
+        let mut amount_y: u64 = 0;
+        for amount_in_bin in self.get_amount_in_bins_of_ranges(ranges)? {
+            amount_x = amount_x.safe_add(amount_in_bin.amount_x)?;
+            amount_y = amount_y.safe_add(amount_in_bin.amount_y)?;
+        }
+        Ok((amount_x, amount_y))
+    }
+
+    // replicate how dlmm distributes amounts to bins from add liquidity params
+    pub fn get_amount_in_bins(
+        &self,
+        parsed_params: &ParsedAddLiquidityParams,
+    ) -> Result<Vec<AmountInBin>> {
+        // only deposit y
+        if self.is_only_deposit_y() {
+            return get_amount_in_bins_bid_side(
+                self.active_id,
+                self.min_delta_id,
+                self.max_delta_id,
+                parsed_params.delta_y,
+                parsed_params.y0,
+            );
+        }
+        // only deposit x
+        if self.is_only_deposit_x() {
+            return get_amount_in_bins_ask_side(
+                self.active_id,
+                self.bin_step,
+                self.min_delta_id,
+                self.max_delta_id,
+                parsed_params.delta_x,
+                parsed_params.x0,
+            );
+        }
+
+        // deposit both x and y
+        let (bid_side_end_delta_id, ask_side_start_delta_id) = self.get_max_delta_id_both_side();
+
+        let amounts_bid_side = get_amount_in_bins_bid_side(
+            self.active_id,
+            self.min_delta_id,
+            bid_side_end_delta_id,
+            parsed_params.delta_y,
+            parsed_params.y0,
+        )?;
+
+        let amounts_ask_side = get_amount_in_bins_ask_side(
+            self.active_id,
+            self.bin_step,
+            ask_side_start_delta_id,
+            self.max_delta_id,
+            parsed_params.delta_x,
+            parsed_params.x0,
+        )?;
+
+        Ok([amounts_bid_side, amounts_ask_side].concat())
+    }
 }
 
 pub trait StrategyHandler {
@@ -220,10 +1102,10 @@ impl StrategyHandler for SpotHandler {
         let min_bin_id = active_id.safe_add(min_delta_id)?;
         let max_bin_id = active_id.safe_add(max_delta_id)?;
 
-        for bin_id in min_bin_id..=max_bin_id {
-            let base_price = U256::from(get_price_from_id(bin_id.neg(), bin_step)?);
+        for_each_inverse_bin_price(min_bin_id, max_bin_id, bin_step, |_bin_id, base_price| {
             total_weight = total_weight.safe_add(base_price)?;
-        }
+            Ok(())
+        })?;
         let amount_x = U256::from(amount_x);
         let x0 = amount_x.safe_shl(64)?.safe_div(total_weight)?;
         let x0 = i128::try_from(x0).map_err(|_| ZapError::TypeCastFailed)?;
@@ -318,14 +1200,19 @@ impl StrategyHandler for CurveHandler {
 
         let mut c_numerator = U256::ZERO;
 
-        for m in m1..=m2 {
-            let bin_id = active_id.safe_add(m)?;
-            let pm = U256::from(get_price_from_id(bin_id.neg(), bin_step)?);
+        for_each_inverse_bin_price(
+            active_id.safe_add(m1)?,
+            active_id.safe_add(m2)?,
+            bin_step,
+            |bin_id, pm| {
+                let m = bin_id.safe_sub(active_id)?;
 
-            b = b.safe_add(pm)?;
+                b = b.safe_add(pm)?;
 
-            c_numerator = c_numerator.safe_add(U256::from(m).safe_mul(pm)?)?;
-        }
+                c_numerator = c_numerator.safe_add(U256::from(m).safe_mul(pm)?)?;
+                Ok(())
+            },
+        )?;
 
         let c = c_numerator.safe_div(U256::from(m2))?;
 
@@ -413,20 +1300,25 @@ impl StrategyHandler for BidAskHandler {
         let mut c = U256::ZERO;
         let m1 = U256::try_from(min_delta_id).map_err(|_| ZapError::TypeCastFailed)?;
 
-        for m in min_delta_id..=max_delta_id {
-            let bin_id = active_id.safe_add(m)?;
-            let pm = U256::from(get_price_from_id(bin_id.neg(), bin_step)?);
+        for_each_inverse_bin_price(
+            active_id.safe_add(min_delta_id)?,
+            active_id.safe_add(max_delta_id)?,
+            bin_step,
+            |bin_id, pm| {
+                let m = bin_id.safe_sub(active_id)?;
 
-            let b_delta = m1.safe_mul(pm)?;
+                let b_delta = m1.safe_mul(pm)?;
 
-            b = b.safe_add(b_delta)?;
+                b = b.safe_add(b_delta)?;
 
-            let c_delta = U256::try_from(m)
-                .map_err(|_| ZapError::TypeCastFailed)?
-                .safe_mul(pm)?;
+                let c_delta = U256::try_from(m)
+                    .map_err(|_| ZapError::TypeCastFailed)?
+                    .safe_mul(pm)?;
 
-            c = c.safe_add(c_delta)?;
-        }
+                c = c.safe_add(c_delta)?;
+                Ok(())
+            },
+        )?;
 
         //reverse b to c
         let delta_x = U256::from(amount_x)
@@ -438,6 +1330,50 @@ impl StrategyHandler for BidAskHandler {
     }
 }
 
+// runs of consecutive bins with the same non-zero weight in [min_delta_id, max_delta_id]
+fn get_weight_runs(
+    get_weight: &dyn Fn(i32) -> Result<u16>,
+    min_delta_id: i32,
+    max_delta_id: i32,
+) -> Result<Vec<(i32, i32, u16)>> {
+    let mut runs: Vec<(i32, i32, u16)> = vec![];
+    for delta_id in min_delta_id..=max_delta_id {
+        let weight = get_weight(delta_id)?;
+        if weight == 0 {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((_, end, run_weight)) if *end + 1 == delta_id && *run_weight == weight => {
+                *end = delta_id;
+            }
+            _ => runs.push((delta_id, delta_id, weight)),
+        }
+    }
+    Ok(runs)
+}
+
+// calls f with (bin_id, (1+b)^-bin_id) from max_bin_id down to min_bin_id, only the price of max bin is a full pow,
+// the others are multiplied by base factor from the previous bin like get_amount_in_bins_ask_side
+pub fn for_each_inverse_bin_price(
+    min_bin_id: i32,
+    max_bin_id: i32,
+    bin_step: u16,
+    mut f: impl FnMut(i32, U256) -> Result<()>,
+) -> Result<()> {
+    let base_u128 = get_price_base_factor(bin_step)?;
+    let base = U256::from(base_u128);
+    let mut inverse_base_price =
+        U256::from(pow(base_u128, max_bin_id.neg()).ok_or(ZapError::MathOverflow)?);
+
+    let mut bin_id = max_bin_id;
+    while bin_id >= min_bin_id {
+        f(bin_id, inverse_base_price)?;
+        (inverse_base_price, _) = inverse_base_price.safe_mul(base)?.overflowing_shr(64);
+        bin_id = bin_id.safe_sub(1)?;
+    }
+    Ok(())
+}
+
 fn find_x0_and_delta_x_single_bin(
     bin_id: i32,
     bin_step: u16,
@@ -448,3 +1384,189 @@ fn find_x0_and_delta_x_single_bin(
     let x0: i128 = x0.try_into().map_err(|_| ZapError::TypeCastFailed)?;
     return Ok((x0, 0));
 }
+
+pub fn get_amount_in_bins_bid_side(
+    active_id: i32,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    delta_y: i128,
+    y0: i128,
+) -> Result<Vec<AmountInBin>> {
+    // This won't be negative because already validated min_bin_id <= max_bin_id at validate_and_get_bin_range
+    let bin_count = max_delta_id.safe_sub(min_delta_id)?.safe_add(1)?;
+    let mut amounts_in_bin = vec![AmountInBin::default(); bin_count as usize];
+
+    let min_bin_id = active_id.safe_add(min_delta_id)?;
+    let max_bin_id = active_id.safe_add(max_delta_id)?;
+
+    for (idx, bin_id) in (min_bin_id..=max_bin_id).enumerate() {
+        let delta_bin = active_id.safe_sub(bin_id)?;
+
+        let total_delta_y = delta_y.safe_mul(delta_bin.into())?;
+
+        let amount_y = y0
+            .safe_add(total_delta_y)?
+            .try_into()
+            .map_err(|_| ZapError::TypeCastFailed)?;
+
+        amounts_in_bin[idx] = AmountInBin {
+            bin_id,
+            amount_x: 0,
+            amount_y,
+        };
+    }
+
+    Ok(amounts_in_bin)
+}
+
+pub fn get_amount_in_bins_ask_side(
+    active_id: i32,
+    bin_step: u16,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    delta_x: i128,
+    x0: i128,
+) -> Result<Vec<AmountInBin>> {
+    // This won't be negative because already validated min_bin_id <= max_bin_id at validate_and_get_bin_range
+    let bin_count = max_delta_id.safe_sub(min_delta_id)?.safe_add(1)?;
+
+    let base_u128 = get_price_base_factor(bin_step)?;
+    let base = U256::from(base_u128);
+
+    let max_bin_id = active_id.safe_add(max_delta_id)?;
+    let min_bin_id = active_id.safe_add(min_delta_id)?;
+
+    // we use inverse base price to avoid safe_div (safe_mul can save more CU)
+    let mut inverse_base_price =
+        U256::from(pow(base_u128, max_bin_id.neg().into()).ok_or_else(|| ZapError::MathOverflow)?);
+
+    let mut amounts_in_bin = vec![AmountInBin::default(); bin_count as usize];
+    let mut current_bin_id = max_bin_id;
+
+    loop {
+        if current_bin_id < min_bin_id {
+            break;
+        }
+
+        let delta_bin = current_bin_id.safe_sub(active_id)?;
+
+        let total_delta_x = delta_x.safe_mul(delta_bin.into())?;
+
+        let amount_x =
+            U256::try_from(x0.safe_add(total_delta_x)?).map_err(|_| ZapError::TypeCastFailed)?;
+
+        let (amount_x, _) = amount_x.safe_mul(inverse_base_price)?.overflowing_shr(64);
+
+        let amount_x = u64::try_from(amount_x).map_err(|_| ZapError::TypeCastFailed)?;
+
+        let idx: usize = current_bin_id
+            .safe_sub(min_bin_id)?
+            .try_into()
+            .map_err(|_| ZapError::TypeCastFailed)?;
+
+        amounts_in_bin[idx] = AmountInBin {
+            bin_id: current_bin_id,
+            amount_x,
+            amount_y: 0,
+        };
+
+        (inverse_base_price, _) = inverse_base_price.safe_mul(base)?.overflowing_shr(64);
+
+        current_bin_id = current_bin_id.safe_sub(1)?;
+    }
+
+    Ok(amounts_in_bin)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ZapInDlmmQuote {
+    pub active_id: i32,
+    /// add liquidity params of linear strategies, zero for custom and piecewise strategies
+    pub x0: u64,
+    pub y0: u64,
+    pub delta_x: u64,
+    pub delta_y: u64,
+    pub bit_flag: u8,
+    /// bin id of the first amount in amount_y_in_bins, it's active_id + min_delta_id
+    pub bid_side_start_bin_id: i32,
+    /// amount y in bid side bins, only y is deposited in bid side
+    pub amount_y_in_bins: Vec<u64>,
+    /// bin id of the first amount in amount_x_in_bins
+    pub ask_side_start_bin_id: i32,
+    /// amount x in ask side bins, only x is deposited in ask side
+    pub amount_x_in_bins: Vec<u64>,
+}
+
+// shared by quote_zap_in_dlmm and off-chain quote
+pub fn get_zap_in_dlmm_quote(
+    lb_pair: &LbPair,
+    amount_x: u64,
+    amount_y: u64,
+    min_delta_id: i32,
+    max_delta_id: i32,
+    favor_x_in_active_id: bool,
+    strategy: StrategyType,
+) -> Result<ZapInDlmmQuote> {
+    require!(
+        min_delta_id <= max_delta_id,
+        ZapError::InvalidDlmmZapInParameters
+    );
+
+    let params = ZapInRebalancingParams {
+        amount_x,
+        amount_y,
+        active_id: lb_pair.active_id,
+        bin_step: lb_pair.bin_step,
+        min_delta_id,
+        max_delta_id,
+        favor_x_in_active_id,
+        strategy,
+    };
+
+    let ranges = params.get_parsed_add_liquidity_ranges()?;
+    let amount_in_bins = params.get_amount_in_bins_of_ranges(&ranges)?;
+
+    // custom and piecewise strategies have several add liquidity params, only amounts in bins are quoted
+    let UnparsedAddLiquidityParams {
+        x0,
+        y0,
+        delta_x,
+        delta_y,
+        bit_flag,
+    } = match (params.get_strategy_handler(), ranges.first()) {
+        (Some(_), Some(range)) => range.params.unparse()?,
+        _ => UnparsedAddLiquidityParams::default(),
+    };
+
+    let (_bid_side_end_delta_id, ask_side_start_delta_id) = params.get_max_delta_id_both_side();
+    let ask_side_start_bin_id = lb_pair
+        .active_id
+        .safe_add(ask_side_start_delta_id.max(min_delta_id))?;
+
+    let mut quote = ZapInDlmmQuote {
+        active_id: lb_pair.active_id,
+        x0,
+        y0,
+        delta_x,
+        delta_y,
+        bit_flag,
+        bid_side_start_bin_id: lb_pair.active_id.safe_add(min_delta_id)?,
+        ask_side_start_bin_id,
+        ..Default::default()
+    };
+
+    for &AmountInBin {
+        bin_id,
+        amount_x,
+        amount_y,
+    } in amount_in_bins.iter()
+    {
+        if bin_id < ask_side_start_bin_id {
+            quote.amount_y_in_bins.push(amount_y);
+        } else {
+            quote.amount_x_in_bins.push(amount_x);
+        }
+    }
+
+    Ok(quote)
+}
@@ -2,5 +2,7 @@ pub mod damm_v2_utils;
 pub use damm_v2_utils::*;
 pub mod dlmm_utils;
 pub use dlmm_utils::*;
+pub mod dlmm_swap_utils;
+pub use dlmm_swap_utils::*;
 pub mod token;
 pub use token::*;
@@ -7,7 +7,7 @@ use anchor_spl::{
             self, transfer_fee::TransferFee, BaseStateWithExtensions, StateWithExtensions,
         },
     },
-    token_interface::Mint,
+    token_interface::{self, Mint, TransferChecked},
 };
 use damm_v2::token::TransferFeeExcludedAmount;
 
@@ -20,6 +20,36 @@ pub struct TransferFeeCalculator {
 }
 
 impl TransferFeeCalculator {
+    pub fn no_transfer_fee() -> Self {
+        TransferFeeCalculator {
+            epoch_transfer_fee: TransferFee::default(),
+            no_transfer_fee_extension: true,
+        }
+    }
+
+    // shared by program and off-chain quote, epoch is only loaded for mint has transfer fee extension
+    pub fn from_mint_data(
+        mint_owner: &Pubkey,
+        mint_data: &[u8],
+        get_epoch: impl FnOnce() -> Result<u64>,
+    ) -> Result<Self> {
+        if *mint_owner == Token::id() {
+            return Ok(Self::no_transfer_fee());
+        }
+        let token_mint_unpacked =
+            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(mint_data)?;
+        if let Ok(transfer_fee_config) =
+            token_mint_unpacked.get_extension::<extension::transfer_fee::TransferFeeConfig>()
+        {
+            Ok(TransferFeeCalculator {
+                epoch_transfer_fee: *transfer_fee_config.get_epoch_fee(get_epoch()?),
+                no_transfer_fee_extension: false,
+            })
+        } else {
+            Ok(Self::no_transfer_fee())
+        }
+    }
+
     pub fn calculate_transfer_fee_excluded_amount(
         &self,
         amount: u64,
@@ -43,34 +73,55 @@ impl TransferFeeCalculator {
             });
         }
     }
+
+    // amount need to be transferred, so the receiver gets exactly the given amount
+    pub fn calculate_transfer_fee_included_amount(&self, amount: u64) -> Result<u64> {
+        if self.no_transfer_fee_extension || amount == 0 {
+            return Ok(amount);
+        }
+        let transfer_fee = self
+            .epoch_transfer_fee
+            .calculate_inverse_fee(amount)
+            .ok_or_else(|| ZapError::MathOverflow)?;
+        Ok(amount
+            .checked_add(transfer_fee)
+            .ok_or_else(|| ZapError::MathOverflow)?)
+    }
 }
 
 pub fn new_transfer_fee_calculator<'info>(
     token_mint: &InterfaceAccount<'info, Mint>,
 ) -> Result<TransferFeeCalculator> {
     let token_mint_info = token_mint.to_account_info();
-    if *token_mint_info.owner == Token::id() {
-        return Ok(TransferFeeCalculator {
-            epoch_transfer_fee: TransferFee::default(),
-            no_transfer_fee_extension: true,
-        });
-    }
-
     let token_mint_data = token_mint_info.try_borrow_data()?;
-    let token_mint_unpacked =
-        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&token_mint_data)?;
-    if let Ok(transfer_fee_config) =
-        token_mint_unpacked.get_extension::<extension::transfer_fee::TransferFeeConfig>()
-    {
-        let epoch = Clock::get()?.epoch;
-        return Ok(TransferFeeCalculator {
-            epoch_transfer_fee: *transfer_fee_config.get_epoch_fee(epoch),
-            no_transfer_fee_extension: false,
-        });
-    } else {
-        return Ok(TransferFeeCalculator {
-            epoch_transfer_fee: TransferFee::default(),
-            no_transfer_fee_extension: true,
-        });
+    TransferFeeCalculator::from_mint_data(token_mint_info.owner, &token_mint_data, || {
+        Ok(Clock::get()?.epoch)
+    })
+}
+
+// transfer token from user token account, doesn't support transfer hook token
+pub fn transfer_from_user<'info>(
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    authority: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
     }
+    token_interface::transfer_checked(
+        CpiContext::new(
+            token_program,
+            TransferChecked {
+                from,
+                mint: token_mint.to_account_info(),
+                to,
+                authority,
+            },
+        ),
+        amount,
+        token_mint.decimals,
+    )
 }
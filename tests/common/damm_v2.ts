@@ -60,6 +60,7 @@ export const DAMM_V2_SWAP_DISC = [248, 198, 158, 145, 225, 117, 135, 200];
 
 export type Pool = IdlAccounts<CpAmm>["pool"];
 export type Position = IdlAccounts<CpAmm>["position"];
+export type DynamicFeeParameters = IdlTypes<CpAmm>["dynamicFeeParameters"];
 
 export function createDammV2Program() {
   const wallet = new Wallet(Keypair.generate());
@@ -169,7 +170,8 @@ export async function createDammV2Pool(
   tokenBMint: PublicKey,
   amountA?: BN,
   amountB?: BN,
-  baseFeeParams?: Buffer
+  baseFeeParams?: Buffer,
+  dynamicFee?: DynamicFeeParameters
 ): Promise<PublicKey> {
   const program = createDammV2Program();
 
@@ -235,7 +237,7 @@ export async function createDammV2Pool(
     .initializeCustomizablePool({
       poolFees: {
         baseFee,
-        dynamicFee: null,
+        dynamicFee: dynamicFee ?? null,
       },
       sqrtMinPrice: MIN_SQRT_PRICE,
       sqrtMaxPrice: MAX_SQRT_PRICE,
@@ -60,6 +60,17 @@ export const StrategyType = {
   Spot: { spot: {} },
   Curve: { curve: {} },
   BidAsk: { bidAsk: {} },
+  // weight of each bin from min delta id to max delta id
+  Custom: (weights: number[]) => ({ custom: { weights } }),
+  // ascending and non-overlapping segments, amounts are split by segment weight
+  Piecewise: (
+    segments: {
+      minDeltaId: number;
+      maxDeltaId: number;
+      strategy: { spot: {} } | { curve: {} } | { bidAsk: {} };
+      weight: number;
+    }[]
+  ) => ({ piecewise: { segments } }),
 };
 export const AccountsType = {
   TransferHookX: {
@@ -1,10 +1,11 @@
 import { BN } from "@coral-xyz/anchor";
-import { LiteSVM } from "litesvm";
+import { LiteSVM, TransactionMetadata } from "litesvm";
 import {
   AccountMeta,
   PublicKey,
   SystemProgram,
   SYSVAR_INSTRUCTIONS_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
   Transaction,
 } from "@solana/web3.js";
 import { DAMM_V2_PROGRAM_ID } from "../damm_v2";
@@ -16,6 +17,7 @@ import {
   getDammV2Pool,
 } from "../pda";
 import { createZapProgram } from "./zapOut";
+import { U64_MAX } from "../utils";
 import { getAssociatedTokenAddressSync } from "@solana/spl-token";
 import {
   DLMM_PROGRAM_ID_LOCAL,
@@ -23,6 +25,124 @@ import {
   MEMO_PROGRAM_ID,
 } from "../dlmm";
 
+export type ZapInResult = {
+  liquidityDelta: BN;
+  amountADeposited: BN;
+  amountBDeposited: BN;
+  swapInAmount: BN;
+  swapOutAmount: BN;
+  remainingAmountA: BN;
+  remainingAmountB: BN;
+  leftoverSwapInAmount: BN;
+  leftoverSwapOutAmount: BN;
+};
+
+export function decodeZapInResult(result: TransactionMetadata): ZapInResult {
+  const zapProgram = createZapProgram();
+  return zapProgram.coder.types.decode(
+    "zapInResult",
+    Buffer.from(result.returnData().data())
+  );
+}
+
+export type RebalanceDlmmPositionResult = {
+  amountXClaimed: BN;
+  amountYClaimed: BN;
+  amountXWithdrawn: BN;
+  amountYWithdrawn: BN;
+  swapInAmount: BN;
+  swapOutAmount: BN;
+  amountXDeposited: BN;
+  amountYDeposited: BN;
+  liquidityShare: BN;
+  remainingAmountX: BN;
+  remainingAmountY: BN;
+  leftoverSwapInAmount: BN;
+  leftoverSwapOutAmount: BN;
+};
+
+export function decodeRebalanceDlmmPositionResult(
+  result: TransactionMetadata
+): RebalanceDlmmPositionResult {
+  const zapProgram = createZapProgram();
+  return zapProgram.coder.types.decode(
+    "rebalanceDlmmPositionResult",
+    Buffer.from(result.returnData().data())
+  );
+}
+
+export type CompoundDammV2PositionResult = {
+  amountAClaimed: BN;
+  amountBClaimed: BN;
+  liquidityDelta: BN;
+  amountADeposited: BN;
+  amountBDeposited: BN;
+  swapInAmount: BN;
+  swapOutAmount: BN;
+  remainingAmountA: BN;
+  remainingAmountB: BN;
+};
+
+export function decodeCompoundDammV2PositionResult(
+  result: TransactionMetadata
+): CompoundDammV2PositionResult {
+  const zapProgram = createZapProgram();
+  return zapProgram.coder.types.decode(
+    "compoundDammV2PositionResult",
+    Buffer.from(result.returnData().data())
+  );
+}
+
+export type ZapInDammV2Quote = {
+  firstLiquidityDelta: BN;
+  tradeDirection: number;
+  swapInAmount: BN;
+  swapOutAmount: BN;
+  postSqrtPrice: BN;
+  liquidityDelta: BN;
+};
+
+export function decodeZapInDammV2Quote(
+  result: TransactionMetadata
+): ZapInDammV2Quote {
+  const zapProgram = createZapProgram();
+  return zapProgram.coder.types.decode(
+    "zapInDammV2Quote",
+    Buffer.from(result.returnData().data())
+  );
+}
+
+export async function quoteZapInDammv2(params: {
+  svm: LiteSVM;
+  pool: PublicKey;
+  amountA: BN;
+  amountB: BN;
+  hasReferral?: boolean;
+  maxRounds?: number;
+  dustThreshold?: BN;
+}): Promise<Transaction> {
+  const zapProgram = createZapProgram();
+  const {
+    svm,
+    pool,
+    amountA,
+    amountB,
+    hasReferral = false,
+    maxRounds = 1,
+    dustThreshold = new BN(0),
+  } = params;
+  const { tokenAMint, tokenBMint } = getDammV2Pool(svm, pool);
+
+  return await zapProgram.methods
+    .quoteZapInDammV2(amountA, amountB, hasReferral, maxRounds, dustThreshold)
+    .accountsPartial({
+      pool,
+      tokenAMint,
+      tokenBMint,
+    })
+    .transaction();
+}
+
 export async function zapInDammv2(params: {
   svm: LiteSVM;
   user: PublicKey;
@@ -31,6 +151,17 @@ export async function zapInDammv2(params: {
   positionNftAccount: PublicKey;
   preSqrtPrice: BN;
   maxSqrtPriceChangeBps: number;
+  minLiquidityDelta?: BN;
+  tokenAAmountThreshold?: BN;
+  tokenBAmountThreshold?: BN;
+  referralTokenAccount?: PublicKey;
+  lockMode?: any;
+  vesting?: PublicKey;
+  maxRounds?: number;
+  dustThreshold?: BN;
+  leftoverPolicy?: any;
+  leftoverTokenAAccount?: PublicKey;
+  leftoverTokenBAccount?: PublicKey;
 }): Promise<Transaction> {
   const zapProgram = createZapProgram();
 
@@ -42,6 +173,17 @@ export async function zapInDammv2(params: {
     positionNftAccount,
     preSqrtPrice,
     maxSqrtPriceChangeBps,
+    minLiquidityDelta = new BN(0),
+    tokenAAmountThreshold = U64_MAX,
+    tokenBAmountThreshold = U64_MAX,
+    referralTokenAccount = null,
+    lockMode = { unlocked: {} },
+    vesting = null,
+    maxRounds = 1,
+    dustThreshold = new BN(0),
+    leftoverPolicy = { keep: {} },
+    leftoverTokenAAccount = null,
+    leftoverTokenBAccount = null,
   } = params;
 
   const poolState = getDammV2Pool(svm, pool);
@@ -66,7 +208,17 @@ export async function zapInDammv2(params: {
   );
 
   return await zapProgram.methods
-    .zapInDammV2(preSqrtPrice, maxSqrtPriceChangeBps)
+    .zapInDammV2(
+      preSqrtPrice,
+      maxSqrtPriceChangeBps,
+      minLiquidityDelta,
+      tokenAAmountThreshold,
+      tokenBAmountThreshold,
+      lockMode,
+      maxRounds,
+      dustThreshold,
+      leftoverPolicy
+    )
     .accountsPartial({
       ledger: deriveLedgerAccount(user),
       pool,
@@ -84,6 +236,12 @@ export async function zapInDammv2(params: {
       tokenBProgram,
       dammProgram: DAMM_V2_PROGRAM_ID,
       dammEventAuthority: deriveDammV2EventAuthority(),
+      referralTokenAccount,
+      vesting,
+      payer: vesting ? user : null,
+      systemProgram: vesting ? SystemProgram.programId : null,
+      leftoverTokenAAccount,
+      leftoverTokenBAccount,
     })
     .remainingAccounts([
       {
@@ -95,6 +253,150 @@ export async function zapInDammv2(params: {
     .transaction();
 }
 
+export async function compoundDammV2Position(params: {
+  svm: LiteSVM;
+  user: PublicKey;
+  pool: PublicKey;
+  position: PublicKey;
+  positionNftAccount: PublicKey;
+  preSqrtPrice: BN;
+  maxSqrtPriceChangeBps: number;
+  minLiquidityDelta?: BN;
+  referralTokenAccount?: PublicKey;
+  maxRounds?: number;
+  dustThreshold?: BN;
+}): Promise<Transaction> {
+  const zapProgram = createZapProgram();
+
+  const {
+    svm,
+    user,
+    pool,
+    position,
+    positionNftAccount,
+    preSqrtPrice,
+    maxSqrtPriceChangeBps,
+    minLiquidityDelta = new BN(0),
+    referralTokenAccount = null,
+    maxRounds = 1,
+    dustThreshold = new BN(0),
+  } = params;
+
+  const poolState = getDammV2Pool(svm, pool);
+  const { tokenAVault, tokenBVault, tokenAMint, tokenBMint } = poolState;
+
+  const tokenAProgram = svm.getAccount(poolState.tokenAMint).owner;
+
+  const tokenBProgram = svm.getAccount(poolState.tokenBMint).owner;
+
+  const tokenAAccount = getAssociatedTokenAddressSync(
+    tokenAMint,
+    user,
+    true,
+    tokenAProgram
+  );
+
+  const tokenBAccount = getAssociatedTokenAddressSync(
+    tokenBMint,
+    user,
+    true,
+    tokenBProgram
+  );
+
+  return await zapProgram.methods
+    .compoundDammV2Position(
+      preSqrtPrice,
+      maxSqrtPriceChangeBps,
+      minLiquidityDelta,
+      maxRounds,
+      dustThreshold
+    )
+    .accountsPartial({
+      pool,
+      poolAuthority: deriveDammV2PoolAuthority(),
+      position,
+      tokenAAccount,
+      tokenBAccount,
+      tokenAVault,
+      tokenBVault,
+      tokenAMint,
+      tokenBMint,
+      positionNftAccount,
+      owner: user,
+      tokenAProgram,
+      tokenBProgram,
+      dammProgram: DAMM_V2_PROGRAM_ID,
+      dammEventAuthority: deriveDammV2EventAuthority(),
+      referralTokenAccount,
+    })
+    .remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: SYSVAR_INSTRUCTIONS_PUBKEY,
+      },
+    ])
+    .transaction();
+}
+
+export type ZapInDlmmQuote = {
+  activeId: number;
+  x0: BN;
+  y0: BN;
+  deltaX: BN;
+  deltaY: BN;
+  bitFlag: number;
+  bidSideStartBinId: number;
+  amountYInBins: BN[];
+  askSideStartBinId: number;
+  amountXInBins: BN[];
+};
+
+export function decodeZapInDlmmQuote(
+  result: TransactionMetadata
+): ZapInDlmmQuote {
+  const zapProgram = createZapProgram();
+  return zapProgram.coder.types.decode(
+    "zapInDlmmQuote",
+    Buffer.from(result.returnData().data())
+  );
+}
+
+export async function quoteZapInDlmm(params: {
+  lbPair: PublicKey;
+  amountX: BN;
+  amountY: BN;
+  minDeltaId: number;
+  maxDeltaId: number;
+  favorXInActiveId: boolean;
+  strategy: any;
+}): Promise<Transaction> {
+  const program = createZapProgram();
+  const {
+    lbPair,
+    amountX,
+    amountY,
+    minDeltaId,
+    maxDeltaId,
+    favorXInActiveId,
+    strategy,
+  } = params;
+
+  return await program.methods
+    .quoteZapInDlmm(
+      amountX,
+      amountY,
+      minDeltaId,
+      maxDeltaId,
+      favorXInActiveId,
+      strategy
+    )
+    .accountsPartial({
+      lbPair,
+    })
+    .transaction();
+}
+
 export async function zapInDlmmforInitializedPosition(params: {
   svm: LiteSVM;
   owner: PublicKey;
@@ -109,6 +411,18 @@ export async function zapInDlmmforInitializedPosition(params: {
   remainingAccountInfo: any;
   binArrays: AccountMeta[];
   binArrayBitmapExtension: PublicKey;
+  // create the bitmap extension in zap in if it doesn't exist yet
+  initializeBinArrayBitmapExtension?: boolean;
+  leftoverPolicy?: any;
+  leftoverTokenXAccount?: PublicKey;
+  leftoverTokenYAccount?: PublicKey;
+  singleTokenSwap?: boolean;
+  shouldClaimFee?: boolean;
+  shouldClaimReward?: boolean;
+  compound?: boolean;
+  shrinkMode?: number;
+  priceRange?: any;
+  maxPriceDeviationBps?: number;
 }): Promise<Transaction> {
   const program = createZapProgram();
 
@@ -126,10 +440,25 @@ export async function zapInDlmmforInitializedPosition(params: {
     remainingAccountInfo,
     binArrayBitmapExtension,
     binArrays,
+    initializeBinArrayBitmapExtension = false,
+    leftoverPolicy = { keep: {} },
+    leftoverTokenXAccount = null,
+    leftoverTokenYAccount = null,
+    singleTokenSwap = false,
+    shouldClaimFee = false,
+    shouldClaimReward = false,
+    compound = false,
+    shrinkMode = 0,
+    priceRange = null,
+    maxPriceDeviationBps = null,
   } = params;
 
   const lbPairState = getLbPairState(svm, lbPair);
-  const { tokenXMint, tokenYMint, reserveX, reserveY } = lbPairState;
+  const { tokenXMint, tokenYMint, reserveX, reserveY, oracle } = lbPairState;
+  const isSwapRequired =
+    singleTokenSwap ||
+    "swapToTokenA" in leftoverPolicy ||
+    "swapToTokenB" in leftoverPolicy;
 
   const tokenXProgram = svm.getAccount(tokenXMint).owner;
   const tokenYProgram = svm.getAccount(tokenYMint).owner;
@@ -158,15 +487,24 @@ export async function zapInDlmmforInitializedPosition(params: {
       maxActiveBinSlippage,
       favorXInActiveId,
       strategy,
-      remainingAccountInfo
+      remainingAccountInfo,
+      leftoverPolicy,
+      singleTokenSwap,
+      shouldClaimFee,
+      shouldClaimReward,
+      compound,
+      shrinkMode,
+      priceRange,
+      maxPriceDeviationBps
     )
     .accountsPartial({
       ledger: deriveLedgerAccount(owner),
       lbPair,
       position,
-      binArrayBitmapExtension: binArrayBitmapExtensionState
-        ? binArrayBitmapExtension
-        : null,
+      binArrayBitmapExtension:
+        binArrayBitmapExtensionState || initializeBinArrayBitmapExtension
+          ? binArrayBitmapExtension
+          : null,
       userTokenX,
       userTokenY,
       reserveX,
@@ -181,11 +519,133 @@ export async function zapInDlmmforInitializedPosition(params: {
       memoProgram: MEMO_PROGRAM_ID,
       dlmmEventAuthority: deriveDlmmEventAuthority(),
       systemProgram: SystemProgram.programId,
+      rent: initializeBinArrayBitmapExtension ? SYSVAR_RENT_PUBKEY : null,
+      oracle: isSwapRequired ? oracle : null,
+      leftoverTokenXAccount,
+      leftoverTokenYAccount,
     })
     .remainingAccounts(binArrays)
     .transaction();
 }
 
+export async function zapInDlmmForMultiplePositions(params: {
+  svm: LiteSVM;
+  owner: PublicKey;
+  lbPair: PublicKey;
+  positions: PublicKey[];
+  activeId: number;
+  minDeltaId: number;
+  maxDeltaId: number;
+  maxActiveBinSlippage: number;
+  favorXInActiveId: boolean;
+  strategy: any;
+  remainingAccountInfo: any;
+  binArrays: AccountMeta[];
+  binArrayBitmapExtension: PublicKey;
+  // create the bitmap extension in zap in if it doesn't exist yet
+  initializeBinArrayBitmapExtension?: boolean;
+  maxPriceDeviationBps?: number;
+  leftoverPolicy?: any;
+  leftoverTokenXAccount?: PublicKey;
+  leftoverTokenYAccount?: PublicKey;
+}): Promise<Transaction> {
+  const program = createZapProgram();
+
+  const {
+    svm,
+    owner,
+    lbPair,
+    positions,
+    activeId,
+    minDeltaId,
+    maxDeltaId,
+    maxActiveBinSlippage,
+    favorXInActiveId,
+    strategy,
+    remainingAccountInfo,
+    binArrayBitmapExtension,
+    binArrays,
+    initializeBinArrayBitmapExtension = false,
+    maxPriceDeviationBps = null,
+    leftoverPolicy = { keep: {} },
+    leftoverTokenXAccount = null,
+    leftoverTokenYAccount = null,
+  } = params;
+
+  const lbPairState = getLbPairState(svm, lbPair);
+  const { tokenXMint, tokenYMint, reserveX, reserveY, oracle } = lbPairState;
+  const isSwapRequired =
+    "swapToTokenA" in leftoverPolicy || "swapToTokenB" in leftoverPolicy;
+
+  const tokenXProgram = svm.getAccount(tokenXMint).owner;
+  const tokenYProgram = svm.getAccount(tokenYMint).owner;
+
+  const userTokenX = getAssociatedTokenAddressSync(
+    tokenXMint,
+    owner,
+    true,
+    tokenXProgram
+  );
+
+  const userTokenY = getAssociatedTokenAddressSync(
+    tokenYMint,
+    owner,
+    true,
+    tokenYProgram
+  );
+
+  let binArrayBitmapExtensionState = svm.getAccount(binArrayBitmapExtension);
+
+  // positions go before bin arrays in remaining accounts
+  const positionAccounts: AccountMeta[] = positions.map((pubkey) => ({
+    pubkey,
+    isSigner: false,
+    isWritable: true,
+  }));
+
+  return await program.methods
+    .zapInDlmmForMultiplePositions(
+      activeId,
+      minDeltaId,
+      maxDeltaId,
+      maxActiveBinSlippage,
+      favorXInActiveId,
+      strategy,
+      remainingAccountInfo,
+      positions.length,
+      maxPriceDeviationBps,
+      leftoverPolicy
+    )
+    .accountsPartial({
+      ledger: deriveLedgerAccount(owner),
+      lbPair,
+      binArrayBitmapExtension:
+        binArrayBitmapExtensionState || initializeBinArrayBitmapExtension
+          ? binArrayBitmapExtension
+          : null,
+      userTokenX,
+      userTokenY,
+      reserveX,
+      reserveY,
+      tokenXMint,
+      tokenYMint,
+      tokenXProgram,
+      tokenYProgram,
+      dlmmProgram: DLMM_PROGRAM_ID_LOCAL,
+      owner,
+      rentPayer: owner,
+      memoProgram: MEMO_PROGRAM_ID,
+      dlmmEventAuthority: deriveDlmmEventAuthority(),
+      systemProgram: SystemProgram.programId,
+      oracle: isSwapRequired ? oracle : null,
+      leftoverTokenXAccount,
+      leftoverTokenYAccount,
+      rent: initializeBinArrayBitmapExtension ? SYSVAR_RENT_PUBKEY : null,
+    })
+    .remainingAccounts([...positionAccounts, ...binArrays])
+    .transaction();
+}
+
 export async function zapInDlmmforUnInitializedPosition(params: {
   svm: LiteSVM;
   owner: PublicKey;
@@ -200,6 +660,15 @@ export async function zapInDlmmforUnInitializedPosition(params: {
   remainingAccountInfo: any;
   binArrays: AccountMeta[];
   binArrayBitmapExtension: PublicKey;
+  // create the bitmap extension in zap in if it doesn't exist yet
+  initializeBinArrayBitmapExtension?: boolean;
+  leftoverPolicy?: any;
+  leftoverTokenXAccount?: PublicKey;
+  leftoverTokenYAccount?: PublicKey;
+  singleTokenSwap?: boolean;
+  priceRange?: any;
+  maxPriceDeviationBps?: number;
+  shrinkMode?: number;
 }): Promise<Transaction> {
   const program = createZapProgram();
 
@@ -217,10 +686,22 @@ export async function zapInDlmmforUnInitializedPosition(params: {
     remainingAccountInfo,
     binArrayBitmapExtension,
     binArrays,
+    initializeBinArrayBitmapExtension = false,
+    leftoverPolicy = { keep: {} },
+    leftoverTokenXAccount = null,
+    leftoverTokenYAccount = null,
+    singleTokenSwap = false,
+    priceRange = null,
+    maxPriceDeviationBps = null,
+    shrinkMode = 0,
   } = params;
 
   const lbPairState = getLbPairState(svm, lbPair);
-  const { tokenXMint, tokenYMint, reserveX, reserveY } = lbPairState;
+  const { tokenXMint, tokenYMint, reserveX, reserveY, oracle } = lbPairState;
+  const isSwapRequired =
+    singleTokenSwap ||
+    "swapToTokenA" in leftoverPolicy ||
+    "swapToTokenB" in leftoverPolicy;
 
   const tokenXProgram = svm.getAccount(tokenXMint).owner;
   const tokenYProgram = svm.getAccount(tokenYMint).owner;
@@ -249,15 +730,141 @@ export async function zapInDlmmforUnInitializedPosition(params: {
       maxActiveBinSlippage,
       favorXInActiveId,
       strategy,
-      remainingAccountInfo
+      remainingAccountInfo,
+      leftoverPolicy,
+      singleTokenSwap,
+      priceRange,
+      maxPriceDeviationBps,
+      shrinkMode
     )
     .accountsPartial({
       ledger: deriveLedgerAccount(owner),
       lbPair,
       position,
-      binArrayBitmapExtension: binArrayBitmapExtensionState
-        ? binArrayBitmapExtension
-        : null,
+      binArrayBitmapExtension:
+        binArrayBitmapExtensionState || initializeBinArrayBitmapExtension
+          ? binArrayBitmapExtension
+          : null,
+      userTokenX,
+      userTokenY,
+      reserveX,
+      reserveY,
+      tokenXMint,
+      tokenYMint,
+      tokenXProgram,
+      tokenYProgram,
+      dlmmProgram: DLMM_PROGRAM_ID_LOCAL,
+      owner,
+      rentPayer: owner,
+      memoProgram: MEMO_PROGRAM_ID,
+      dlmmEventAuthority: deriveDlmmEventAuthority(),
+      systemProgram: SystemProgram.programId,
+      rent: initializeBinArrayBitmapExtension ? SYSVAR_RENT_PUBKEY : null,
+      oracle: isSwapRequired ? oracle : null,
+      leftoverTokenXAccount,
+      leftoverTokenYAccount,
+    })
+    .remainingAccounts(binArrays)
+    .transaction();
+}
+
+export async function rebalanceDlmmPosition(params: {
+  svm: LiteSVM;
+  owner: PublicKey;
+  lbPair: PublicKey;
+  position: PublicKey;
+  activeId: number;
+  minDeltaId: number;
+  maxDeltaId: number;
+  maxActiveBinSlippage: number;
+  favorXInActiveId: boolean;
+  strategy: any;
+  remainingAccountInfo: any;
+  binArrays: AccountMeta[];
+  binArrayBitmapExtension: PublicKey;
+  // create the bitmap extension in rebalance if it doesn't exist yet
+  initializeBinArrayBitmapExtension?: boolean;
+  shouldClaimFee?: boolean;
+  shouldClaimReward?: boolean;
+  compound?: boolean;
+  maxPriceDeviationBps?: number;
+  shrinkMode?: number;
+  leftoverPolicy?: any;
+  leftoverTokenXAccount?: PublicKey;
+  leftoverTokenYAccount?: PublicKey;
+}): Promise<Transaction> {
+  const program = createZapProgram();
+
+  const {
+    svm,
+    owner,
+    lbPair,
+    position,
+    activeId,
+    minDeltaId,
+    maxDeltaId,
+    maxActiveBinSlippage,
+    favorXInActiveId,
+    strategy,
+    remainingAccountInfo,
+    binArrayBitmapExtension,
+    binArrays,
+    initializeBinArrayBitmapExtension = false,
+    shouldClaimFee = false,
+    shouldClaimReward = false,
+    compound = false,
+    maxPriceDeviationBps = null,
+    shrinkMode = 0,
+    leftoverPolicy = { keep: {} },
+    leftoverTokenXAccount = null,
+    leftoverTokenYAccount = null,
+  } = params;
+
+  const lbPairState = getLbPairState(svm, lbPair);
+  const { tokenXMint, tokenYMint, reserveX, reserveY, oracle } = lbPairState;
+
+  const tokenXProgram = svm.getAccount(tokenXMint).owner;
+  const tokenYProgram = svm.getAccount(tokenYMint).owner;
+
+  const userTokenX = getAssociatedTokenAddressSync(
+    tokenXMint,
+    owner,
+    true,
+    tokenXProgram
+  );
+
+  const userTokenY = getAssociatedTokenAddressSync(
+    tokenYMint,
+    owner,
+    true,
+    tokenYProgram
+  );
+
+  let binArrayBitmapExtensionState = svm.getAccount(binArrayBitmapExtension);
+
+  return await program.methods
+    .rebalanceDlmmPosition(
+      activeId,
+      minDeltaId,
+      maxDeltaId,
+      maxActiveBinSlippage,
+      favorXInActiveId,
+      strategy,
+      remainingAccountInfo,
+      shouldClaimFee,
+      shouldClaimReward,
+      compound,
+      maxPriceDeviationBps,
+      shrinkMode,
+      leftoverPolicy
+    )
+    .accountsPartial({
+      lbPair,
+      position,
+      binArrayBitmapExtension:
+        binArrayBitmapExtensionState || initializeBinArrayBitmapExtension
+          ? binArrayBitmapExtension
+          : null,
       userTokenX,
       userTokenY,
       reserveX,
@@ -272,6 +879,10 @@ export async function zapInDlmmforUnInitializedPosition(params: {
       memoProgram: MEMO_PROGRAM_ID,
       dlmmEventAuthority: deriveDlmmEventAuthority(),
       systemProgram: SystemProgram.programId,
+      oracle,
+      leftoverTokenXAccount,
+      leftoverTokenYAccount,
+      rent: initializeBinArrayBitmapExtension ? SYSVAR_RENT_PUBKEY : null,
     })
     .remainingAccounts(binArrays)
     .transaction();
@@ -1,6 +1,7 @@
 import {
   FailedTransactionMetadata,
   LiteSVM,
+  SimulatedTransactionInfo,
   TransactionMetadata,
 } from "litesvm";
 import {
@@ -19,11 +20,18 @@ import {
   updateLedgerBalanceAfterSwap,
   getTokenBalance,
   zapInDammv2,
+  decodeZapInResult,
+  quoteZapInDammv2,
+  decodeZapInDammV2Quote,
+  compoundDammV2Position,
+  decodeCompoundDammV2PositionResult,
   closeLedgerAccount,
   warpSlotBy,
   TOKEN_DECIMALS,
   U64_MAX,
   U32_MAX,
+  expectThrowsErrorCode,
+  getProgramErrorCodeHexString,
 } from "../common";
 
 import ZapIDL from "../../target/idl/zap.json";
@@ -489,6 +497,660 @@ describe("Zap In damm V2", () => {
     }
     expect(result).instanceOf(TransactionMetadata);
   });
+
+  it("single token zap in", async () => {
+    const pool = await createDammV2Pool(svm, admin, tokenAMint, tokenBMint);
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    const poolState = getDammV2Pool(svm, pool);
+
+    const totalAmountA = new BN(LAMPORTS_PER_SOL);
+    const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+
+    // only fund token a, token b will come from the swap in zap in
+    const setLedgerBalanceTx = await setLedgerBalance(
+      user.publicKey,
+      totalAmountA,
+      true
+    );
+
+    const tokenAAccount = getAssociatedTokenAddressSync(
+      tokenAMint,
+      user.publicKey
+    );
+    const tokenBAccount = getAssociatedTokenAddressSync(
+      tokenBMint,
+      user.publicKey
+    );
+    const preTokenABalance = getTokenBalance(svm, tokenAAccount);
+    const preTokenBBalance = getTokenBalance(svm, tokenBAccount);
+
+    const zapInTx = await zapInDammv2({
+      svm,
+      user: user.publicKey,
+      pool,
+      position,
+      positionNftAccount,
+      preSqrtPrice: poolState.sqrtPrice,
+      maxSqrtPriceChangeBps: 5000,
+    });
+
+    const closeLedgerTx = await closeLedgerAccount(user.publicKey);
+
+    const finalTx = new Transaction()
+      .add(initializeLedgerTx)
+      .add(setLedgerBalanceTx)
+      .add(zapInTx)
+      .add(closeLedgerTx);
+
+    finalTx.recentBlockhash = svm.latestBlockhash();
+    finalTx.sign(user);
+
+    const result = svm.sendTransaction(finalTx);
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    const positionState = getDammV2Position(svm, position);
+    expect(positionState.unlockedLiquidity.gt(new BN(0))).to.be.true;
+
+    const postTokenABalance = getTokenBalance(svm, tokenAAccount);
+    const postTokenBBalance = getTokenBalance(svm, tokenBAccount);
+
+    const usedTokenA = preTokenABalance.sub(postTokenABalance);
+    expect(usedTokenA.lte(totalAmountA)).to.be.true;
+    // token b from the swap should be deposited back
+    expect(
+      postTokenBBalance.sub(preTokenBBalance).toNumber() <
+        0.0001 * 10 ** TOKEN_DECIMALS
+    ).to.be.true;
+  });
+
+  it("quote zap in matches zap in result", async () => {
+    const pool = await createDammV2Pool(svm, admin, tokenAMint, tokenBMint);
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    const poolState = getDammV2Pool(svm, pool);
+    const totalAmountA = new BN(LAMPORTS_PER_SOL);
+
+    const quoteTx = await quoteZapInDammv2({
+      svm,
+      pool,
+      amountA: totalAmountA,
+      amountB: new BN(0),
+    });
+    quoteTx.recentBlockhash = svm.latestBlockhash();
+    quoteTx.feePayer = user.publicKey;
+    quoteTx.sign(user);
+
+    const simulateResult = svm.simulateTransaction(quoteTx);
+    if (simulateResult instanceof FailedTransactionMetadata) {
+      console.log(simulateResult.meta().logs());
+    }
+    expect(simulateResult).instanceOf(SimulatedTransactionInfo);
+    const quote = decodeZapInDammV2Quote(
+      (simulateResult as SimulatedTransactionInfo).meta()
+    );
+
+    const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+    const setLedgerBalanceTx = await setLedgerBalance(
+      user.publicKey,
+      totalAmountA,
+      true
+    );
+    const zapInTx = await zapInDammv2({
+      svm,
+      user: user.publicKey,
+      pool,
+      position,
+      positionNftAccount,
+      preSqrtPrice: poolState.sqrtPrice,
+      maxSqrtPriceChangeBps: 5000,
+    });
+    const closeLedgerTx = await closeLedgerAccount(user.publicKey);
+
+    const finalTx = new Transaction()
+      .add(initializeLedgerTx)
+      .add(setLedgerBalanceTx)
+      .add(zapInTx)
+      .add(closeLedgerTx);
+
+    finalTx.recentBlockhash = svm.latestBlockhash();
+    finalTx.sign(user);
+
+    const result = svm.sendTransaction(finalTx);
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    const zapInResult = decodeZapInResult(result as TransactionMetadata);
+    expect(zapInResult.swapInAmount.eq(quote.swapInAmount)).to.be.true;
+    expect(zapInResult.swapOutAmount.eq(quote.swapOutAmount)).to.be.true;
+    expect(zapInResult.liquidityDelta.eq(quote.liquidityDelta)).to.be.true;
+    expect(getDammV2Pool(svm, pool).sqrtPrice.eq(quote.postSqrtPrice)).to.be
+      .true;
+  });
+
+  it("single token zap in with multiple rounds", async () => {
+    const pool = await createDammV2Pool(svm, admin, tokenAMint, tokenBMint);
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    const poolState = getDammV2Pool(svm, pool);
+    const totalAmountA = new BN(LAMPORTS_PER_SOL);
+
+    const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+    const setLedgerBalanceTx = await setLedgerBalance(
+      user.publicKey,
+      totalAmountA,
+      true
+    );
+    const zapInTx = await zapInDammv2({
+      svm,
+      user: user.publicKey,
+      pool,
+      position,
+      positionNftAccount,
+      preSqrtPrice: poolState.sqrtPrice,
+      maxSqrtPriceChangeBps: 5000,
+      maxRounds: 3,
+      dustThreshold: new BN(1000),
+    });
+
+    const finalTx = new Transaction()
+      .add(initializeLedgerTx)
+      .add(setLedgerBalanceTx)
+      .add(zapInTx);
+
+    finalTx.recentBlockhash = svm.latestBlockhash();
+    finalTx.sign(user);
+
+    const result = svm.sendTransaction(finalTx);
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    const zapInResult = decodeZapInResult(result as TransactionMetadata);
+    expect(zapInResult.liquidityDelta.gt(new BN(0))).to.be.true;
+    // remaining token a is less than 0.1% of total amount
+    expect(zapInResult.remainingAmountA.muln(1000).lt(totalAmountA)).to.be
+      .true;
+  });
+
+  it("quote zap in matches zap in result with multiple rounds", async () => {
+    // dynamic fee changes between rounds, quote must follow it
+    const pool = await createDammV2Pool(
+      svm,
+      admin,
+      tokenAMint,
+      tokenBMint,
+      undefined,
+      undefined,
+      undefined,
+      {
+        binStep: 1,
+        binStepU128: new BN("1844674407370955"),
+        filterPeriod: 10,
+        decayPeriod: 120,
+        reductionFactor: 5000,
+        maxVolatilityAccumulator: 14460000,
+        variableFeeControl: 1500000,
+      }
+    );
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    const poolState = getDammV2Pool(svm, pool);
+    const totalAmountA = new BN(LAMPORTS_PER_SOL);
+    const maxRounds = 3;
+    const dustThreshold = new BN(1000);
+
+    const quoteTx = await quoteZapInDammv2({
+      svm,
+      pool,
+      amountA: totalAmountA,
+      amountB: new BN(0),
+      maxRounds,
+      dustThreshold,
+    });
+    quoteTx.recentBlockhash = svm.latestBlockhash();
+    quoteTx.feePayer = user.publicKey;
+    quoteTx.sign(user);
+
+    const simulateResult = svm.simulateTransaction(quoteTx);
+    if (simulateResult instanceof FailedTransactionMetadata) {
+      console.log(simulateResult.meta().logs());
+    }
+    expect(simulateResult).instanceOf(SimulatedTransactionInfo);
+    const quote = decodeZapInDammV2Quote(
+      (simulateResult as SimulatedTransactionInfo).meta()
+    );
+
+    const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+    const setLedgerBalanceTx = await setLedgerBalance(
+      user.publicKey,
+      totalAmountA,
+      true
+    );
+    const zapInTx = await zapInDammv2({
+      svm,
+      user: user.publicKey,
+      pool,
+      position,
+      positionNftAccount,
+      preSqrtPrice: poolState.sqrtPrice,
+      maxSqrtPriceChangeBps: 5000,
+      maxRounds,
+      dustThreshold,
+    });
+    const closeLedgerTx = await closeLedgerAccount(user.publicKey);
+
+    const finalTx = new Transaction()
+      .add(initializeLedgerTx)
+      .add(setLedgerBalanceTx)
+      .add(zapInTx)
+      .add(closeLedgerTx);
+
+    finalTx.recentBlockhash = svm.latestBlockhash();
+    finalTx.sign(user);
+
+    const result = svm.sendTransaction(finalTx);
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    const zapInResult = decodeZapInResult(result as TransactionMetadata);
+    expect(zapInResult.swapInAmount.eq(quote.swapInAmount)).to.be.true;
+    expect(zapInResult.swapOutAmount.eq(quote.swapOutAmount)).to.be.true;
+    expect(zapInResult.liquidityDelta.eq(quote.liquidityDelta)).to.be.true;
+    expect(getDammV2Pool(svm, pool).sqrtPrice.eq(quote.postSqrtPrice)).to.be
+      .true;
+  });
+
+  it("zap in fails when added liquidity is below minimum", async () => {
+    const pool = await createDammV2Pool(svm, admin, tokenAMint, tokenBMint);
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    const poolState = getDammV2Pool(svm, pool);
+
+    const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+    const setLedgerBalanceTx = await setLedgerBalance(
+      user.publicKey,
+      new BN(LAMPORTS_PER_SOL),
+      true
+    );
+
+    const zapInTx = await zapInDammv2({
+      svm,
+      user: user.publicKey,
+      pool,
+      position,
+      positionNftAccount,
+      preSqrtPrice: poolState.sqrtPrice,
+      maxSqrtPriceChangeBps: 5000,
+      minLiquidityDelta: poolState.liquidity,
+    });
+
+    const finalTx = new Transaction()
+      .add(initializeLedgerTx)
+      .add(setLedgerBalanceTx)
+      .add(zapInTx);
+
+    finalTx.recentBlockhash = svm.latestBlockhash();
+    finalTx.sign(user);
+
+    const result = svm.sendTransaction(finalTx);
+    expectThrowsErrorCode(
+      result,
+      getProgramErrorCodeHexString("ExceededSlippage")
+    );
+  });
+
+  it("zap in and permanently lock position", async () => {
+    const pool = await createDammV2Pool(svm, admin, tokenAMint, tokenBMint);
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    const poolState = getDammV2Pool(svm, pool);
+
+    const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+    const setLedgerBalanceTx = await setLedgerBalance(
+      user.publicKey,
+      new BN(LAMPORTS_PER_SOL),
+      true
+    );
+
+    const zapInTx = await zapInDammv2({
+      svm,
+      user: user.publicKey,
+      pool,
+      position,
+      positionNftAccount,
+      preSqrtPrice: poolState.sqrtPrice,
+      maxSqrtPriceChangeBps: 5000,
+      lockMode: { permanent: {} },
+    });
+
+    const closeLedgerTx = await closeLedgerAccount(user.publicKey);
+
+    const finalTx = new Transaction()
+      .add(initializeLedgerTx)
+      .add(setLedgerBalanceTx)
+      .add(zapInTx)
+      .add(closeLedgerTx);
+
+    finalTx.recentBlockhash = svm.latestBlockhash();
+    finalTx.sign(user);
+
+    const result = svm.sendTransaction(finalTx);
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    const positionState = getDammV2Position(svm, position);
+    expect(positionState.unlockedLiquidity.isZero()).to.be.true;
+    expect(positionState.permanentLockedLiquidity.gt(new BN(0))).to.be.true;
+
+    // zap in result is set as return data
+    const zapInResult = decodeZapInResult(result as TransactionMetadata);
+    expect(
+      zapInResult.liquidityDelta.eq(positionState.permanentLockedLiquidity)
+    ).to.be.true;
+    expect(zapInResult.swapInAmount.gt(new BN(0))).to.be.true;
+  });
+
+  it("zap in and transfer leftover", async () => {
+    const pool = await createDammV2Pool(svm, admin, tokenAMint, tokenBMint);
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    const poolState = getDammV2Pool(svm, pool);
+
+    const adminTokenAAccount = getAssociatedTokenAddressSync(
+      tokenAMint,
+      admin.publicKey
+    );
+    const adminTokenBAccount = getAssociatedTokenAddressSync(
+      tokenBMint,
+      admin.publicKey
+    );
+
+    const buildTx = async (leftoverPolicy: any) => {
+      const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+      const setLedgerBalanceTx = await setLedgerBalance(
+        user.publicKey,
+        new BN(LAMPORTS_PER_SOL),
+        true
+      );
+      const zapInTx = await zapInDammv2({
+        svm,
+        user: user.publicKey,
+        pool,
+        position,
+        positionNftAccount,
+        preSqrtPrice: poolState.sqrtPrice,
+        maxSqrtPriceChangeBps: 5000,
+        leftoverPolicy,
+        leftoverTokenAAccount: adminTokenAAccount,
+        leftoverTokenBAccount: adminTokenBAccount,
+      });
+      const closeLedgerTx = await closeLedgerAccount(user.publicKey);
+      const tx = new Transaction()
+        .add(initializeLedgerTx)
+        .add(setLedgerBalanceTx)
+        .add(zapInTx)
+        .add(closeLedgerTx);
+      tx.recentBlockhash = svm.latestBlockhash();
+      tx.sign(user);
+      return tx;
+    };
+
+    // leftover if we keep it in the ledger
+    const simulation = svm.simulateTransaction(await buildTx({ keep: {} }));
+    expect(simulation).instanceOf(SimulatedTransactionInfo);
+    const keepResult = decodeZapInResult(
+      (simulation as SimulatedTransactionInfo).meta()
+    );
+
+    const preAdminTokenABalance = getTokenBalance(svm, adminTokenAAccount);
+    const preAdminTokenBBalance = getTokenBalance(svm, adminTokenBAccount);
+
+    const result = svm.sendTransaction(await buildTx({ transfer: {} }));
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    const zapInResult = decodeZapInResult(result as TransactionMetadata);
+    expect(zapInResult.remainingAmountA.isZero()).to.be.true;
+    expect(zapInResult.remainingAmountB.isZero()).to.be.true;
+
+    const postAdminTokenABalance = getTokenBalance(svm, adminTokenAAccount);
+    const postAdminTokenBBalance = getTokenBalance(svm, adminTokenBAccount);
+    expect(
+      postAdminTokenABalance
+        .sub(preAdminTokenABalance)
+        .eq(keepResult.remainingAmountA)
+    ).to.be.true;
+    expect(
+      postAdminTokenBBalance
+        .sub(preAdminTokenBBalance)
+        .eq(keepResult.remainingAmountB)
+    ).to.be.true;
+  });
+
+  it("zap in and swap leftover", async () => {
+    const pool = await createDammV2Pool(svm, admin, tokenAMint, tokenBMint);
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    const poolState = getDammV2Pool(svm, pool);
+
+    const buildTx = async (leftoverPolicy: any) => {
+      const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+      const setLedgerBalanceTx = await setLedgerBalance(
+        user.publicKey,
+        new BN(LAMPORTS_PER_SOL),
+        true
+      );
+      const zapInTx = await zapInDammv2({
+        svm,
+        user: user.publicKey,
+        pool,
+        position,
+        positionNftAccount,
+        preSqrtPrice: poolState.sqrtPrice,
+        maxSqrtPriceChangeBps: 5000,
+        leftoverPolicy,
+      });
+      const closeLedgerTx = await closeLedgerAccount(user.publicKey);
+      const tx = new Transaction()
+        .add(initializeLedgerTx)
+        .add(setLedgerBalanceTx)
+        .add(zapInTx)
+        .add(closeLedgerTx);
+      tx.recentBlockhash = svm.latestBlockhash();
+      tx.sign(user);
+      return tx;
+    };
+
+    const simulation = svm.simulateTransaction(await buildTx({ keep: {} }));
+    expect(simulation).instanceOf(SimulatedTransactionInfo);
+    const keepResult = decodeZapInResult(
+      (simulation as SimulatedTransactionInfo).meta()
+    );
+    expect(keepResult.leftoverSwapInAmount.isZero()).to.be.true;
+
+    // swap the side having leftover to the other side
+    const swapToTokenB = keepResult.remainingAmountA.gt(
+      keepResult.remainingAmountB
+    );
+    const result = svm.sendTransaction(
+      await buildTx(swapToTokenB ? { swapToTokenB: {} } : { swapToTokenA: {} })
+    );
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    // leftover swap is reported apart from the zap in swap
+    const zapInResult = decodeZapInResult(result as TransactionMetadata);
+    expect(zapInResult.swapInAmount.eq(keepResult.swapInAmount)).to.be.true;
+    expect(zapInResult.swapOutAmount.eq(keepResult.swapOutAmount)).to.be.true;
+    expect(
+      zapInResult.leftoverSwapInAmount.eq(
+        swapToTokenB ? keepResult.remainingAmountA : keepResult.remainingAmountB
+      )
+    ).to.be.true;
+    expect(zapInResult.leftoverSwapOutAmount.gtn(0)).to.be.true;
+    if (swapToTokenB) {
+      expect(zapInResult.remainingAmountA.isZero()).to.be.true;
+      expect(
+        zapInResult.remainingAmountB.eq(
+          keepResult.remainingAmountB.add(zapInResult.leftoverSwapOutAmount)
+        )
+      ).to.be.true;
+    } else {
+      expect(zapInResult.remainingAmountB.isZero()).to.be.true;
+      expect(
+        zapInResult.remainingAmountA.eq(
+          keepResult.remainingAmountA.add(zapInResult.leftoverSwapOutAmount)
+        )
+      ).to.be.true;
+    }
+  });
+
+  it("compound one-sided fees", async () => {
+    // pool collects fees only in token b
+    const pool = await createDammV2Pool(svm, admin, tokenAMint, tokenBMint);
+
+    const { position, positionNftAccount } = await createDammV2Position(
+      svm,
+      user,
+      pool
+    );
+
+    let poolState = getDammV2Pool(svm, pool);
+    const initializeLedgerTx = await initializeLedgerAccount(user.publicKey);
+    const setLedgerBalanceTx = await setLedgerBalance(
+      user.publicKey,
+      new BN(LAMPORTS_PER_SOL),
+      true
+    );
+    const zapInTx = await zapInDammv2({
+      svm,
+      user: user.publicKey,
+      pool,
+      position,
+      positionNftAccount,
+      preSqrtPrice: poolState.sqrtPrice,
+      maxSqrtPriceChangeBps: 5000,
+    });
+    const closeLedgerTx = await closeLedgerAccount(user.publicKey);
+    const zapInFlowTx = new Transaction()
+      .add(initializeLedgerTx)
+      .add(setLedgerBalanceTx)
+      .add(zapInTx)
+      .add(closeLedgerTx);
+    zapInFlowTx.recentBlockhash = svm.latestBlockhash();
+    zapInFlowTx.sign(user);
+    expect(svm.sendTransaction(zapInFlowTx)).instanceOf(TransactionMetadata);
+
+    const swapTx = await swap({
+      svm,
+      user: admin.publicKey,
+      pool,
+      amountIn: new BN(LAMPORTS_PER_SOL),
+      inputTokenMint: tokenAMint,
+      outputTokenMint: tokenBMint,
+    });
+    swapTx.recentBlockhash = svm.latestBlockhash();
+    swapTx.sign(admin);
+    expect(svm.sendTransaction(swapTx)).instanceOf(TransactionMetadata);
+
+    poolState = getDammV2Pool(svm, pool);
+    const buildTx = async (dustThreshold: BN) => {
+      const tx = await compoundDammV2Position({
+        svm,
+        user: user.publicKey,
+        pool,
+        position,
+        positionNftAccount,
+        preSqrtPrice: poolState.sqrtPrice,
+        maxSqrtPriceChangeBps: 5000,
+        dustThreshold,
+      });
+      tx.recentBlockhash = svm.latestBlockhash();
+      tx.sign(user);
+      return tx;
+    };
+
+    // fee below dust threshold is nothing swappable, compound is a no-op instead of failing
+    const simulation = svm.simulateTransaction(await buildTx(U64_MAX));
+    expect(simulation).instanceOf(SimulatedTransactionInfo);
+    const noOpResult = decodeCompoundDammV2PositionResult(
+      (simulation as SimulatedTransactionInfo).meta()
+    );
+    expect(noOpResult.amountAClaimed.isZero()).to.be.true;
+    expect(noOpResult.amountBClaimed.gtn(0)).to.be.true;
+    expect(noOpResult.liquidityDelta.isZero()).to.be.true;
+    expect(noOpResult.remainingAmountB.eq(noOpResult.amountBClaimed)).to.be
+      .true;
+
+    const result = svm.sendTransaction(await buildTx(new BN(0)));
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    const compoundResult = decodeCompoundDammV2PositionResult(
+      result as TransactionMetadata
+    );
+    expect(compoundResult.amountAClaimed.isZero()).to.be.true;
+    expect(compoundResult.amountBClaimed.gtn(0)).to.be.true;
+    expect(compoundResult.swapInAmount.lte(compoundResult.amountBClaimed)).to
+      .be.true;
+  });
 });
 
 async function zapInFullFlow(params: {
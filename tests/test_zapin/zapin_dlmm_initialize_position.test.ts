@@ -1,6 +1,7 @@
 import {
   FailedTransactionMetadata,
   LiteSVM,
+  SimulatedTransactionInfo,
   TransactionMetadata,
 } from "litesvm";
 import {
@@ -19,8 +20,14 @@ import {
   getTokenBalance,
   updateLedgerBalanceAfterSwap,
   zapInDlmmforInitializedPosition,
+  quoteZapInDlmm,
+  decodeZapInDlmmQuote,
   closeLedgerAccount,
   U64_MAX,
+  rebalanceDlmmPosition,
+  decodeRebalanceDlmmPositionResult,
+  zapInDlmmForMultiplePositions,
+  decodeZapInResult,
 } from "../common";
 import babar from "babar";
 import { expect } from "chai";
@@ -43,6 +50,7 @@ import {
   SET_COMPUTE_UNIT_LIMIT_IX,
   initializeBinArrayBitmapExtension,
   getBinArrayAccountMetaByBinRange,
+  getDlmmPositionState,
 } from "../common/dlmm";
 import { BN } from "@coral-xyz/anchor";
 import { deriveBinArrayBitmapExtension } from "../common/pda";
@@ -173,6 +181,46 @@ describe("Zapin DLMM with initialize position", () => {
     });
   });
 
+  it("Quote zapin dlmm bin distribution", async () => {
+    const amountX = new BN(LAMPORTS_PER_SOL);
+    const amountY = new BN(LAMPORTS_PER_SOL);
+
+    const quoteTx = await quoteZapInDlmm({
+      lbPair,
+      amountX,
+      amountY,
+      minDeltaId,
+      maxDeltaId,
+      favorXInActiveId: false,
+      strategy: StrategyType.Curve,
+    });
+    quoteTx.recentBlockhash = svm.latestBlockhash();
+    quoteTx.feePayer = user.publicKey;
+    quoteTx.sign(user);
+
+    const result = svm.simulateTransaction(quoteTx);
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(SimulatedTransactionInfo);
+    const quote = decodeZapInDlmmQuote(
+      (result as SimulatedTransactionInfo).meta()
+    );
+
+    const lbPairState = getLbPairState(svm, lbPair);
+    expect(quote.activeId).eq(lbPairState.activeId);
+    expect(quote.bidSideStartBinId).eq(lbPairState.activeId + minDeltaId);
+    expect(quote.askSideStartBinId).eq(lbPairState.activeId + 1);
+    expect(quote.amountYInBins.length + quote.amountXInBins.length).eq(
+      maxDeltaId - minDeltaId + 1
+    );
+
+    const totalX = quote.amountXInBins.reduce((a, b) => a.add(b), new BN(0));
+    const totalY = quote.amountYInBins.reduce((a, b) => a.add(b), new BN(0));
+    expect(totalX.lte(amountX)).to.be.true;
+    expect(totalY.lte(amountY)).to.be.true;
+  });
+
   it("Zapin dlmm with Bidask strategy", async () => {
     await initializeBinArrayBitmapExtension(svm, lbPair, admin);
     const position = await createDlmmPosition(svm, user, lbPair, lowerBinId);
@@ -320,6 +368,232 @@ describe("Zapin DLMM with initialize position", () => {
       remainingAccountInfo: { slices: [] },
     });
   });
+
+  it("Zapin dlmm expands lower side of position", async () => {
+    // position starts at active bin, zap in range goes below it
+    const positionLowerBinId = activeId.toNumber();
+    const position = await createDlmmPosition(
+      svm,
+      user,
+      lbPair,
+      positionLowerBinId,
+      10
+    );
+    const amountTokenA = new BN(LAMPORTS_PER_SOL);
+    const amountSwap = amountTokenA.divn(2);
+
+    const binArrays = getBinArrayAccountMetaByBinRange(
+      lbPair,
+      new BN(lowerBinId),
+      new BN(upperBinId)
+    );
+
+    await zapInDlmmFullFlow({
+      svm,
+      user,
+      lbPair,
+      position,
+      inputTokenMint: tokenXMint,
+      outputTokenMint: tokenYMint,
+      totalAmount: amountTokenA,
+      amountSwap,
+      strategy: StrategyType.Spot,
+      minDeltaId: -5,
+      maxDeltaId: 5,
+      binArrays,
+      remainingAccountInfo: { slices: [] },
+    });
+
+    const positionState = getDlmmPositionState(svm, position);
+    expect(positionState.lowerBinId).lt(positionLowerBinId);
+  });
+
+  it("Zapin dlmm for two positions", async () => {
+    // positions are next to each other and cover the zap in range
+    const firstPosition = await createDlmmPosition(
+      svm,
+      user,
+      lbPair,
+      activeId.toNumber() - 10,
+      10
+    );
+    const secondPosition = await createDlmmPosition(
+      svm,
+      user,
+      lbPair,
+      activeId.toNumber(),
+      11
+    );
+    const amountX = new BN(LAMPORTS_PER_SOL);
+    const amountY = new BN(LAMPORTS_PER_SOL);
+
+    const lbPairState = getLbPairState(svm, lbPair);
+    const [binArrayBitmapExtension] = deriveBinArrayBitmapExtension(lbPair);
+    const zapInTx = await zapInDlmmForMultiplePositions({
+      svm,
+      owner: user.publicKey,
+      lbPair,
+      positions: [firstPosition, secondPosition],
+      activeId: lbPairState.activeId,
+      minDeltaId: -10,
+      maxDeltaId: 10,
+      maxActiveBinSlippage: 10,
+      favorXInActiveId: false,
+      strategy: StrategyType.Spot,
+      remainingAccountInfo: { slices: [] },
+      binArrays: getBinArrayAccountMetaByBinRange(
+        lbPair,
+        activeId.subn(10),
+        activeId.addn(10)
+      ),
+      binArrayBitmapExtension,
+    });
+
+    const finalTx = new Transaction()
+      .add(SET_COMPUTE_UNIT_LIMIT_IX)
+      .add(await initializeLedgerAccount(user.publicKey))
+      .add(await setLedgerBalance(user.publicKey, amountX, true))
+      .add(await setLedgerBalance(user.publicKey, amountY, false))
+      .add(zapInTx)
+      .add(await closeLedgerAccount(user.publicKey));
+
+    finalTx.recentBlockhash = svm.latestBlockhash();
+    finalTx.sign(user);
+
+    const result = svm.sendTransaction(finalTx);
+    if (result instanceof FailedTransactionMetadata) {
+      console.log(result.err());
+      console.log(result.meta().logs());
+    }
+    expect(result).instanceOf(TransactionMetadata);
+
+    // every bin of both positions gets liquidity with Spot strategy
+    for (const position of [firstPosition, secondPosition]) {
+      const liquidities = getPositionTotalLiquidityAllBin(svm, position);
+      expect(liquidities.length).gt(0);
+      for (const [, liquidity] of liquidities) {
+        expect(liquidity).gt(0);
+      }
+    }
+
+    // positions keep their ranges
+    expect(getDlmmPositionState(svm, firstPosition).lowerBinId).eq(
+      activeId.toNumber() - 10
+    );
+    expect(getDlmmPositionState(svm, secondPosition).upperBinId).eq(
+      activeId.toNumber() + 10
+    );
+
+    const zapInResult = decodeZapInResult(result as TransactionMetadata);
+    expect(zapInResult.liquidityDelta.gtn(0)).to.be.true;
+    expect(zapInResult.swapInAmount.isZero()).to.be.true;
+    expect(
+      zapInResult.remainingAmountA.eq(
+        amountX.sub(zapInResult.amountADeposited)
+      )
+    ).to.be.true;
+    expect(
+      zapInResult.remainingAmountB.eq(
+        amountY.sub(zapInResult.amountBDeposited)
+      )
+    ).to.be.true;
+  });
+
+  it("Rebalance out of range dlmm position", async () => {
+    // position below active bin only holds token y
+    const positionLowerBinId = activeId.toNumber() - 30;
+    const position = await createDlmmPosition(
+      svm,
+      user,
+      lbPair,
+      positionLowerBinId,
+      10
+    );
+    await depositToDlmmPosition({
+      svm,
+      user,
+      lbPair,
+      position,
+      amountX: new BN(0),
+      amountY: new BN(LAMPORTS_PER_SOL),
+      minDeltaId: -30,
+      maxDeltaId: -21,
+    });
+
+    const result = await rebalanceDlmmPositionFlow({
+      svm,
+      user,
+      lbPair,
+      position,
+      minDeltaId: -5,
+      maxDeltaId: 5,
+      binArrays: getBinArrayAccountMetaByBinRange(
+        lbPair,
+        new BN(positionLowerBinId),
+        activeId.addn(10)
+      ),
+    });
+
+    expect(result.amountXWithdrawn.isZero()).to.be.true;
+    expect(result.amountYWithdrawn.gtn(0)).to.be.true;
+    // part of token y is swapped to token x for the ask side bins
+    expect(result.swapInAmount.gtn(0)).to.be.true;
+    expect(result.swapOutAmount.gtn(0)).to.be.true;
+    expect(result.amountXDeposited.gtn(0)).to.be.true;
+    expect(result.amountYDeposited.gtn(0)).to.be.true;
+
+    const lbPairState = getLbPairState(svm, lbPair);
+    const positionState = getDlmmPositionState(svm, position);
+    expect(positionState.lowerBinId).gt(positionLowerBinId + 9);
+    expect(positionState.lowerBinId).lte(lbPairState.activeId);
+    expect(positionState.upperBinId).gte(lbPairState.activeId);
+  });
+
+  it("Rebalance dlmm position without swap", async () => {
+    const positionLowerBinId = activeId.toNumber() - 30;
+    const position = await createDlmmPosition(
+      svm,
+      user,
+      lbPair,
+      positionLowerBinId,
+      10
+    );
+    await depositToDlmmPosition({
+      svm,
+      user,
+      lbPair,
+      position,
+      amountX: new BN(0),
+      amountY: new BN(LAMPORTS_PER_SOL),
+      minDeltaId: -30,
+      maxDeltaId: -21,
+    });
+
+    // new range is bid side only, withdrawn token y already matches it
+    const result = await rebalanceDlmmPositionFlow({
+      svm,
+      user,
+      lbPair,
+      position,
+      minDeltaId: -10,
+      maxDeltaId: -1,
+      binArrays: getBinArrayAccountMetaByBinRange(
+        lbPair,
+        new BN(positionLowerBinId),
+        activeId
+      ),
+    });
+
+    expect(result.swapInAmount.isZero()).to.be.true;
+    expect(result.swapOutAmount.isZero()).to.be.true;
+    expect(result.amountXDeposited.isZero()).to.be.true;
+    expect(result.amountYDeposited.gtn(0)).to.be.true;
+    expect(result.amountYDeposited.lte(result.amountYWithdrawn)).to.be.true;
+
+    const positionState = getDlmmPositionState(svm, position);
+    expect(positionState.lowerBinId).eq(activeId.toNumber() - 10);
+    expect(positionState.upperBinId).eq(activeId.toNumber() - 1);
+  });
 });
 
 async function zapInDlmmFullFlow(params: {
@@ -439,3 +713,112 @@ async function zapInDlmmFullFlow(params: {
   }
 
 }
+
+async function depositToDlmmPosition(params: {
+  svm: LiteSVM;
+  user: Keypair;
+  lbPair: PublicKey;
+  position: PublicKey;
+  amountX: BN;
+  amountY: BN;
+  minDeltaId: number;
+  maxDeltaId: number;
+}) {
+  const {
+    svm,
+    user,
+    lbPair,
+    position,
+    amountX,
+    amountY,
+    minDeltaId,
+    maxDeltaId,
+  } = params;
+
+  const lbPairState = getLbPairState(svm, lbPair);
+  const [binArrayBitmapExtension] = deriveBinArrayBitmapExtension(lbPair);
+  const zapInTx = await zapInDlmmforInitializedPosition({
+    svm,
+    owner: user.publicKey,
+    lbPair,
+    position,
+    activeId: lbPairState.activeId,
+    minDeltaId,
+    maxDeltaId,
+    maxActiveBinSlippage: 10,
+    favorXInActiveId: false,
+    strategy: StrategyType.Spot,
+    remainingAccountInfo: { slices: [] },
+    binArrays: getBinArrayAccountMetaByBinRange(
+      lbPair,
+      new BN(lbPairState.activeId + minDeltaId),
+      new BN(lbPairState.activeId + maxDeltaId)
+    ),
+    binArrayBitmapExtension,
+  });
+
+  const finalTx = new Transaction()
+    .add(SET_COMPUTE_UNIT_LIMIT_IX)
+    .add(await initializeLedgerAccount(user.publicKey))
+    .add(await setLedgerBalance(user.publicKey, amountX, true))
+    .add(await setLedgerBalance(user.publicKey, amountY, false))
+    .add(zapInTx)
+    .add(await closeLedgerAccount(user.publicKey));
+
+  finalTx.recentBlockhash = svm.latestBlockhash();
+  finalTx.sign(user);
+
+  const result = svm.sendTransaction(finalTx);
+  if (result instanceof FailedTransactionMetadata) {
+    console.log(result.err());
+    console.log(result.meta().logs());
+  }
+  expect(result).instanceOf(TransactionMetadata);
+}
+
+async function rebalanceDlmmPositionFlow(params: {
+  svm: LiteSVM;
+  user: Keypair;
+  lbPair: PublicKey;
+  position: PublicKey;
+  minDeltaId: number;
+  maxDeltaId: number;
+  binArrays: AccountMeta[];
+}) {
+  const { svm, user, lbPair, position, minDeltaId, maxDeltaId, binArrays } =
+    params;
+
+  const lbPairState = getLbPairState(svm, lbPair);
+  const [binArrayBitmapExtension] = deriveBinArrayBitmapExtension(lbPair);
+  const rebalanceTx = await rebalanceDlmmPosition({
+    svm,
+    owner: user.publicKey,
+    lbPair,
+    position,
+    activeId: lbPairState.activeId,
+    minDeltaId,
+    maxDeltaId,
+    maxActiveBinSlippage: 10,
+    favorXInActiveId: false,
+    strategy: StrategyType.Spot,
+    remainingAccountInfo: { slices: [] },
+    binArrays,
+    binArrayBitmapExtension,
+  });
+
+  const finalTx = new Transaction()
+    .add(SET_COMPUTE_UNIT_LIMIT_IX)
+    .add(rebalanceTx);
+
+  finalTx.recentBlockhash = svm.latestBlockhash();
+  finalTx.sign(user);
+
+  const result = svm.sendTransaction(finalTx);
+  if (result instanceof FailedTransactionMetadata) {
+    console.log(result.err());
+    console.log(result.meta().logs());
+  }
+  expect(result).instanceOf(TransactionMetadata);
+
+  return decodeRebalanceDlmmPositionResult(result as TransactionMetadata);
+}